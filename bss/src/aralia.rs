@@ -0,0 +1,181 @@
+//! Aralia fault trees.
+//!
+//! The text format of the Aralia benchmark suite (static subset). A model is a sequence of
+//! `;`-terminated statements:
+//!
+//! ```text
+//! // comments run to the end of the line (or /* ... */)
+//! TOP := G1 | (e3 & ~e4);
+//! G1  := @(2, [e1, e2, e3]);        // at least 2 of 3
+//! G2  := #(1, 2, [e1, e2, e3]);     // between 1 and 2 of 3
+//! p(e1) = 1.0e-3;
+//! ```
+//!
+//! Connectives, loosest first: `=>` / `<=>`, `|`, `^`, `&`, and the prefix `~`. A name is a
+//! gate if some statement defines it, otherwise a basic event. The top gate is the first gate
+//! no other gate references.
+
+use common::error::RelibError;
+
+use crate::bdd::BddMgr;
+use crate::fault_tree::{self, Definitions, FaultTree, Formula, Op, Pos, Token, Tokens};
+
+fn op(op: Op, args: Vec<Formula>, pos: Pos) -> Formula {
+    Formula::Op(op, args, pos)
+}
+
+fn formula(t: &mut Tokens) -> Result<Formula, String> {
+    let pos = t.pos();
+    let lhs = or(t)?;
+    if t.eat("=>") {
+        let rhs = or(t)?;
+        Ok(op(Op::Or, vec![op(Op::Not, vec![lhs], pos), rhs], pos))
+    } else if t.eat("<=>") {
+        let rhs = or(t)?;
+        Ok(op(Op::Not, vec![op(Op::Xor, vec![lhs, rhs], pos)], pos))
+    } else {
+        Ok(lhs)
+    }
+}
+
+fn binary(
+    t: &mut Tokens,
+    sym: &str,
+    kind: Op,
+    operand: fn(&mut Tokens) -> Result<Formula, String>,
+) -> Result<Formula, String> {
+    let pos = t.pos();
+    let mut args = vec![operand(t)?];
+    while t.eat(sym) {
+        args.push(operand(t)?);
+    }
+    Ok(if args.len() == 1 {
+        args.pop().unwrap()
+    } else {
+        op(kind, args, pos)
+    })
+}
+
+fn or(t: &mut Tokens) -> Result<Formula, String> {
+    binary(t, "|", Op::Or, xor)
+}
+
+fn xor(t: &mut Tokens) -> Result<Formula, String> {
+    binary(t, "^", Op::Xor, and)
+}
+
+fn and(t: &mut Tokens) -> Result<Formula, String> {
+    binary(t, "&", Op::And, unary)
+}
+
+fn unary(t: &mut Tokens) -> Result<Formula, String> {
+    let pos = t.pos();
+    if t.eat("~") {
+        Ok(op(Op::Not, vec![unary(t)?], pos))
+    } else if t.eat("(") {
+        let f = formula(t)?;
+        t.expect(")")?;
+        Ok(f)
+    } else if t.eat("@") {
+        t.expect("(")?;
+        let k = t.number::<usize>("a count")?;
+        t.expect(",")?;
+        let args = list(t)?;
+        t.expect(")")?;
+        Ok(op(Op::AtLeast(k), args, pos))
+    } else if t.eat("#") {
+        t.expect("(")?;
+        let lo = t.number::<usize>("a count")?;
+        t.expect(",")?;
+        let hi = t.number::<usize>("a count")?;
+        t.expect(",")?;
+        let args = list(t)?;
+        t.expect(")")?;
+        if lo > hi || hi > args.len() {
+            return Err(format!(
+                "{}: cardinality range #({}, {}, ...) is not within 0..={}",
+                pos,
+                lo,
+                hi,
+                args.len()
+            ));
+        }
+        // `#(0, ...)` has no lower bound and `#(.., n, ...)` no upper one.
+        let mut bounds = Vec::new();
+        if lo > 0 {
+            bounds.push(op(Op::AtLeast(lo), args.clone(), pos));
+        }
+        if hi < args.len() {
+            bounds.push(op(Op::Not, vec![op(Op::AtLeast(hi + 1), args, pos)], pos));
+        }
+        Ok(if bounds.is_empty() {
+            Formula::Const(true)
+        } else {
+            op(Op::And, bounds, pos)
+        })
+    } else {
+        match t.peek() {
+            Some(Token::Word(_)) => {
+                let (name, pos) = t.word("a name")?;
+                Ok(Formula::Event(name, pos))
+            }
+            _ => t.unexpected("a formula"),
+        }
+    }
+}
+
+fn list(t: &mut Tokens) -> Result<Vec<Formula>, String> {
+    t.expect("[")?;
+    let mut args = vec![formula(t)?];
+    while t.eat(",") {
+        args.push(formula(t)?);
+    }
+    t.expect("]")?;
+    Ok(args)
+}
+
+/// Imports an Aralia fault tree into `mgr`.
+///
+/// # Errors
+///
+/// Returns `Err` with the line and column on syntax errors, redefined or circular gates,
+/// probabilities outside `[0, 1]`, and a model without gates.
+///
+/// # Example
+///
+/// ```
+/// use bss::prelude::*;
+///
+/// let src = "TOP := G1 | e3;  G1 := e1 & e2;  p(e1) = 0.1; p(e2) = 0.2; p(e3) = 0.5;";
+/// let mut bss = BssMgr::new();
+/// let ft = bss.read_aralia(src).unwrap();
+/// assert_eq!(ft.top_gate, "TOP");
+/// assert!((ft.top.prob(&ft.probs, &[true]) - 0.51).abs() < 1e-12);
+/// ```
+pub fn read_aralia(mgr: &mut BddMgr, src: &str) -> Result<FaultTree, RelibError> {
+    let mut t = Tokens::new(src)?;
+    let mut defs = Definitions::default();
+    while t.peek().is_some() {
+        if t.peek() == Some(&Token::Word("p".to_string())) && t.peek_at(1) == Some(&Token::Punct("(")) {
+            t.next();
+            t.expect("(")?;
+            let (name, _) = t.word("an event name")?;
+            t.expect(")")?;
+            t.expect("=")?;
+            let pos = t.pos();
+            let p = t.number::<f64>("a probability")?;
+            if !(0.0..=1.0).contains(&p) {
+                return Err(RelibError::Invalid(format!("{}: probability {} of {} is not in [0, 1]", pos, p, name)));
+            }
+            defs.probs.insert(name, p);
+        } else {
+            let (name, pos) = t.word("a gate name or `p(`")?;
+            t.expect(":=")?;
+            let f = formula(&mut t)?;
+            defs.define_gate(&name, f, pos)?;
+        }
+        t.expect(";")?;
+    }
+    fault_tree::build(mgr, defs, None)
+        .map_err(RelibError::Invalid)
+}
@@ -1,15 +1,18 @@
 use bddcore::prelude::*;
+use crate::bdd_coherence;
 use crate::bdd_count;
 use crate::bdd_prob;
 use crate::bdd_dual;
 use crate::bdd_kofn;
+use crate::bdd_parse;
+use crate::bdd_ccf::{self, CcfGroup, CcfModel};
 use crate::bdd_path::BddPath;
 
 use std::collections::HashMap;
 use std::rc::Rc;
 use std::cell::RefCell;
 use std::rc::Weak;
-use std::ops::{Add, Sub, Mul};
+use std::ops::{Add, Div, Sub, Mul};
 
 /// Minimum live-node count at which automatic gc may fire.
 const GC_FLOOR: usize = 1 << 16;
@@ -56,6 +59,9 @@ pub struct BddMgr {
     bdd: Rc<RefCell<BddManager>>,
     gc: Rc<RefCell<GcState>>,
     vars: HashMap<String, BddNode>,
+    /// Declared common-cause failure groups, and the expansion each member name resolves to.
+    ccf: Vec<CcfGroup>,
+    ccf_members: HashMap<String, BddNode>,
 }
 
 /// A handle to a node in a [`BddMgr`]'s forest.
@@ -128,6 +134,8 @@ impl BddMgr {
                 floor: GC_FLOOR,
             })),
             vars: HashMap::default(),
+            ccf: Vec::new(),
+            ccf_members: HashMap::default(),
         }
     }
 
@@ -195,6 +203,9 @@ impl BddMgr {
         if let Some(node) = self.vars.get(var) {
             return node.clone();
         }
+        if let Some(node) = self.ccf_members.get(var) {
+            return node.clone();
+        }
         let level = self.vars.len();
         let node = {
             let mut bdd = self.bdd.borrow_mut();
@@ -208,6 +219,83 @@ impl BddMgr {
         bnode
     }
 
+    /// Declares a common-cause failure group over the basic events `members`.
+    ///
+    /// Creates the group's CCF basic events as new variables (`name[A]`, `name[A,B]`, …, see
+    /// [`bdd_ccf`](crate::bdd_ccf)) and from then on resolves each member name — in
+    /// [`defvar`](Self::defvar) and therefore in [`rpn`](Self::rpn) — to the OR of the events
+    /// it takes part in. Structure functions built afterwards carry the CCF events like any
+    /// other variable; quantify them with [`ccf_prob`](Self::ccf_prob).
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the model does not fit the group (see
+    /// [`CcfGroup::new`](crate::bdd_ccf::CcfGroup::new)), if the group name is taken, or if a
+    /// member or event name is already in use — members must be declared before their first
+    /// use, since diagrams built earlier would keep the plain variable.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bss::prelude::*;
+    /// use std::collections::HashMap;
+    ///
+    /// let mut mgr = BddMgr::new();
+    /// mgr.defccf("pumps", &["A", "B"], CcfModel::BetaFactor(0.1)).unwrap();
+    /// // Both pumps failing: (pumps[A] & pumps[B]) | pumps[A,B].
+    /// let top = mgr.rpn("A B &").unwrap();
+    ///
+    /// let mut pv = HashMap::new();
+    /// pv.insert("A".to_string(), 1.0e-2_f64);
+    /// pv.insert("B".to_string(), 1.0e-2);
+    /// let p = top.prob(&mgr.ccf_prob(&pv), &[true]);
+    /// assert!(p > 1.0e-3); // dominated by the CCF event, not by 1e-4
+    /// ```
+    pub fn defccf(&mut self, name: &str, members: &[&str], model: CcfModel) -> Result<(), RelibError> {
+        if self.ccf.iter().any(|g| g.name() == name) {
+            return Err(RelibError::Invalid(format!("CCF group {} is already defined", name)));
+        }
+        let group = CcfGroup::new(name, members, model)?;
+        let events = group.events();
+        for x in members
+            .iter()
+            .map(|x| x.to_string())
+            .chain(events.iter().map(|s| group.event_name(s)))
+        {
+            if self.vars.contains_key(&x) || self.ccf_members.contains_key(&x) {
+                return Err(RelibError::Invalid(format!("{} is already defined", x)));
+            }
+        }
+        let mut expansion: Vec<Vec<BddNode>> = vec![Vec::new(); members.len()];
+        for s in events.iter() {
+            let v = self.defvar(&group.event_name(s));
+            for &i in s {
+                expansion[i].push(v.clone());
+            }
+        }
+        for (x, nodes) in members.iter().zip(expansion.iter()) {
+            let node = self.or(nodes);
+            self.ccf_members.insert(x.to_string(), node);
+        }
+        self.ccf.push(group);
+        Ok(())
+    }
+
+    /// The declared common-cause failure groups, in declaration order.
+    pub fn ccf_groups(&self) -> &[CcfGroup] {
+        &self.ccf
+    }
+
+    /// `pv` (each member's total failure probability under its own name) extended with the
+    /// probabilities of every CCF basic event, ready for [`BddNode::prob`] / [`BddNode::bmeas`].
+    /// See [`bdd_ccf::ccf_prob`].
+    pub fn ccf_prob<T>(&self, pv: &HashMap<String, T>) -> HashMap<String, T>
+    where
+        T: Add<Output = T> + Mul<Output = T> + Clone + Copy + From<f64>,
+    {
+        bdd_ccf::ccf_prob(&self.ccf, pv)
+    }
+
     pub fn get_varorder(&self) -> Vec<String> {
         let bdd = self.bdd.borrow();
         let mut result = vec!["?".to_string(); self.vars.len()];
@@ -248,8 +336,9 @@ impl BddMgr {
     ///
     /// # Errors
     ///
-    /// Returns `Err` if `load(id)` names something never saved, if `save(id)` is used on
-    /// an empty stack, or if the expression does not reduce to exactly one node.
+    /// Returns `Err` if an operator lacks operands, if `load(id)` names something never
+    /// saved, if `save(id)` is used on an empty stack, or if the expression does not reduce
+    /// to exactly one node.
     ///
     /// # Example
     ///
@@ -262,6 +351,16 @@ impl BddMgr {
     /// assert!(!node.is_zero());
     /// ```
     pub fn rpn(&mut self, expr: &str) -> Result<BddNode, String> {
+        self.try_rpn(expr).map_err(String::from)
+    }
+
+    /// [`rpn`](Self::rpn) with the error as a [`RelibError`].
+    pub fn try_rpn(&mut self, expr: &str) -> Result<BddNode, RelibError> {
+        fn pop(stack: &mut Vec<NodeId>, token: &str) -> Result<NodeId, RelibError> {
+            stack
+                .pop()
+                .ok_or_else(|| RelibError::Invalid(format!("missing operand for {}", token)))
+        }
         let mut stack = Vec::new();
         let mut cache = HashMap::new();
         for token in expr.split_whitespace() {
@@ -276,32 +375,32 @@ impl BddMgr {
                 }
                 "&" => {
                     let mut bdd = self.bdd.borrow_mut();
-                    let right = stack.pop().unwrap();
-                    let left = stack.pop().unwrap();
+                    let right = pop(&mut stack, token)?;
+                    let left = pop(&mut stack, token)?;
                     stack.push(bdd.and(left, right));
                 }
                 "|" => {
                     let mut bdd = self.bdd.borrow_mut();
-                    let right = stack.pop().unwrap();
-                    let left = stack.pop().unwrap();
+                    let right = pop(&mut stack, token)?;
+                    let left = pop(&mut stack, token)?;
                     stack.push(bdd.or(left, right));
                 }
                 "^" => {
                     let mut bdd = self.bdd.borrow_mut();
-                    let right = stack.pop().unwrap();
-                    let left = stack.pop().unwrap();
+                    let right = pop(&mut stack, token)?;
+                    let left = pop(&mut stack, token)?;
                     stack.push(bdd.xor(left, right));
                 }
                 "~" => {
                     let mut bdd = self.bdd.borrow_mut();
-                    let node = stack.pop().unwrap();
+                    let node = pop(&mut stack, token)?;
                     stack.push(bdd.not(node));
                 }
                 "?" => {
                     let mut bdd = self.bdd.borrow_mut();
-                    let else_ = stack.pop().unwrap();
-                    let then = stack.pop().unwrap();
-                    let cond = stack.pop().unwrap();
+                    let else_ = pop(&mut stack, token)?;
+                    let then = pop(&mut stack, token)?;
+                    let cond = pop(&mut stack, token)?;
                     stack.push(bdd.ite(cond, then, else_));
                 }
                 _ if token.starts_with("save(") && token.ends_with(")") => {
@@ -309,7 +408,7 @@ impl BddMgr {
                     if let Some(node) = stack.last() {
                         cache.insert(name.to_string(), node.clone());
                     } else {
-                        return Err("Stack is empty for save operation".to_string());
+                        return Err(RelibError::Invalid("Stack is empty for save operation".to_string()));
                     }
                 }
                 _ if token.starts_with("load(") && token.ends_with(")") => {
@@ -317,7 +416,7 @@ impl BddMgr {
                     if let Some(node) = cache.get(name) {
                         stack.push(node.clone());
                     } else {
-                        return Err(format!("No cached value for {}", name));
+                        return Err(RelibError::Invalid(format!("No cached value for {}", name)));
                     }
                 }
                 _ => {
@@ -329,10 +428,40 @@ impl BddMgr {
         if stack.len() == 1 {
             return Ok(self.wrap(stack.pop().unwrap()));
         } else {
-            return Err("Invalid expression".to_string());
+            return Err(RelibError::Invalid("Invalid expression".to_string()));
         }
     }
 
+    /// Builds a BDD from an infix expression: `&`, `|`, `^`, `~`, parentheses,
+    /// `atleast(k, …)`, `ite(c, t, e)`, `0`/`1`/`true`/`false`, `"quoted"` names and
+    /// `let x = …;` bindings (grammar in [`bdd_parse`]). Unknown names become variables,
+    /// as in [`rpn`](Self::rpn).
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`RelibError::Parse`] with the byte span of the offending text for anything
+    /// outside the grammar, e.g. an unknown function, a missing operand or an unbalanced
+    /// parenthesis.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bss::prelude::*;
+    ///
+    /// let mut mgr = BddMgr::new();
+    /// let f = mgr.parse("let g = atleast(2, A, B, C); g | (D & ~\"E-1\")").unwrap();
+    /// let h = mgr.rpn("A B & A C & | B C & | D E-1 ~ & |").unwrap();
+    /// assert_eq!(f.get_id(), h.get_id());
+    ///
+    /// match mgr.parse("A & min(B, C)") {
+    ///     Err(RelibError::Parse(err)) => assert_eq!(err.span, 4..7),
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    pub fn parse(&mut self, expr: &str) -> Result<BddNode, RelibError> {
+        bdd_parse::parse(self, expr).map_err(RelibError::Parse)
+    }
+
     pub fn and(&self, nodes: &[BddNode]) -> BddNode {
         let ids = nodes.iter().map(|x| x.node).collect::<Vec<NodeId>>();
         let result = bdd_kofn::and(&mut self.bdd.borrow_mut(), &ids);
@@ -351,6 +480,38 @@ impl BddMgr {
         self.wrap(result)
     }
 
+    /// `Σ weights[i]·nodes[i] >= threshold` with integer weights (see
+    /// [`bdd_kofn::weighted_threshold`]); `kofn(k, nodes)` is the unit-weight case.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `weights` and `nodes` differ in length.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bss::prelude::*;
+    ///
+    /// let mut mgr = BddMgr::new();
+    /// let xs = ["A", "B", "C"].map(|x| mgr.defvar(x));
+    /// let f = mgr.weighted_threshold(&[3, 2, 1], 4, &xs);
+    /// let g = mgr.rpn("A B & A C & |").unwrap();
+    /// assert_eq!(f.get_id(), g.get_id());
+    /// ```
+    pub fn weighted_threshold(&self, weights: &[i64], threshold: i64, nodes: &[BddNode]) -> BddNode {
+        let ids = nodes.iter().map(|x| x.node).collect::<Vec<NodeId>>();
+        let result = bdd_kofn::weighted_threshold(&mut self.bdd.borrow_mut(), weights, threshold, &ids);
+        self.wrap(result)
+    }
+
+    /// Some `k` consecutive `nodes` are all true; with `circular` the last node is adjacent
+    /// to the first (see [`bdd_kofn::consecutive_kofn`]).
+    pub fn consecutive_kofn(&self, k: usize, nodes: &[BddNode], circular: bool) -> BddNode {
+        let ids = nodes.iter().map(|x| x.node).collect::<Vec<NodeId>>();
+        let result = bdd_kofn::consecutive_kofn(&mut self.bdd.borrow_mut(), k, &ids, circular);
+        self.wrap(result)
+    }
+
     pub fn clear_cache(&mut self) {
         self.bdd.borrow_mut().clear_cache();
     }
@@ -358,7 +519,12 @@ impl BddMgr {
 
 impl BddNode {
     pub fn get_mgr(&self) -> Rc<RefCell<BddManager>> {
-        self.parent.upgrade().unwrap()
+        self.try_get_mgr().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`get_mgr`](Self::get_mgr): `Err` if the manager has been dropped.
+    pub fn try_get_mgr(&self) -> Result<Rc<RefCell<BddManager>>, RelibError> {
+        self.mgr_of(&[])
     }
 
     pub fn get_id(&self) -> NodeId {
@@ -366,14 +532,14 @@ impl BddNode {
     }
 
     pub fn get_header(&self) -> Option<HeaderId> {
-        let bddmgr = self.parent.upgrade().unwrap();
+        let bddmgr = self.get_mgr();
         let bdd = bddmgr.borrow();
         let node = bdd.get_node(&self.node)?;
         node.headerid()
     }
 
     pub fn get_level(&self) -> Option<Level> {
-        let bddmgr = self.parent.upgrade().unwrap();
+        let bddmgr = self.get_mgr();
         let bdd = bddmgr.borrow();
         let node = bdd.get_node(&self.node)?;
         let hid = node.headerid()?;
@@ -382,7 +548,7 @@ impl BddNode {
     }
 
     pub fn get_label(&self) -> Option<String> {
-        let bddmgr = self.parent.upgrade().unwrap();
+        let bddmgr = self.get_mgr();
         let bdd = bddmgr.borrow();
         let node = bdd.get_node(&self.node)?;
         let hid = node.headerid()?;
@@ -391,22 +557,26 @@ impl BddNode {
     }
 
     pub fn get_children(&self) -> Option<(BddNode, BddNode)> {
-        let bddmgr = self.parent.upgrade().unwrap();
+        self.try_get_children().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`get_children`](Self::get_children): `Err` if the manager has been dropped.
+    pub fn try_get_children(&self) -> Result<Option<(BddNode, BddNode)>, RelibError> {
+        let bddmgr = self.mgr_of(&[])?;
         let bdd = bddmgr.borrow();
-        let node = bdd.get_node(&self.node)?;
-        match node {
-            Node::Zero | Node::One | Node::Undet => None,
-            Node::NonTerminal(fnode) => {
+        Ok(match bdd.get_node(&self.node) {
+            Some(Node::NonTerminal(fnode)) => {
                 // A `bdd` borrow is held here, so pin only (no maybe_gc).
                 let f0 = BddNode::from_weak(self.parent.clone(), self.gc.clone(), fnode.edge(0));
                 let f1 = BddNode::from_weak(self.parent.clone(), self.gc.clone(), fnode.edge(1));
                 Some((f0, f1))
             }
-        }
+            _ => None,
+        })
     }
 
     pub fn is_zero(&self) -> bool {
-        let bddmgr = self.parent.upgrade().unwrap();
+        let bddmgr = self.get_mgr();
         let bdd = bddmgr.borrow();
         let node = bdd.get_node(&self.node).unwrap();
         match node {
@@ -416,7 +586,7 @@ impl BddNode {
     }
 
     pub fn is_one(&self) -> bool {
-        let bddmgr = self.parent.upgrade().unwrap();
+        let bddmgr = self.get_mgr();
         let bdd = bddmgr.borrow();
         let node = bdd.get_node(&self.node).unwrap();
         match node {
@@ -426,7 +596,7 @@ impl BddNode {
     }
 
     pub fn is_undet(&self) -> bool {
-        let bddmgr = self.parent.upgrade().unwrap();
+        let bddmgr = self.get_mgr();
         let bdd = bddmgr.borrow();
         let node = bdd.get_node(&self.node).unwrap();
         match node {
@@ -436,39 +606,90 @@ impl BddNode {
     }
 
     pub fn dot(&self) -> String {
-        let bdd = self.parent.upgrade().unwrap();
+        self.try_dot().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`dot`](Self::dot): `Err` if the manager has been dropped.
+    pub fn try_dot(&self) -> Result<String, RelibError> {
+        let bdd = self.mgr_of(&[])?;
         let result = bdd.borrow().dot_string(&self.node);
-        result
+        Ok(result)
     }
 
-    pub fn and(&self, other: &BddNode) -> BddNode {
-        let bdd = self.parent.upgrade().unwrap();
+    /// The manager of `self` and `others`.
+    fn mgr_of(&self, others: &[&BddNode]) -> Result<Rc<RefCell<BddManager>>, RelibError> {
+        let bdd = self.parent.upgrade().ok_or(RelibError::ManagerDropped)?;
+        for x in others {
+            if !x.parent.ptr_eq(&self.parent) {
+                return Err(if x.parent.strong_count() == 0 {
+                    RelibError::ManagerDropped
+                } else {
+                    RelibError::ManagerMismatch
+                });
+            }
+        }
+        Ok(bdd)
+    }
+
+    /// Fallible [`and`](Self::and): `Err` if the manager has been dropped or `other` belongs
+    /// to another manager.
+    pub fn try_and(&self, other: &BddNode) -> Result<BddNode, RelibError> {
+        let bdd = self.mgr_of(&[other])?;
         let result = bdd.borrow_mut().and(self.node, other.node);
-        self.rewrap(&bdd, result)
+        Ok(self.rewrap(&bdd, result))
     }
 
-    pub fn or(&self, other: &BddNode) -> BddNode {
-        let bdd = self.parent.upgrade().unwrap();
+    /// Fallible [`or`](Self::or); see [`try_and`](Self::try_and).
+    pub fn try_or(&self, other: &BddNode) -> Result<BddNode, RelibError> {
+        let bdd = self.mgr_of(&[other])?;
         let result = bdd.borrow_mut().or(self.node, other.node);
-        self.rewrap(&bdd, result)
+        Ok(self.rewrap(&bdd, result))
     }
 
-    pub fn xor(&self, other: &BddNode) -> BddNode {
-        let bdd = self.parent.upgrade().unwrap();
+    /// Fallible [`xor`](Self::xor); see [`try_and`](Self::try_and).
+    pub fn try_xor(&self, other: &BddNode) -> Result<BddNode, RelibError> {
+        let bdd = self.mgr_of(&[other])?;
         let result = bdd.borrow_mut().xor(self.node, other.node);
-        self.rewrap(&bdd, result)
+        Ok(self.rewrap(&bdd, result))
     }
 
-    pub fn not(&self) -> BddNode {
-        let bdd = self.parent.upgrade().unwrap();
+    /// Fallible [`not`](Self::not): `Err` if the manager has been dropped.
+    pub fn try_not(&self) -> Result<BddNode, RelibError> {
+        let bdd = self.mgr_of(&[])?;
         let result = bdd.borrow_mut().not(self.node);
-        self.rewrap(&bdd, result)
+        Ok(self.rewrap(&bdd, result))
     }
 
-    pub fn ite(&self, then: &BddNode, else_: &BddNode) -> BddNode {
-        let bdd = self.parent.upgrade().unwrap();
+    /// Fallible [`ite`](Self::ite); see [`try_and`](Self::try_and).
+    pub fn try_ite(&self, then: &BddNode, else_: &BddNode) -> Result<BddNode, RelibError> {
+        let bdd = self.mgr_of(&[then, else_])?;
         let result = bdd.borrow_mut().ite(self.node, then.node, else_.node);
-        self.rewrap(&bdd, result)
+        Ok(self.rewrap(&bdd, result))
+    }
+
+    /// Panicking form of [`try_and`](Self::try_and).
+    pub fn and(&self, other: &BddNode) -> BddNode {
+        self.try_and(other).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Panicking form of [`try_or`](Self::try_or).
+    pub fn or(&self, other: &BddNode) -> BddNode {
+        self.try_or(other).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Panicking form of [`try_xor`](Self::try_xor).
+    pub fn xor(&self, other: &BddNode) -> BddNode {
+        self.try_xor(other).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Panicking form of [`try_not`](Self::try_not).
+    pub fn not(&self) -> BddNode {
+        self.try_not().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Panicking form of [`try_ite`](Self::try_ite).
+    pub fn ite(&self, then: &BddNode, else_: &BddNode) -> BddNode {
+        self.try_ite(then, else_).unwrap_or_else(|e| panic!("{}", e))
     }
 
     pub fn eq(&self, other: &BddNode) -> bool {
@@ -479,23 +700,281 @@ impl BddNode {
     where
         T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialEq + From<f64>,
     {
-        let bdd = self.parent.upgrade().unwrap();
+        self.try_prob(pv, ss).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`prob`](Self::prob): `Err` if the manager has been dropped or an `Undet`
+    /// node is reachable, where `prob` panics.
+    pub fn try_prob<T>(&self, pv: &HashMap<String, T>, ss: &[bool]) -> Result<T, RelibError>
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialEq + From<f64>,
+    {
+        let bdd = self.mgr_of(&[])?;
+        let dd = bdd.borrow();
+        bdd_prob::check_determined(&dd, self.node)?;
         let mut cache = BddHashMap::default();
-        bdd_prob::prob(
-            &mut bdd.clone().borrow_mut(),
-            self.node,
-            &pv,
-            ss,
-            &mut cache,
-        )
+        Ok(bdd_prob::prob(&dd, self.node, pv, ss, &mut cache))
+    }
+
+    /// `(P(f ∈ ss), P(f ∉ ss))` with the complement summed from its own terminals instead
+    /// of computed as `1 − P` (see [`bdd_prob::prob_with_complement`]), so a reliability
+    /// close to 1 and the corresponding unreliability are both accurate. For very rare
+    /// events also use a more precise `T` ([`DoubleDouble`], [`LogProb`]).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bss::prelude::*;
+    /// use std::collections::HashMap;
+    ///
+    /// let mut mgr = BddMgr::new();
+    /// let xs = ["A", "B", "C", "D"].map(|x| mgr.defvar(x));
+    /// let failure = mgr.and(&xs);
+    /// let pv: HashMap<String, f64> = ["A", "B", "C", "D"].iter().map(|x| (x.to_string(), 1e-4)).collect();
+    ///
+    /// // Unreliability 1e-16: `1 - P(working)` is 10% off in f64, the direct sum is not.
+    /// let (working, failed) = failure.not().prob_with_complement(&pv, &[true]);
+    /// assert!(((1.0 - working) / 1e-16 - 1.0).abs() > 0.1);
+    /// assert!((failed / 1e-16 - 1.0).abs() < 1e-12);
+    /// ```
+    pub fn prob_with_complement<T>(&self, pv: &HashMap<String, T>, ss: &[bool]) -> (T, T)
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialEq + From<f64>,
+    {
+        self.try_prob_with_complement(pv, ss).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`prob_with_complement`](Self::prob_with_complement); see [`try_prob`](Self::try_prob).
+    pub fn try_prob_with_complement<T>(&self, pv: &HashMap<String, T>, ss: &[bool]) -> Result<(T, T), RelibError>
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialEq + From<f64>,
+    {
+        let bdd = self.mgr_of(&[])?;
+        let dd = bdd.borrow();
+        bdd_prob::check_determined(&dd, self.node)?;
+        let mut cache = BddHashMap::default();
+        Ok(bdd_prob::prob_with_complement(&dd, self.node, pv, ss, &mut cache))
+    }
+
+    /// Lower and upper bounds of [`prob`](Self::prob) from interval-valued variable
+    /// probabilities (see [`bdd_prob::prob_interval`]). The bounds are always sound and exact
+    /// for coherent functions; [`BssMgr::prob_interval`](crate::bss::BssMgr::prob_interval)
+    /// checks coherence first.
+    pub fn prob_interval(&self, pv: &HashMap<String, Interval>, ss: &[bool]) -> Interval {
+        self.try_prob_interval(pv, ss).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`prob_interval`](Self::prob_interval); see [`try_prob`](Self::try_prob).
+    pub fn try_prob_interval(&self, pv: &HashMap<String, Interval>, ss: &[bool]) -> Result<Interval, RelibError> {
+        let bdd = self.mgr_of(&[])?;
+        let dd = bdd.borrow();
+        bdd_prob::check_determined(&dd, self.node)?;
+        let mut cache = BddHashMap::default();
+        Ok(bdd_prob::prob_interval(&dd, self.node, pv, ss, &mut cache))
+    }
+
+    /// [`prob`](Self::prob) given observed variable values: returns
+    /// `(P(f ∈ ss, evidence), P(f ∈ ss | evidence))` without rebuilding the diagram (see
+    /// [`bdd_prob::prob_given`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the manager has been dropped, an `Undet` node is reachable, or an
+    /// observed variable has no entry in `pv` ([`RelibError::MissingProbability`]).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bss::prelude::*;
+    /// use std::collections::HashMap;
+    ///
+    /// let mut mgr = BddMgr::new();
+    /// mgr.defvar("V3");
+    /// mgr.defvar("P1");
+    /// let top = mgr.rpn("V3 P1 |").unwrap();
+    /// let pv = HashMap::from([("V3".to_string(), 0.1_f64), ("P1".to_string(), 0.2)]);
+    /// let evidence = HashMap::from([("V3".to_string(), false)]);
+    ///
+    /// let (joint, conditional) = top.prob_given(&pv, &[true], &evidence).unwrap();
+    /// assert!((conditional - 0.2).abs() < 1e-12);
+    /// assert!((joint - 0.9 * 0.2).abs() < 1e-12);
+    /// ```
+    pub fn prob_given<T>(
+        &self,
+        pv: &HashMap<String, T>,
+        ss: &[bool],
+        evidence: &HashMap<String, bool>,
+    ) -> Result<(T, T), RelibError>
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialEq + From<f64>,
+    {
+        let bdd = self.mgr_of(&[])?;
+        let dd = bdd.borrow();
+        bdd_prob::check_determined(&dd, self.node)?;
+        bdd_prob::prob_given(&dd, self.node, pv, ss, evidence)
+    }
+
+    /// [`prob`](Self::prob) for many parameter sets at once: the diagram is linearized into
+    /// a [`ProbTape`](bdd_prob::ProbTape) a single time and every parameter set is evaluated
+    /// against it, instead of re-walking the graph per set.
+    pub fn prob_batch<T>(&self, params: &[HashMap<String, T>], ss: &[bool]) -> Vec<T>
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialEq + From<f64>,
+    {
+        self.try_prob_batch(params, ss).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`prob_batch`](Self::prob_batch); see [`try_prob`](Self::try_prob).
+    pub fn try_prob_batch<T>(&self, params: &[HashMap<String, T>], ss: &[bool]) -> Result<Vec<T>, RelibError>
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialEq + From<f64>,
+    {
+        let bdd = self.mgr_of(&[])?;
+        let dd = bdd.borrow();
+        bdd_prob::check_determined(&dd, self.node)?;
+        Ok(bdd_prob::prob_batch(&dd, self.node, params, ss))
+    }
+
+    /// Columnar [`prob_batch`](Self::prob_batch): `params[k][level]` is the probability of
+    /// the variable at `level` (the `defvar` order, see [`BddMgr::get_varorder`]) in the
+    /// `k`-th parameter set. A level past the end of a row counts as probability 0, as a
+    /// variable missing from `pv` does in [`prob`](Self::prob).
+    pub fn prob_batch_levels<T>(&self, params: &[Vec<T>], ss: &[bool]) -> Vec<T>
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialEq + From<f64>,
+    {
+        self.try_prob_batch_levels(params, ss).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`prob_batch_levels`](Self::prob_batch_levels); see [`try_prob`](Self::try_prob).
+    pub fn try_prob_batch_levels<T>(&self, params: &[Vec<T>], ss: &[bool]) -> Result<Vec<T>, RelibError>
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialEq + From<f64>,
+    {
+        let bdd = self.mgr_of(&[])?;
+        let dd = bdd.borrow();
+        bdd_prob::check_determined(&dd, self.node)?;
+        Ok(bdd_prob::prob_batch_levels(&dd, self.node, params, ss))
     }
 
     pub fn bmeas<T>(&self, pv: &HashMap<String, T>, ss: &[bool]) -> HashMap<String, T>
     where
         T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialEq + From<f64>,
     {
-        let bdd = self.parent.upgrade().unwrap();
-        bdd_prob::bmeas(&mut bdd.clone().borrow_mut(), ss, self.node, &pv)
+        self.try_bmeas(pv, ss).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`bmeas`](Self::bmeas); see [`try_prob`](Self::try_prob).
+    pub fn try_bmeas<T>(&self, pv: &HashMap<String, T>, ss: &[bool]) -> Result<HashMap<String, T>, RelibError>
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialEq + From<f64>,
+    {
+        let bdd = self.mgr_of(&[])?;
+        let dd = bdd.borrow();
+        bdd_prob::check_determined(&dd, self.node)?;
+        Ok(bdd_prob::bmeas(&dd, ss, self.node, pv))
+    }
+
+    /// Posterior probability `P(x = 1 | f ∈ ss)` of every variable `x` of the diagram, in
+    /// one pass (see [`bdd_prob::posterior`]). For a fault tree, `ss = &[true]` conditions
+    /// on the top event: the result is the probability each basic event has occurred given
+    /// that the system failed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bss::prelude::*;
+    /// use std::collections::HashMap;
+    ///
+    /// let mut mgr = BddMgr::new();
+    /// mgr.defvar("A");
+    /// mgr.defvar("B");
+    /// let top = mgr.rpn("A B |").unwrap();
+    /// let pv = HashMap::from([("A".to_string(), 0.1_f64), ("B".to_string(), 0.2)]);
+    ///
+    /// let post = top.posterior(&pv, &[true]);
+    /// assert!((post["A"] - 0.1 / 0.28).abs() < 1e-12);
+    /// ```
+    pub fn posterior<T>(&self, pv: &HashMap<String, T>, ss: &[bool]) -> HashMap<String, T>
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Div<Output = T> + Clone + Copy + PartialEq + From<f64>,
+    {
+        self.try_posterior(pv, ss).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`posterior`](Self::posterior); see [`try_prob`](Self::try_prob).
+    pub fn try_posterior<T>(&self, pv: &HashMap<String, T>, ss: &[bool]) -> Result<HashMap<String, T>, RelibError>
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Div<Output = T> + Clone + Copy + PartialEq + From<f64>,
+    {
+        let bdd = self.mgr_of(&[])?;
+        let dd = bdd.borrow();
+        bdd_prob::check_determined(&dd, self.node)?;
+        Ok(bdd_prob::posterior(&dd, ss, self.node, pv))
+    }
+
+    /// Most probable explanation of `f ∈ ss`: the most probable assignment of the diagram's
+    /// variables under which `f` takes a value in `ss`, with its probability, or `None` if
+    /// there is none (see [`bdd_prob::mpe`]).
+    pub fn mpe<T>(&self, pv: &HashMap<String, T>, ss: &[bool]) -> Option<(T, HashMap<String, bool>)>
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Div<Output = T> + Clone + Copy + PartialOrd + From<f64>,
+    {
+        self.try_mpe(pv, ss).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`mpe`](Self::mpe); see [`try_prob`](Self::try_prob).
+    pub fn try_mpe<T>(&self, pv: &HashMap<String, T>, ss: &[bool]) -> Result<Option<(T, HashMap<String, bool>)>, RelibError>
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Div<Output = T> + Clone + Copy + PartialOrd + From<f64>,
+    {
+        let bdd = self.mgr_of(&[])?;
+        let dd = bdd.borrow();
+        bdd_prob::check_determined(&dd, self.node)?;
+        Ok(bdd_prob::mpe(&dd, ss, self.node, pv))
+    }
+
+    /// Whether the function is monotone (see [`bdd_coherence`]); if not, a variable and two
+    /// assignments differing only in it on which raising it turns the function from true
+    /// to false. This is why [`BssMgr::minpath`](crate::bss::BssMgr::minpath) returns `None`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bss::prelude::*;
+    ///
+    /// let mut mgr = BddMgr::new();
+    /// let f = mgr.parse("A & ~B").unwrap();
+    /// match f.check_coherence() {
+    ///     Coherence::Decreasing { var, low, high } => {
+    ///         assert_eq!(var, "B");
+    ///         assert_eq!((low["A"], low["B"], high["B"]), (true, false, true));
+    ///     }
+    ///     Coherence::Coherent => unreachable!(),
+    /// }
+    /// ```
+    pub fn check_coherence(&self) -> Coherence<bool> {
+        self.try_check_coherence().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`check_coherence`](Self::check_coherence): `Err` if the manager has been dropped.
+    pub fn try_check_coherence(&self) -> Result<Coherence<bool>, RelibError> {
+        let bdd = self.mgr_of(&[])?;
+        let mut dd = bdd.borrow_mut();
+        Ok(bdd_coherence::check_coherence(&mut dd, self.node))
+    }
+
+    /// The defined variables the function does not depend on (irrelevant components), in
+    /// variable order.
+    pub fn irrelevant(&self) -> Vec<String> {
+        self.try_irrelevant().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`irrelevant`](Self::irrelevant): `Err` if the manager has been dropped.
+    pub fn try_irrelevant(&self) -> Result<Vec<String>, RelibError> {
+        let bdd = self.mgr_of(&[])?;
+        let dd = bdd.borrow();
+        Ok(bdd_coherence::irrelevant(&dd, self.node))
     }
 
     /// The dual structure function `φ^D(x) = ¬φ(¬x)` (swap children + complement
@@ -506,19 +985,30 @@ impl BddNode {
     /// set families) live on [`BssMgr`](crate::bss::BssMgr), since they also need a
     /// [`ZddMgr`](crate::zdd::ZddMgr); `dual` is a pure BDD operation and stays here.
     pub fn dual(&self) -> BddNode {
-        let bdd = self.parent.upgrade().unwrap();
+        self.try_dual().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`dual`](Self::dual): `Err` if the manager has been dropped.
+    pub fn try_dual(&self) -> Result<BddNode, RelibError> {
+        let bdd = self.mgr_of(&[])?;
         let mut cache = BddHashMap::default();
         let result = {
             let mut mgr = bdd.borrow_mut();
             bdd_dual::dual(&mut mgr, self.node, &mut cache)
         };
-        self.rewrap(&bdd, result)
+        Ok(self.rewrap(&bdd, result))
     }
 
     pub fn bdd_count(&self, ss: &[bool]) -> u64 {
-        let bdd = self.parent.upgrade().unwrap();
+        self.try_bdd_count(ss).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`bdd_count`](Self::bdd_count): `Err` if the manager has been dropped.
+    pub fn try_bdd_count(&self, ss: &[bool]) -> Result<u64, RelibError> {
+        let bdd = self.mgr_of(&[])?;
         let mut cache = BddHashMap::default();
-        bdd_count::bdd_count(&mut bdd.clone().borrow_mut(), ss, self.node, &mut cache)
+        let result = bdd_count::bdd_count(&bdd.borrow(), ss, self.node, &mut cache);
+        Ok(result)
     }
 
     pub fn bdd_extract(&self, ss: &[bool]) -> BddPath {
@@ -526,11 +1016,16 @@ impl BddNode {
     }
 
     pub fn size(&self) -> (u64, u64, u64) {
-        let bddmgr = self.parent.upgrade().unwrap();
+        self.try_size().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`size`](Self::size): `Err` if the manager has been dropped.
+    pub fn try_size(&self) -> Result<(u64, u64, u64), RelibError> {
+        let bddmgr = self.mgr_of(&[])?;
         let bdd = bddmgr.borrow();
         let mut cache = BddHashSet::default();
         let (nn, nv, ne) = bdd_count::node_count(&bdd, self.node, &mut cache);
-        (nn, nv, ne-1)
+        Ok((nn, nv, ne - 1))
     }
 }
 
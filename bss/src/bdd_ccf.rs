@@ -0,0 +1,215 @@
+//! Common-cause failure (CCF) groups for binary-state structure functions.
+//!
+//! A CCF group is a set of `m` redundant basic events that are not failing independently.
+//! Each member is expanded into the OR of the **CCF basic events** it takes part in: one per
+//! subset `S` of the group that contains it, named `group[A,B,…]`. The singleton subset
+//! `group[A]` is the independent part of `A`. [`BddMgr::defccf`](crate::bdd::BddMgr::defccf)
+//! creates these events as ordinary variables (via `defvar`), so the expanded structure
+//! function is a plain BDD and `prob`, `bmeas` and `minpath`/`mincut` see the CCF events
+//! directly; [`ccf_prob`] supplies their probabilities.
+//!
+//! The parametric models give the probability `Q_k` of a basic event for one *specific*
+//! subset of size `k`, as a fraction of the member's total failure probability `Q_t`:
+//!
+//! | model | events | `Q_k / Q_t` |
+//! |---|---|---|
+//! | beta factor `β` | singletons + the whole group | `1 − β` (k = 1), `β` (k = m) |
+//! | alpha factor `α_1..α_m` | every subset | `k / C(m−1, k−1) · α_k / α_t`, `α_t = Σ_j j·α_j` (non-staggered testing) |
+//! | MGL `β, γ, δ, …` | every subset | `1 / C(m−1, k−1) · (ρ_1 ⋯ ρ_k)(1 − ρ_{k+1})`, `ρ_1 = 1`, `ρ_2 = β`, …, `ρ_{m+1} = 0` |
+//!
+//! All three keep the member's total failure probability: `Σ_k C(m−1, k−1) Q_k = Q_t`.
+
+use std::collections::HashMap;
+use std::ops::{Add, Mul};
+
+use common::error::RelibError;
+
+/// Upper bound on the group size: the alpha-factor and MGL models create `2^m − 1` events.
+const MAX_GROUP_SIZE: usize = 16;
+
+/// Parametric CCF model of a group.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CcfModel {
+    /// Beta-factor model: a fraction `β` of each member's failures fails the whole group.
+    BetaFactor(f64),
+    /// Alpha-factor model with `α_1, …, α_m` (one per failure multiplicity).
+    AlphaFactor(Vec<f64>),
+    /// Multiple Greek Letter model with `β, γ, δ, …` (`m − 1` parameters).
+    Mgl(Vec<f64>),
+}
+
+/// A declared CCF group: its name, members (in declaration order) and model.
+#[derive(Debug, Clone)]
+pub struct CcfGroup {
+    name: String,
+    members: Vec<String>,
+    model: CcfModel,
+}
+
+fn binom(n: usize, k: usize) -> f64 {
+    let k = k.min(n - k);
+    let mut r = 1.0;
+    for i in 0..k {
+        r = r * (n - i) as f64 / (i + 1) as f64;
+    }
+    r
+}
+
+impl CcfGroup {
+    /// Validates the model against the group size.
+    pub fn new(name: &str, members: &[&str], model: CcfModel) -> Result<Self, RelibError> {
+        let m = members.len();
+        if m < 2 {
+            return Err(RelibError::Invalid(format!("CCF group {} needs at least two members", name)));
+        }
+        if m > MAX_GROUP_SIZE {
+            return Err(RelibError::Invalid(format!(
+                "CCF group {} has {} members (at most {} supported)",
+                name, m, MAX_GROUP_SIZE
+            )));
+        }
+        for (i, x) in members.iter().enumerate() {
+            if members[..i].contains(x) {
+                return Err(RelibError::Invalid(format!("CCF group {} lists {} twice", name, x)));
+            }
+        }
+        let in_unit = |x: f64| (0.0..=1.0).contains(&x);
+        match &model {
+            CcfModel::BetaFactor(beta) => {
+                if !in_unit(*beta) {
+                    return Err(RelibError::Invalid(format!("CCF group {}: beta factor must be in [0, 1]", name)));
+                }
+            }
+            CcfModel::AlphaFactor(alpha) => {
+                if alpha.len() != m {
+                    return Err(RelibError::Invalid(format!(
+                        "CCF group {}: expected {} alpha factors, got {}",
+                        name, m, alpha.len()
+                    )));
+                }
+                if alpha.iter().any(|&a| a < 0.0) || alpha.iter().all(|&a| a == 0.0) {
+                    return Err(RelibError::Invalid(format!(
+                        "CCF group {}: alpha factors must be non-negative and not all zero",
+                        name
+                    )));
+                }
+            }
+            CcfModel::Mgl(rho) => {
+                if rho.len() != m - 1 {
+                    return Err(RelibError::Invalid(format!(
+                        "CCF group {}: expected {} MGL parameters, got {}",
+                        name, m - 1, rho.len()
+                    )));
+                }
+                if !rho.iter().all(|&x| in_unit(x)) {
+                    return Err(RelibError::Invalid(format!("CCF group {}: MGL parameters must be in [0, 1]", name)));
+                }
+            }
+        }
+        Ok(CcfGroup {
+            name: name.to_string(),
+            members: members.iter().map(|x| x.to_string()).collect(),
+            model,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn members(&self) -> &[String] {
+        &self.members
+    }
+
+    pub fn model(&self) -> &CcfModel {
+        &self.model
+    }
+
+    /// The subsets of the group that get a CCF basic event, as member indices, ordered by
+    /// size and then lexicographically (singletons first).
+    pub fn events(&self) -> Vec<Vec<usize>> {
+        let m = self.members.len();
+        let mut result: Vec<Vec<usize>> = (1u32..(1 << m))
+            .map(|mask| (0..m).filter(|&i| (mask >> i) & 1 == 1).collect::<Vec<_>>())
+            .filter(|s| match self.model {
+                CcfModel::BetaFactor(_) => s.len() == 1 || s.len() == m,
+                _ => true,
+            })
+            .collect();
+        result.sort_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+        result
+    }
+
+    /// Variable name of the CCF basic event for `subset`: `group[A,B,…]`.
+    pub fn event_name(&self, subset: &[usize]) -> String {
+        let names: Vec<&str> = subset.iter().map(|&i| self.members[i].as_str()).collect();
+        format!("{}[{}]", self.name, names.join(","))
+    }
+
+    /// `Q_k / Q_t`: the probability of the basic event for one specific subset of size `k`,
+    /// relative to a member's total failure probability.
+    pub fn factor(&self, k: usize) -> f64 {
+        let m = self.members.len();
+        match &self.model {
+            CcfModel::BetaFactor(beta) => {
+                if k == 1 {
+                    1.0 - beta
+                } else if k == m {
+                    *beta
+                } else {
+                    0.0
+                }
+            }
+            CcfModel::AlphaFactor(alpha) => {
+                let alpha_t: f64 = alpha.iter().enumerate().map(|(j, a)| (j + 1) as f64 * a).sum();
+                k as f64 / binom(m - 1, k - 1) * alpha[k - 1] / alpha_t
+            }
+            CcfModel::Mgl(rho) => {
+                // rho[0] = β = ρ_2, so ρ_i = rho[i - 2] for i = 2..=m, ρ_1 = 1, ρ_{m+1} = 0.
+                let r = |i: usize| {
+                    if i == 1 {
+                        1.0
+                    } else if i > m {
+                        0.0
+                    } else {
+                        rho[i - 2]
+                    }
+                };
+                let prod: f64 = (1..=k).map(r).product();
+                prod * (1.0 - r(k + 1)) / binom(m - 1, k - 1)
+            }
+        }
+    }
+}
+
+/// Probabilities of the CCF basic events of `groups`, added to (a copy of) `pv`.
+///
+/// `pv` gives each member's **total** failure probability `Q_t` under the member's own name.
+/// The independent part `group[A]` takes `Q_1 / Q_t` of member `A`'s own `Q_t`; an event
+/// shared by several members takes its factor of the group's mean `Q_t` (the models assume a
+/// symmetric group, so the two coincide when the members are alike). As in `prob`, a member
+/// without a probability is taken as `0`. The result is meant for
+/// [`BddNode::prob`](crate::bdd::BddNode::prob) / [`bmeas`](crate::bdd::BddNode::bmeas) on a
+/// structure function built after the groups were declared.
+pub fn ccf_prob<T>(groups: &[CcfGroup], pv: &HashMap<String, T>) -> HashMap<String, T>
+where
+    T: Add<Output = T> + Mul<Output = T> + Clone + Copy + From<f64>,
+{
+    let mut result = pv.clone();
+    for g in groups {
+        let qt: Vec<T> = g
+            .members
+            .iter()
+            .map(|x| *pv.get(x).unwrap_or(&T::from(0.0)))
+            .collect();
+        let mean = qt
+            .iter()
+            .fold(T::from(0.0), |acc, &q| acc + q)
+            * T::from(1.0 / qt.len() as f64);
+        for s in g.events() {
+            let base = if s.len() == 1 { qt[s[0]] } else { mean };
+            result.insert(g.event_name(&s), T::from(g.factor(s.len())) * base);
+        }
+    }
+    result
+}
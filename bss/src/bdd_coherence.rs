@@ -0,0 +1,123 @@
+//! Coherence (monotonicity) and relevance checks with counterexamples.
+//!
+//! [`check_coherence`] applies the per-node test of the minsol — the low cofactor of every
+//! node implies the high one — to each reachable node. At the first node that fails it, a
+//! path from the root fixes the variables above, and a descent through the two cofactors
+//! fixes the variables below to an assignment where the low cofactor is true and the high
+//! one false. [`irrelevant`] lists the defined variables that label no node of the reduced
+//! diagram, i.e. that the function does not depend on.
+
+use std::collections::HashMap;
+
+use bddcore::prelude::*;
+
+use crate::bdd_minsol::ascends;
+
+/// [`Coherence::Coherent`] if `node` is nondecreasing in every variable, otherwise a
+/// counterexample.
+pub fn check_coherence(dd: &mut BddManager, node: NodeId) -> Coherence<bool> {
+    let mut path = Vec::new();
+    let mut visited = BddHashSet::default();
+    let Some((var, assignment)) = search(dd, node, &mut path, &mut visited) else {
+        return Coherence::Coherent;
+    };
+    let mut low: HashMap<String, bool> = diagram_labels(dd, node).into_iter().map(|x| (x, false)).collect();
+    low.extend(assignment);
+    let mut high = low.clone();
+    low.insert(var.clone(), false);
+    high.insert(var.clone(), true);
+    Coherence::Decreasing { var, low, high }
+}
+
+/// The defined variables `node` does not depend on, in variable order (lowest level first).
+pub fn irrelevant(dd: &BddManager, node: NodeId) -> Vec<String> {
+    let used = diagram_labels(dd, node);
+    let (nheaders, _, _) = dd.size();
+    let mut headers: Vec<_> = (0..nheaders).filter_map(|h| dd.get_header(&h)).collect();
+    headers.sort_by_key(|h| h.level());
+    headers
+        .into_iter()
+        .map(|h| h.label().to_string())
+        .filter(|x| !used.contains(x))
+        .collect()
+}
+
+fn diagram_labels(dd: &BddManager, node: NodeId) -> Vec<String> {
+    let mut labels = Vec::new();
+    let mut visited = BddHashSet::default();
+    let mut stack = vec![node];
+    while let Some(f) = stack.pop() {
+        if !visited.insert(f) {
+            continue;
+        }
+        if let Node::NonTerminal(fnode) = dd.get_node(&f).unwrap() {
+            let label = dd.label(&f).unwrap().to_string();
+            if !labels.contains(&label) {
+                labels.push(label);
+            }
+            stack.push(fnode.edge(0));
+            stack.push(fnode.edge(1));
+        }
+    }
+    labels
+}
+
+fn search(
+    dd: &mut BddManager,
+    f: NodeId,
+    path: &mut Vec<(String, bool)>,
+    visited: &mut BddHashSet<NodeId>,
+) -> Option<(String, Vec<(String, bool)>)> {
+    if !visited.insert(f) {
+        return None;
+    }
+    let Node::NonTerminal(fnode) = dd.get_node(&f).unwrap() else {
+        return None;
+    };
+    let (f0, f1) = (fnode.edge(0), fnode.edge(1));
+    let label = dd.label(&f).unwrap().to_string();
+    if !ascends(dd, f0, f1) {
+        let mut below = Vec::new();
+        greater(dd, f0, f1, &mut below);
+        let mut assignment = path.clone();
+        assignment.extend(below);
+        return Some((label, assignment));
+    }
+    for (k, c) in [(false, f0), (true, f1)] {
+        path.push((label.clone(), k));
+        let found = search(dd, c, path, visited);
+        path.pop();
+        if found.is_some() {
+            return found;
+        }
+    }
+    None
+}
+
+/// Descends `f` and `g` together to an assignment on which `f` is true and `g` false,
+/// pushing its values to `out`; `false` (with `out` untouched) if `f ⇒ g`.
+fn greater(dd: &mut BddManager, f: NodeId, g: NodeId, out: &mut Vec<(String, bool)>) -> bool {
+    if ascends(dd, f, g) {
+        return false;
+    }
+    let top = match (dd.level(&f), dd.level(&g)) {
+        (None, None) => return true,
+        (lf, lg) if lf >= lg => f,
+        _ => g,
+    };
+    let level = dd.level(&top);
+    let label = dd.label(&top).unwrap().to_string();
+    let cofactors = |dd: &BddManager, x: NodeId| match dd.get_node(&x).unwrap() {
+        Node::NonTerminal(xnode) if dd.level(&x) == level => (xnode.edge(0), xnode.edge(1)),
+        _ => (x, x),
+    };
+    let (f0, f1) = cofactors(dd, f);
+    let (g0, g1) = cofactors(dd, g);
+    for (k, fk, gk) in [(false, f0, g0), (true, f1, g1)] {
+        if greater(dd, fk, gk, out) {
+            out.push((label, k));
+            return true;
+        }
+    }
+    false
+}
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use bddcore::prelude::*;
 
 pub fn and(
@@ -64,3 +66,127 @@ fn kofn_rec(
     memo[idx] = Some(res);
     res
 }
+
+/// BDD for the pseudo-Boolean constraint `Σ weights[i]·node[i] >= threshold`.
+///
+/// Shannon expansion on `node[start]` with memoization on `(start, remaining threshold)`.
+/// A subproblem is decided without expanding further as soon as the weights left cannot
+/// reach the threshold (→ 0) or cannot fall below it (→ 1), so with non-negative weights
+/// there are at most `n · threshold` distinct subproblems. Negative weights are allowed;
+/// the partial sums are taken in `i128`, so no choice of `i64` weights overflows.
+///
+/// # Panics
+///
+/// Panics if `weights` and `node` differ in length.
+pub fn weighted_threshold(
+    dd: &mut BddManager,
+    weights: &[i64],
+    threshold: i64,
+    node: &[NodeId]
+) -> NodeId {
+    assert_eq!(weights.len(), node.len(), "one weight per node");
+    // Bounds (min, max) of the weighted sum over node[i..].
+    let mut bounds = vec![(0, 0); node.len() + 1];
+    for i in (0..node.len()).rev() {
+        let (lo, hi) = bounds[i + 1];
+        let w = weights[i] as i128;
+        bounds[i] = (lo + w.min(0), hi + w.max(0));
+    }
+    let mut memo = HashMap::new();
+    weighted_rec(dd, node, weights, &bounds, threshold as i128, 0, &mut memo)
+}
+
+fn weighted_rec(
+    dd: &mut BddManager,
+    node: &[NodeId],
+    weights: &[i64],
+    bounds: &[(i128, i128)],
+    t: i128,
+    start: usize,
+    memo: &mut HashMap<(usize, i128), NodeId>,
+) -> NodeId {
+    let (lo, hi) = bounds[start];
+    if lo >= t {
+        return dd.one();
+    }
+    if hi < t {
+        return dd.zero();
+    }
+    if let Some(&v) = memo.get(&(start, t)) {
+        return v;
+    }
+    let then = weighted_rec(dd, node, weights, bounds, t - weights[start] as i128, start + 1, memo);
+    let else_ = weighted_rec(dd, node, weights, bounds, t, start + 1, memo);
+    let res = dd.ite(node[start], then, else_);
+    memo.insert((start, t), res);
+    res
+}
+
+/// BDD for "some `k` consecutive nodes are all true" (a consecutive-k-out-of-n system).
+/// With `circular`, `node` is a ring and a run may wrap from the last node to the first.
+///
+/// Linear: Shannon expansion along `node` with memoization on `(start, run)`, where `run`
+/// is the length of the current run of true nodes (below `k`), so O(n·k) `ite` calls.
+/// Circular: the leading run `p` is expanded first; the rest is the linear recursion in
+/// which a trailing run `r` at the end also succeeds when `p + r >= k`, so O(n·k²).
+pub fn consecutive_kofn(
+    dd: &mut BddManager,
+    k: usize,
+    node: &[NodeId],
+    circular: bool
+) -> NodeId {
+    if k == 0 {
+        return dd.one();
+    }
+    let mut memo = HashMap::new();
+    if circular {
+        leading_rec(dd, node, k, 0, &mut memo)
+    } else {
+        consecutive_rec(dd, node, k, 0, 0, 0, &mut memo)
+    }
+}
+
+/// `node[..start]` are all true (the leading run of a ring, shorter than `k`).
+fn leading_rec(
+    dd: &mut BddManager,
+    node: &[NodeId],
+    k: usize,
+    start: usize,
+    memo: &mut HashMap<(usize, usize, usize), NodeId>,
+) -> NodeId {
+    if start == k {
+        return dd.one();
+    }
+    if start == node.len() {
+        return dd.zero(); // the whole ring is shorter than k
+    }
+    let then = leading_rec(dd, node, k, start + 1, memo);
+    let else_ = consecutive_rec(dd, node, k, start, start + 1, 0, memo);
+    dd.ite(node[start], then, else_)
+}
+
+/// `lead` is the leading run that wraps onto the trailing one (0 for a line).
+fn consecutive_rec(
+    dd: &mut BddManager,
+    node: &[NodeId],
+    k: usize,
+    lead: usize,
+    start: usize,
+    run: usize,
+    memo: &mut HashMap<(usize, usize, usize), NodeId>,
+) -> NodeId {
+    if run == k {
+        return dd.one();
+    }
+    if start == node.len() {
+        return if lead + run >= k { dd.one() } else { dd.zero() };
+    }
+    if let Some(&v) = memo.get(&(lead, start, run)) {
+        return v;
+    }
+    let then = consecutive_rec(dd, node, k, lead, start + 1, run + 1, memo);
+    let else_ = consecutive_rec(dd, node, k, lead, start + 1, 0, memo);
+    let res = dd.ite(node[start], then, else_);
+    memo.insert((lead, start, run), res);
+    res
+}
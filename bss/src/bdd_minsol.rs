@@ -33,9 +33,7 @@ pub fn minsol(
                 Some(low) => match minsol(dd, f1, cache1, cache2) {
                     None => None,
                     Some(high_src) => {
-                        // Monotone in this variable iff f0 ⇒ f1, i.e.
-                        // and(f0, f1) == f0 (canonical -> O(1) id compare).
-                        if dd.and(f0, f1) != f0 {
+                        if !ascends(dd, f0, f1) {
                             None
                         } else {
                             let high = without(dd, high_src, f0, cache2);
@@ -51,6 +49,12 @@ pub fn minsol(
     result
 }
 
+/// Whether a node with cofactors `(f0, f1)` is monotone in its variable: `f0 ⇒ f1`, i.e.
+/// `and(f0, f1) == f0` (canonical -> O(1) id compare).
+pub(crate) fn ascends(dd: &mut BddManager, f0: NodeId, f1: NodeId) -> bool {
+    dd.and(f0, f1) == f0
+}
+
 // enum BddStackValue<'a, 'b> {
 //     Bdd2(&'a bdd::BddNode, &'a bdd::BddNode),
 //     BddHeader((NodeId, NodeId), &'b dd::nodes::NodeHeader),
@@ -0,0 +1,168 @@
+//! Infix boolean expressions, the readable alternative to [`BddMgr::rpn`].
+//!
+//! ```text
+//! program := ("let" name "=" expr ";")* expr
+//! expr    := xor ("|" xor)*
+//! xor     := and ("^" and)*
+//! and     := unary ("&" unary)*
+//! unary   := "~" unary | atom
+//! atom    := "0" | "1" | "true" | "false" | name | "(" expr ")"
+//!          | "atleast" "(" k "," expr ("," expr)* ")"
+//!          | "ite" "(" expr "," expr "," expr ")"
+//! name    := identifier | "quoted identifier"
+//! ```
+//!
+//! A name is a `let` binding if one is in scope, otherwise a variable (created with
+//! `defvar` on first use). `let`, `true`, `false`, `atleast` and `ite` are keywords; quote
+//! them (`"ite"`) to use them as variable names. Unlike `rpn`, anything that is not part of
+//! the grammar — an unknown function such as `min(…)`, a stray operator, a missing operand —
+//! is an error carrying the span of the offending text.
+
+use std::collections::HashMap;
+
+use common::parse::{ParseError, Token, TokenStream};
+
+use crate::bdd::{BddMgr, BddNode};
+
+const PUNCTS: [&str; 9] = ["&", "|", "^", "~", "(", ")", ",", "=", ";"];
+const KEYWORDS: [&str; 5] = ["let", "true", "false", "atleast", "ite"];
+
+struct Parser<'a> {
+    mgr: &'a mut BddMgr,
+    t: TokenStream,
+    bindings: HashMap<String, BddNode>,
+}
+
+impl<'a> Parser<'a> {
+    fn program(&mut self) -> Result<BddNode, ParseError> {
+        while self.t.eat_ident("let") {
+            let (name, _) = self.t.name(&KEYWORDS)?;
+            self.t.expect("=")?;
+            let value = self.expr()?;
+            self.t.expect(";")?;
+            self.bindings.insert(name, value);
+        }
+        let result = self.expr()?;
+        if !self.t.is_end() {
+            return self.t.unexpected("an operator or end of input");
+        }
+        Ok(result)
+    }
+
+    fn expr(&mut self) -> Result<BddNode, ParseError> {
+        let mut x = self.xor()?;
+        while self.t.eat("|") {
+            x = x.or(&self.xor()?);
+        }
+        Ok(x)
+    }
+
+    fn xor(&mut self) -> Result<BddNode, ParseError> {
+        let mut x = self.and()?;
+        while self.t.eat("^") {
+            x = x.xor(&self.and()?);
+        }
+        Ok(x)
+    }
+
+    fn and(&mut self) -> Result<BddNode, ParseError> {
+        let mut x = self.unary()?;
+        while self.t.eat("&") {
+            x = x.and(&self.unary()?);
+        }
+        Ok(x)
+    }
+
+    fn unary(&mut self) -> Result<BddNode, ParseError> {
+        if self.t.eat("~") {
+            Ok(self.unary()?.not())
+        } else {
+            self.atom()
+        }
+    }
+
+    fn args(&mut self) -> Result<Vec<BddNode>, ParseError> {
+        let mut args = vec![self.expr()?];
+        while self.t.eat(",") {
+            args.push(self.expr()?);
+        }
+        self.t.expect(")")?;
+        Ok(args)
+    }
+
+    fn atom(&mut self) -> Result<BddNode, ParseError> {
+        let span = self.t.span();
+        match self.t.peek().cloned() {
+            Some(Token::Punct("(")) => {
+                self.t.bump();
+                let x = self.expr()?;
+                self.t.expect(")")?;
+                Ok(x)
+            }
+            Some(Token::Number(n)) => {
+                self.t.bump();
+                match n.as_str() {
+                    "0" => Ok(self.mgr.zero()),
+                    "1" => Ok(self.mgr.one()),
+                    _ => Err(ParseError::new(
+                        format!("`{}` is not a boolean constant (0 or 1)", n),
+                        span,
+                    )),
+                }
+            }
+            Some(Token::Ident(w)) if w == "true" || w == "false" => {
+                self.t.bump();
+                Ok(if w == "true" { self.mgr.one() } else { self.mgr.zero() })
+            }
+            Some(Token::Ident(w)) if w == "atleast" => {
+                self.t.bump();
+                self.t.expect("(")?;
+                let (k, k_span) = self.t.number::<usize>("a count")?;
+                self.t.expect(",")?;
+                let args = self.args()?;
+                if k == 0 || k > args.len() {
+                    return Err(ParseError::new(
+                        format!("`atleast` needs 1 <= k <= {}, found {}", args.len(), k),
+                        k_span,
+                    ));
+                }
+                Ok(self.mgr.kofn(k, &args))
+            }
+            Some(Token::Ident(w)) if w == "ite" => {
+                self.t.bump();
+                self.t.expect("(")?;
+                let args = self.args()?;
+                if args.len() != 3 {
+                    let span = span.start..self.t.last_end();
+                    return Err(ParseError::new(
+                        format!("`ite` takes 3 arguments, found {}", args.len()),
+                        span,
+                    ));
+                }
+                Ok(args[0].ite(&args[1], &args[2]))
+            }
+            Some(Token::Ident(w)) if matches!(self.t.peek_at(1), Some(Token::Punct("("))) => {
+                Err(ParseError::new(format!("unknown function `{}`", w), span))
+            }
+            Some(Token::Ident(_)) | Some(Token::Quoted(_)) => {
+                let (name, _) = self.t.name(&KEYWORDS)?;
+                match self.bindings.get(&name) {
+                    Some(x) => Ok(x.clone()),
+                    None => Ok(self.mgr.defvar(&name)),
+                }
+            }
+            _ => self.t.unexpected("an operand"),
+        }
+    }
+}
+
+/// Parses an infix expression (see the [module docs](self)) into a BDD of `mgr`.
+pub fn parse(mgr: &mut BddMgr, src: &str) -> Result<BddNode, ParseError> {
+    let t = TokenStream::new(src, &PUNCTS)?;
+    let mut p = Parser {
+        mgr,
+        t,
+        bindings: HashMap::new(),
+    };
+    p.program()
+}
@@ -1,6 +1,6 @@
 use bddcore::prelude::*;
-use std::collections::{HashMap, VecDeque};
-use std::ops::{Add, Sub, Mul};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::ops::{Add, Div, Mul, Sub};
 
 pub fn prob<T>(
     dd: &BddManager,
@@ -44,6 +44,293 @@ where
     result
 }
 
+/// `Err(Undetermined)` if an `Undet` node is reachable from `node`: the check the fallible
+/// `try_*` evaluations run before the passes of this module, which panic on `Undet`.
+pub fn check_determined(dd: &BddManager, node: NodeId) -> Result<(), RelibError> {
+    let undet = topological_sort(dd, node)
+        .iter()
+        .any(|f| matches!(dd.get_node(f), Some(Node::Undet)));
+    if undet {
+        Err(RelibError::Undetermined)
+    } else {
+        Ok(())
+    }
+}
+
+/// [`prob`] under hard evidence: `evidence` fixes variables to observed values. Returns
+/// `(joint, conditional)` = `(P(f ∈ ss, evidence), P(f ∈ ss | evidence))`.
+///
+/// The variables are independent, so conditioning only replaces the probability of each
+/// observed variable by `1` or `0` during the traversal, which restricts its nodes to the
+/// observed branch; the joint is the conditional times `P(evidence)`, the product of the
+/// observed values' priors. Evidence on a variable outside the diagram only enters
+/// `P(evidence)`.
+///
+/// # Errors
+///
+/// Returns [`RelibError::MissingProbability`] if an observed variable has no entry in `pv`:
+/// without a prior, `P(evidence)` is unknown.
+pub fn prob_given<T>(
+    dd: &BddManager,
+    node: NodeId,
+    pv: &HashMap<String, T>,
+    ss: &[bool],
+    evidence: &HashMap<String, bool>,
+) -> Result<(T, T), RelibError>
+where
+    T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialEq + From<f64>,
+{
+    let mut observed = pv.clone();
+    let mut pe = T::from(1.0);
+    for (x, &v) in evidence.iter() {
+        let p = *pv.get(x).ok_or_else(|| RelibError::MissingProbability(x.clone()))?;
+        pe = pe * if v { p } else { T::from(1.0) - p };
+        observed.insert(x.clone(), T::from(if v { 1.0 } else { 0.0 }));
+    }
+    let mut cache = BddHashMap::default();
+    let conditional = prob(dd, node, &observed, ss, &mut cache);
+    Ok((pe * conditional, conditional))
+}
+
+/// Bounds of [`prob`] when each variable's probability is only known to lie in an
+/// [`Interval`].
+///
+/// A node's probability `(1 − p)·low + p·high` is linear in `p`, so its lower (upper) bound
+/// takes the endpoint of `p`'s interval that minimizes (maximizes) it, given the bounds of
+/// the children. Choosing the endpoint node by node treats the occurrences of a variable in
+/// different nodes as independent, so the result always encloses the true range; it is
+/// exact when the choice is the same at every node of a variable, as for a coherent
+/// function. Plain interval arithmetic (`prob::<Interval>`) is also sound but looser, since
+/// it also decouples `p` from `1 − p`.
+pub fn prob_interval(
+    dd: &BddManager,
+    node: NodeId,
+    pv: &HashMap<String, Interval>,
+    ss: &[bool],
+    cache: &mut BddHashMap<NodeId, Interval>,
+) -> Interval {
+    if let Some(x) = cache.get(&node) {
+        return *x;
+    }
+    let result = match dd.get_node(&node).unwrap() {
+        Node::Zero => Interval::from(if ss.contains(&false) { 1.0 } else { 0.0 }),
+        Node::One => Interval::from(if ss.contains(&true) { 1.0 } else { 0.0 }),
+        Node::NonTerminal(fnode) => {
+            let x = dd.label(&node).unwrap();
+            let fp = *pv.get(x).unwrap_or(&Interval::from(0.0));
+            let low = prob_interval(dd, fnode.edge(0), pv, ss, cache);
+            let high = prob_interval(dd, fnode.edge(1), pv, ss, cache);
+            let bound = |l: f64, h: f64, lower: bool| {
+                let p = if (h >= l) == lower { fp.lo } else { fp.hi };
+                (1.0 - p) * l + p * h
+            };
+            Interval::new(bound(low.lo, high.lo, true), bound(low.hi, high.hi, false))
+        }
+        Node::Undet => panic!("Undetermined node"),
+    };
+    cache.insert(node, result);
+    result
+}
+
+/// `(P(f ∈ ss), P(f ∉ ss))`, each accumulated from its own terminals in one traversal.
+///
+/// `1 − prob(..)` cancels catastrophically when the probability is close to 1: a
+/// reliability of `1 − 1e-13` keeps only about three significant digits of the
+/// unreliability in `f64`. Here the complement is summed directly from the terminals outside
+/// `ss` (for a fault tree, the `Zero` terminal), so both are accurate to the precision of
+/// `T` — given that `1 − p` of each variable is; pass the smaller of the two probabilities
+/// per variable when it matters.
+pub fn prob_with_complement<T>(
+    dd: &BddManager,
+    node: NodeId,
+    pv: &HashMap<String, T>,
+    ss: &[bool],
+    cache: &mut BddHashMap<NodeId, (T, T)>,
+) -> (T, T)
+where
+    T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialEq + From<f64>,
+{
+    if let Some(x) = cache.get(&node) {
+        return *x;
+    }
+    let terminal = |inside: bool| {
+        if inside {
+            (T::from(1.0), T::from(0.0))
+        } else {
+            (T::from(0.0), T::from(1.0))
+        }
+    };
+    let result = match dd.get_node(&node).unwrap() {
+        Node::Zero => terminal(ss.contains(&false)),
+        Node::One => terminal(ss.contains(&true)),
+        Node::NonTerminal(fnode) => {
+            let x = dd.label(&node).unwrap();
+            let fp = *pv.get(x).unwrap_or(&T::from(0.0));
+            let barp = T::from(1.0) - fp;
+            let (l0, l1) = prob_with_complement(dd, fnode.edge(0), pv, ss, cache);
+            let (h0, h1) = prob_with_complement(dd, fnode.edge(1), pv, ss, cache);
+            (barp * l0 + fp * h0, barp * l1 + fp * h1)
+        }
+        Node::Undet => panic!("Undetermined node"),
+    };
+    cache.insert(node, result);
+    result
+}
+
+/// One instruction of a [`ProbTape`]: a terminal, or a Shannon node reading its two
+/// children from earlier slots of the tape.
+#[derive(Debug, Clone)]
+enum ProbInstr {
+    Zero,
+    One,
+    Node { level: Level, low: usize, high: usize },
+}
+
+/// A BDD linearized into a topologically ordered instruction tape (children before
+/// parents, root last), for evaluating the probability of one structure function under
+/// many parameter vectors.
+///
+/// [`prob`] rebuilds its memo table and re-walks the graph on every call; the tape does the
+/// walk once in [`ProbTape::new`], after which each evaluation is a single linear sweep over
+/// a flat buffer. Parameters are given **per level** (`p[level]`, the level being the
+/// `defvar` order, see `BddMgr::get_varorder`); [`ProbTape::levels`] maps a name-keyed
+/// parameter set onto that layout. As in [`prob`], a variable without a probability is
+/// taken as `0`.
+#[derive(Debug, Clone)]
+pub struct ProbTape {
+    tape: Vec<ProbInstr>,
+    /// Variable label per level, for the levels the diagram actually reads.
+    labels: Vec<Option<String>>,
+}
+
+impl ProbTape {
+    pub fn new(dd: &BddManager, node: NodeId) -> Self {
+        let mut tape = ProbTape {
+            tape: Vec::new(),
+            labels: Vec::new(),
+        };
+        let mut slots = BddHashMap::default();
+        tape.build(dd, node, &mut slots);
+        tape
+    }
+
+    fn build(&mut self, dd: &BddManager, node: NodeId, slots: &mut BddHashMap<NodeId, usize>) -> usize {
+        if let Some(&i) = slots.get(&node) {
+            return i;
+        }
+        let instr = match dd.get_node(&node).unwrap() {
+            Node::Zero => ProbInstr::Zero,
+            Node::One => ProbInstr::One,
+            Node::NonTerminal(fnode) => {
+                let low = self.build(dd, fnode.edge(0), slots);
+                let high = self.build(dd, fnode.edge(1), slots);
+                let level = dd.level(&node).unwrap();
+                if self.labels.len() <= level {
+                    self.labels.resize(level + 1, None);
+                }
+                self.labels[level] = dd.label(&node).map(|x| x.to_string());
+                ProbInstr::Node { level, low, high }
+            }
+            Node::Undet => panic!("Undetermined node"),
+        };
+        self.tape.push(instr);
+        let i = self.tape.len() - 1;
+        slots.insert(node, i);
+        i
+    }
+
+    /// Number of instructions (distinct nodes, terminals included).
+    pub fn len(&self) -> usize {
+        self.tape.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tape.is_empty()
+    }
+
+    /// Lay out a name-keyed parameter set per level, as [`eval`](Self::eval) expects.
+    pub fn levels<T>(&self, pv: &HashMap<String, T>) -> Vec<T>
+    where
+        T: Clone + Copy + From<f64>,
+    {
+        self.labels
+            .iter()
+            .map(|x| match x {
+                Some(label) => *pv.get(label).unwrap_or(&T::from(0.0)),
+                None => T::from(0.0),
+            })
+            .collect()
+    }
+
+    /// Probability that the structure function takes a value in `ss`, with `p[level]` the
+    /// probability of the variable at `level`.
+    pub fn eval<T>(&self, p: &[T], ss: &[bool]) -> T
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialEq + From<f64>,
+    {
+        let mut buf = Vec::with_capacity(self.tape.len());
+        self.eval_into(p, ss, &mut buf)
+    }
+
+    /// [`eval`](Self::eval) reusing `buf` as the slot buffer, so a batch allocates it once.
+    fn eval_into<T>(&self, p: &[T], ss: &[bool], buf: &mut Vec<T>) -> T
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialEq + From<f64>,
+    {
+        let zero = if ss.contains(&false) { T::from(1.0) } else { T::from(0.0) };
+        let one = if ss.contains(&true) { T::from(1.0) } else { T::from(0.0) };
+        buf.clear();
+        for instr in self.tape.iter() {
+            let v = match instr {
+                ProbInstr::Zero => zero,
+                ProbInstr::One => one,
+                ProbInstr::Node { level, low, high } => {
+                    let fp = p.get(*level).copied().unwrap_or(T::from(0.0));
+                    (T::from(1.0) - fp) * buf[*low] + fp * buf[*high]
+                }
+            };
+            buf.push(v);
+        }
+        *buf.last().unwrap()
+    }
+}
+
+/// [`prob`] for every parameter set in `pvs`, sharing one [`ProbTape`].
+pub fn prob_batch<T>(
+    dd: &BddManager,
+    node: NodeId,
+    pvs: &[HashMap<String, T>],
+    ss: &[bool],
+) -> Vec<T>
+where
+    T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialEq + From<f64>,
+{
+    let tape = ProbTape::new(dd, node);
+    let mut buf = Vec::with_capacity(tape.len());
+    pvs.iter()
+        .map(|pv| {
+            let p = tape.levels(pv);
+            tape.eval_into(&p, ss, &mut buf)
+        })
+        .collect()
+}
+
+/// Columnar [`prob_batch`]: each entry of `pvs` is a parameter vector indexed by level;
+/// levels past its end count as probability 0.
+pub fn prob_batch_levels<T>(
+    dd: &BddManager,
+    node: NodeId,
+    pvs: &[Vec<T>],
+    ss: &[bool],
+) -> Vec<T>
+where
+    T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialEq + From<f64>,
+{
+    let tape = ProbTape::new(dd, node);
+    let mut buf = Vec::with_capacity(tape.len());
+    pvs.iter().map(|p| tape.eval_into(p, ss, &mut buf)).collect()
+}
+
 pub fn bmeas<T>(
     dd: &BddManager,
     ss: &[bool],
@@ -92,6 +379,140 @@ where
     gradevent
 }
 
+/// Posterior marginals `P(x = 1 | f ∈ ss)` of every variable of the diagram.
+///
+/// The same forward/backward pass as [`bmeas`]: `w(u)`, the probability of reaching node
+/// `u` from the root, is pushed down in topological order and combined with `prob` of the
+/// children. For a variable `x` at level `l`,
+///
+/// ```text
+/// P(x = 1, f ∈ ss) = Σ_u w(u)·p·prob(u₁) + p·(P(f ∈ ss) − Σ_u w(u)·prob(u))
+/// ```
+///
+/// over the nodes `u` labelled `x`; the second term is the mass of the paths that skip
+/// level `l`, on which `x` keeps its prior. Dividing by `P(f ∈ ss)` gives the posterior, so
+/// the result is undefined (`NaN` for `f64`) when `f ∈ ss` is impossible.
+pub fn posterior<T>(
+    dd: &BddManager,
+    ss: &[bool],
+    node: NodeId,
+    env: &HashMap<String, T>,
+) -> HashMap<String, T>
+where
+    T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Div<Output = T> + Clone + Copy + PartialEq + From<f64>,
+{
+    let sorted_nodes = topological_sort(dd, node);
+    let mut bddcache = BddHashMap::default();
+    let total = prob(dd, node, env, ss, &mut bddcache);
+    let mut weight = BddHashMap::default();
+    // label -> (mass through the level, mass through its high edges)
+    let mut mass: HashMap<String, (T, T)> = HashMap::new();
+    weight.insert(node, T::from(1.0));
+    for f in sorted_nodes {
+        if let Node::NonTerminal(fnode) = dd.get_node(&f).unwrap() {
+            let w = *weight.get(&f).unwrap_or(&T::from(0.0));
+            let x = dd.label(&f).unwrap();
+            let p = *env.get(x).unwrap_or(&T::from(0.0));
+            let barp = T::from(1.0) - p;
+            for (e, q) in [(fnode.edge(0), barp), (fnode.edge(1), p)] {
+                let acc = *weight.get(&e).unwrap_or(&T::from(0.0));
+                weight.insert(e, acc + w * q);
+            }
+            let p0 = prob(dd, fnode.edge(0), env, ss, &mut bddcache);
+            let p1 = prob(dd, fnode.edge(1), env, ss, &mut bddcache);
+            let (through, high) = mass.entry(x.to_string()).or_insert((T::from(0.0), T::from(0.0)));
+            *through = *through + w * (barp * p0 + p * p1);
+            *high = *high + w * p * p1;
+        }
+    }
+    mass.into_iter()
+        .map(|(x, (through, high))| {
+            let p = *env.get(&x).unwrap_or(&T::from(0.0));
+            (x, (high + p * (total - through)) / total)
+        })
+        .collect()
+}
+
+/// Most probable explanation: the assignment of the diagram's variables with `f ∈ ss` that
+/// has the highest probability, with that probability; `None` if `f` never takes a value in
+/// `ss`.
+///
+/// [`prob`] with max-product in place of sum-product: a node keeps the better of its two
+/// branches. A variable skipped on an edge is free there and takes its more probable value,
+/// `max(p, 1 − p) >= 1/2`, so the products over skipped levels come from prefix products
+/// (divided, never by zero). Ties go to the low (`false`) branch.
+pub fn mpe<T>(
+    dd: &BddManager,
+    ss: &[bool],
+    node: NodeId,
+    env: &HashMap<String, T>,
+) -> Option<(T, HashMap<String, bool>)>
+where
+    T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Div<Output = T> + Clone + Copy + PartialOrd + From<f64>,
+{
+    let sorted_nodes = topological_sort(dd, node);
+    let mut labels = BTreeMap::new();
+    for f in sorted_nodes.iter() {
+        if let (Some(level), Some(x)) = (dd.level(f), dd.label(f)) {
+            labels.insert(level, x.to_string());
+        }
+    }
+    // Position 0 is the terminals, position k + 1 the k-th lowest level of the diagram;
+    // free[k] is the product of max(p, 1 - p) over the positions below k + 1.
+    let (levels, names): (Vec<Level>, Vec<String>) = labels.into_iter().unzip();
+    let probs: Vec<T> = names.iter().map(|x| *env.get(x).unwrap_or(&T::from(0.0))).collect();
+    let pos: HashMap<Level, usize> = levels.iter().enumerate().map(|(k, &l)| (l, k + 1)).collect();
+    let mut free = vec![T::from(1.0)];
+    for &p in probs.iter() {
+        let m = if p > T::from(1.0) - p { p } else { T::from(1.0) - p };
+        free.push(*free.last().unwrap() * m);
+    }
+    let position = |f: &NodeId| dd.level(f).map_or(0, |l| pos[&l]);
+    let skipped = |a: usize, b: usize| free[a - 1] / free[b];
+
+    let mut best: BddHashMap<NodeId, (Option<T>, bool)> = BddHashMap::default();
+    for f in sorted_nodes.iter().rev() {
+        let entry = match dd.get_node(f).unwrap() {
+            Node::Zero => (ss.contains(&false).then(|| T::from(1.0)), false),
+            Node::One => (ss.contains(&true).then(|| T::from(1.0)), false),
+            Node::NonTerminal(fnode) => {
+                let a = position(f);
+                let p = probs[a - 1];
+                let branch = |e: NodeId, q: T| best[&e].0.map(|v| q * v * skipped(a, position(&e)));
+                match (branch(fnode.edge(0), T::from(1.0) - p), branch(fnode.edge(1), p)) {
+                    (Some(lo), Some(hi)) if hi > lo => (Some(hi), true),
+                    (Some(lo), _) => (Some(lo), false),
+                    (None, hi) => (hi, true),
+                }
+            }
+            Node::Undet => panic!("Undetermined node"),
+        };
+        best.insert(*f, entry);
+    }
+
+    let value = best[&node].0?;
+    let mut assignment = HashMap::new();
+    let mut f = node;
+    let mut above = names.len() + 1;
+    loop {
+        let here = position(&f);
+        for k in here + 1..above {
+            let p = probs[k - 1];
+            assignment.insert(names[k - 1].clone(), p > T::from(1.0) - p);
+        }
+        match dd.get_node(&f).unwrap() {
+            Node::NonTerminal(fnode) => {
+                let high = best[&f].1;
+                assignment.insert(dd.label(&f).unwrap().to_string(), high);
+                f = fnode.edge(if high { 1 } else { 0 });
+                above = here;
+            }
+            _ => break,
+        }
+    }
+    Some((value, assignment))
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum CheckedState {
     Persistent,
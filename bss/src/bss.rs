@@ -1,7 +1,17 @@
 use bddcore::prelude::*;
+use std::collections::HashMap;
+use std::ops::{Add, Mul};
 use crate::bdd::{BddMgr, BddNode};
 use crate::zdd::{ZddMgr, ZddNode};
+use crate::bdd_ccf::{CcfGroup, CcfModel};
+use crate::fault_tree::FaultTree;
+use crate::mef::{self, MefModel};
+use crate::{aralia, galileo};
+use crate::network::Network;
+use crate::rbd::{Block, Rbd};
+use crate::phased::PhasedMission;
 use crate::bdd_minsol;
+use crate::bdd_prob;
 use crate::zdd_convert;
 
 /// Binary-state system manager: owns a [`BddMgr`] (boolean structure functions) **and** a
@@ -54,10 +64,78 @@ impl BssMgr {
         self.bdd.defvar(var)
     }
 
+    pub fn defccf(&mut self, name: &str, members: &[&str], model: CcfModel) -> Result<(), RelibError> {
+        self.bdd.defccf(name, members, model)
+    }
+
+    pub fn ccf_groups(&self) -> &[CcfGroup] {
+        self.bdd.ccf_groups()
+    }
+
+    pub fn ccf_prob<T>(&self, pv: &HashMap<String, T>) -> HashMap<String, T>
+    where
+        T: Add<Output = T> + Mul<Output = T> + Clone + Copy + From<f64>,
+    {
+        self.bdd.ccf_prob(pv)
+    }
+
     pub fn rpn(&mut self, expr: &str) -> Result<BddNode, String> {
         self.bdd.rpn(expr)
     }
 
+    pub fn try_rpn(&mut self, expr: &str) -> Result<BddNode, RelibError> {
+        self.bdd.try_rpn(expr)
+    }
+
+    pub fn parse(&mut self, expr: &str) -> Result<BddNode, RelibError> {
+        self.bdd.parse(expr)
+    }
+
+    /// Imports an Open-PSA MEF fault tree; see [`mef::read_mef`].
+    pub fn read_mef(&mut self, xml: &str, mission_time: Option<f64>) -> Result<MefModel, RelibError> {
+        mef::read_mef(&mut self.bdd, xml, mission_time)
+    }
+
+    /// Compiles a reliability block diagram; see [`Rbd::compile`].
+    pub fn compile_rbd(&mut self, rbd: &Rbd, block: &Block) -> Result<BddNode, RelibError> {
+        rbd.compile(&mut self.bdd, block)
+    }
+
+    /// Two-terminal connectivity of `net`; see [`Network::st_connectivity`].
+    pub fn st_connectivity(&mut self, net: &Network, s: usize, t: usize) -> Result<BddNode, RelibError> {
+        net.st_connectivity(&mut self.bdd, s, t)
+    }
+
+    /// k-terminal connectivity of `net`; see [`Network::k_terminal`].
+    pub fn k_terminal(&mut self, net: &Network, terminals: &[usize]) -> Result<BddNode, RelibError> {
+        net.k_terminal(&mut self.bdd, terminals)
+    }
+
+    /// All-terminal connectivity of `net`; see [`Network::all_terminal`].
+    pub fn all_terminal(&mut self, net: &Network) -> Result<BddNode, RelibError> {
+        net.all_terminal(&mut self.bdd)
+    }
+
+    /// Defines the per-phase copies of `components`; see [`PhasedMission::new`].
+    pub fn phased_mission(&mut self, components: &[&str], phases: &[&str]) -> Result<PhasedMission, RelibError> {
+        PhasedMission::new(&mut self.bdd, components, phases)
+    }
+
+    /// Moves `f` into `phase` of `pm`; see [`PhasedMission::in_phase`].
+    pub fn in_phase(&mut self, pm: &PhasedMission, f: &BddNode, phase: &str) -> Result<BddNode, RelibError> {
+        pm.in_phase(&mut self.bdd, f, phase)
+    }
+
+    /// Imports an Aralia fault tree; see [`aralia::read_aralia`].
+    pub fn read_aralia(&mut self, src: &str) -> Result<FaultTree, RelibError> {
+        aralia::read_aralia(&mut self.bdd, src)
+    }
+
+    /// Imports a Galileo fault tree; see [`galileo::read_galileo`].
+    pub fn read_galileo(&mut self, src: &str, mission_time: Option<f64>) -> Result<FaultTree, RelibError> {
+        galileo::read_galileo(&mut self.bdd, src, mission_time)
+    }
+
     pub fn zero(&self) -> BddNode {
         self.bdd.zero()
     }
@@ -82,6 +160,14 @@ impl BssMgr {
         self.bdd.kofn(k, nodes)
     }
 
+    pub fn weighted_threshold(&self, weights: &[i64], threshold: i64, nodes: &[BddNode]) -> BddNode {
+        self.bdd.weighted_threshold(weights, threshold, nodes)
+    }
+
+    pub fn consecutive_kofn(&self, k: usize, nodes: &[BddNode], circular: bool) -> BddNode {
+        self.bdd.consecutive_kofn(k, nodes, circular)
+    }
+
     pub fn get_varorder(&self) -> Vec<String> {
         self.bdd.get_varorder()
     }
@@ -104,7 +190,8 @@ impl BssMgr {
     /// A minimal path vector is a minimal set of components whose functioning makes the
     /// system function. The minsol runs on the BDD; the result is converted (internally,
     /// once) into this manager's [`ZddMgr`], so the returned [`ZddNode`] supports the set
-    /// algebra. Non-monotone input (built with `xor`/`not`/`<`/`!=`) returns `None`.
+    /// algebra. Non-monotone input (built with `xor`/`not`/`<`/`!=`) returns `None`;
+    /// [`BddNode::check_coherence`] gives a counterexample.
     /// See [`mincut`](Self::mincut) for the dual.
     pub fn minpath(&self, node: &BddNode) -> Option<ZddNode> {
         let bdd = node.get_mgr();
@@ -126,6 +213,13 @@ impl BssMgr {
         })
     }
 
+    /// Fallible [`minpath`](Self::minpath): `Err` if the manager of `node` has been dropped
+    /// or an `Undet` node is reachable, where `minpath` panics.
+    pub fn try_minpath(&self, node: &BddNode) -> Result<Option<ZddNode>, RelibError> {
+        bdd_prob::check_determined(&node.try_get_mgr()?.borrow(), node.get_id())?;
+        Ok(self.minpath(node))
+    }
+
     /// Minimal **cut** vectors of the structure function `node` as a genuine ZDD set family,
     /// or `None` if it is not monotone.
     ///
@@ -134,4 +228,48 @@ impl BssMgr {
     pub fn mincut(&self, node: &BddNode) -> Option<ZddNode> {
         self.minpath(&node.dual())
     }
+
+    /// Fallible [`mincut`](Self::mincut); see [`try_minpath`](Self::try_minpath).
+    pub fn try_mincut(&self, node: &BddNode) -> Result<Option<ZddNode>, RelibError> {
+        bdd_prob::check_determined(&node.try_get_mgr()?.borrow(), node.get_id())?;
+        Ok(self.mincut(node))
+    }
+
+    // --- bounds from interval-valued probabilities ---------------------------
+
+    /// Lower and upper bounds of `P(f ∈ ss)` when each variable's probability is only known
+    /// to lie in an [`Interval`].
+    ///
+    /// If [`minpath`](Self::minpath) reports `node` coherent, `P(f = 1)` is nondecreasing in
+    /// every probability, so the bounds are the probabilities at the vectors of lower and of
+    /// upper endpoints, and they are exact. Otherwise this falls back to
+    /// [`BddNode::prob_interval`], whose bounds are sound but may be loose.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bss::prelude::*;
+    /// use std::collections::HashMap;
+    ///
+    /// let mut bss = BssMgr::new();
+    /// bss.defvar("A");
+    /// bss.defvar("B");
+    /// let top = bss.rpn("A B &").unwrap();
+    /// let pv = HashMap::from([
+    ///     ("A".to_string(), Interval::new(0.8, 0.9)),
+    ///     ("B".to_string(), Interval::new(0.5, 0.6)),
+    /// ]);
+    ///
+    /// let p = bss.prob_interval(&top, &pv, &[true]);
+    /// assert!((p.lo - 0.4).abs() < 1e-12 && (p.hi - 0.54).abs() < 1e-12);
+    /// ```
+    pub fn prob_interval(&self, node: &BddNode, pv: &HashMap<String, Interval>, ss: &[bool]) -> Interval {
+        if self.minpath(node).is_none() {
+            return node.prob_interval(pv, ss);
+        }
+        let lo: HashMap<String, f64> = pv.iter().map(|(x, p)| (x.clone(), p.lo)).collect();
+        let hi: HashMap<String, f64> = pv.iter().map(|(x, p)| (x.clone(), p.hi)).collect();
+        let (a, b) = (node.prob(&lo, ss), node.prob(&hi, ss));
+        Interval::new(a.min(b), a.max(b))
+    }
 }
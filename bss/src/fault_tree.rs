@@ -0,0 +1,406 @@
+//! Static fault trees read from external formats.
+//!
+//! The readers ([`mef`](crate::mef), [`aralia`](crate::aralia), [`galileo`](crate::galileo))
+//! parse their syntax into gate [`Formula`]s and hand them to [`build`], which resolves
+//! references (gates may be used before they are defined), rejects circular definitions and
+//! builds every gate through a [`BddMgr`]. The result is a [`FaultTree`].
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::bdd::{BddMgr, BddNode};
+
+/// A fault tree read into a [`BddMgr`].
+#[derive(Debug)]
+pub struct FaultTree {
+    /// Name of the top gate: given by the format, or else the first defined gate no other
+    /// gate references.
+    pub top_gate: String,
+    /// The structure function of the top gate.
+    pub top: BddNode,
+    /// Every defined gate.
+    pub gates: HashMap<String, BddNode>,
+    /// Probability of every basic event that has one, ready for
+    /// [`BddNode::prob`] / [`BddNode::bmeas`].
+    pub probs: HashMap<String, f64>,
+}
+
+/// Source position used in error messages (`col` is `None` for line-oriented formats).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Pos {
+    pub line: usize,
+    pub col: Option<usize>,
+}
+
+impl fmt::Display for Pos {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.col {
+            Some(c) => write!(f, "line {}, column {}", self.line, c),
+            None => write!(f, "line {}", self.line),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Op {
+    And,
+    Or,
+    Xor,
+    Nand,
+    Nor,
+    Not,
+    AtLeast(usize),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Formula {
+    Op(Op, Vec<Formula>, Pos),
+    Const(bool),
+    /// A reference that must be a gate.
+    Gate(String, Pos),
+    /// A basic event (becomes a variable).
+    Basic(String),
+    /// A reference that must be a house event.
+    House(String, Pos),
+    /// A gate if one has that name, else a house event, else a basic event.
+    Event(String, Pos),
+}
+
+/// Definitions collected by a reader.
+#[derive(Debug, Default)]
+pub(crate) struct Definitions {
+    gates: Vec<(String, Formula, Pos)>,
+    gate_index: HashMap<String, usize>,
+    pub house_events: HashMap<String, bool>,
+    pub probs: HashMap<String, f64>,
+}
+
+impl Definitions {
+    pub fn define_gate(&mut self, name: &str, f: Formula, pos: Pos) -> Result<(), String> {
+        if let Some(&i) = self.gate_index.get(name) {
+            return Err(format!(
+                "{}: gate {} is already defined on {}",
+                pos, name, self.gates[i].2
+            ));
+        }
+        self.gate_index.insert(name.to_string(), self.gates.len());
+        self.gates.push((name.to_string(), f, pos));
+        Ok(())
+    }
+
+    pub fn is_gate(&self, name: &str) -> bool {
+        self.gate_index.contains_key(name)
+    }
+}
+
+struct Builder<'a> {
+    mgr: &'a mut BddMgr,
+    defs: &'a Definitions,
+    done: HashMap<&'a str, BddNode>,
+    visiting: HashSet<&'a str>,
+}
+
+impl<'a> Builder<'a> {
+    fn gate(&mut self, name: &'a str, pos: Pos) -> Result<BddNode, String> {
+        if let Some(x) = self.done.get(name) {
+            return Ok(x.clone());
+        }
+        let formula = match self.defs.gate_index.get(name) {
+            Some(&i) => &self.defs.gates[i].1,
+            None => return Err(format!("{}: unknown gate {}", pos, name)),
+        };
+        if !self.visiting.insert(name) {
+            return Err(format!("{}: gate {} is defined in terms of itself", pos, name));
+        }
+        let node = self.formula(formula)?;
+        self.visiting.remove(name);
+        self.done.insert(name, node.clone());
+        Ok(node)
+    }
+
+    fn constant(&self, b: bool) -> BddNode {
+        if b {
+            self.mgr.one()
+        } else {
+            self.mgr.zero()
+        }
+    }
+
+    fn formula(&mut self, f: &'a Formula) -> Result<BddNode, String> {
+        match f {
+            Formula::Const(b) => Ok(self.constant(*b)),
+            Formula::Basic(name) => Ok(self.mgr.defvar(name)),
+            Formula::Gate(name, pos) => self.gate(name, *pos),
+            Formula::House(name, pos) => match self.defs.house_events.get(name.as_str()) {
+                Some(&b) => Ok(self.constant(b)),
+                None => Err(format!("{}: unknown house event {}", pos, name)),
+            },
+            Formula::Event(name, pos) => {
+                if self.defs.is_gate(name) {
+                    self.gate(name, *pos)
+                } else if let Some(&b) = self.defs.house_events.get(name.as_str()) {
+                    Ok(self.constant(b))
+                } else {
+                    Ok(self.mgr.defvar(name))
+                }
+            }
+            Formula::Op(op, args, pos) => {
+                let v = args
+                    .iter()
+                    .map(|x| self.formula(x))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if v.is_empty() {
+                    return Err(format!("{}: connective without arguments", pos));
+                }
+                if let Op::AtLeast(k) = op {
+                    if *k == 0 || *k > v.len() {
+                        return Err(format!(
+                            "{}: at-least gate needs 1 <= k <= {}, got {}",
+                            pos,
+                            v.len(),
+                            k
+                        ));
+                    }
+                }
+                Ok(match op {
+                    Op::And => self.mgr.and(&v),
+                    Op::Or => self.mgr.or(&v),
+                    Op::Nand => self.mgr.and(&v).not(),
+                    Op::Nor => self.mgr.or(&v).not(),
+                    Op::Xor => v[1..].iter().fold(v[0].clone(), |acc, x| acc.xor(x)),
+                    Op::Not => {
+                        if v.len() != 1 {
+                            return Err(format!("{}: negation takes one argument", pos));
+                        }
+                        v[0].not()
+                    }
+                    Op::AtLeast(k) => self.mgr.kofn(*k, &v),
+                })
+            }
+        }
+    }
+}
+
+fn referenced_gates<'a>(f: &'a Formula, defs: &Definitions, out: &mut HashSet<&'a str>) {
+    match f {
+        Formula::Gate(name, _) | Formula::Event(name, _) if defs.is_gate(name) => {
+            out.insert(name);
+        }
+        Formula::Op(_, args, _) => {
+            for x in args {
+                referenced_gates(x, defs, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Builds every gate of `defs`. `top` names the top gate; without it the top gate is the
+/// first defined gate that no other gate references. The top gate is built first so its
+/// basic events get the first levels.
+pub(crate) fn build(
+    mgr: &mut BddMgr,
+    defs: Definitions,
+    top: Option<(&str, Pos)>,
+) -> Result<FaultTree, String> {
+    if defs.gates.is_empty() {
+        return Err("the fault tree defines no gates".to_string());
+    }
+    let (top_gate, top_pos) = match top {
+        Some((name, pos)) => (name.to_string(), pos),
+        None => {
+            let mut referenced = HashSet::new();
+            for (_, f, _) in defs.gates.iter() {
+                referenced_gates(f, &defs, &mut referenced);
+            }
+            let (name, _, pos) = defs
+                .gates
+                .iter()
+                .find(|(name, _, _)| !referenced.contains(name.as_str()))
+                .ok_or_else(|| "every gate is referenced by another: no top gate".to_string())?;
+            (name.clone(), *pos)
+        }
+    };
+
+    let mut builder = Builder {
+        mgr,
+        defs: &defs,
+        done: HashMap::new(),
+        visiting: HashSet::new(),
+    };
+    let top = builder.gate(&top_gate, top_pos)?;
+    for (name, _, pos) in defs.gates.iter() {
+        builder.gate(name, *pos)?;
+    }
+    let gates = builder
+        .done
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect();
+    Ok(FaultTree {
+        top_gate,
+        top,
+        gates,
+        probs: defs.probs,
+    })
+}
+
+// --- a small tokenizer shared by the text formats ----------------------------------------
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Token {
+    /// Identifier or number (`[A-Za-z0-9_.+-]+`), or a `"quoted"` name.
+    Word(String),
+    /// Punctuation; multi-character operators (`:=`, `=>`, `<=>`) are one token.
+    Punct(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Word(w) => write!(f, "`{}`", w),
+            Token::Punct(p) => write!(f, "`{}`", p),
+        }
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '+' | '-')
+}
+
+const PUNCT: [&str; 16] = [
+    "<=>", ":=", "=>", "&", "|", "~", "^", "@", "#", "(", ")", "[", "]", ",", ";", "=",
+];
+
+/// Splits `src` into tokens with their positions. `//` and `/* */` are comments.
+pub(crate) fn tokenize(src: &str) -> Result<Vec<(Token, Pos)>, String> {
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut col = 1;
+    let mut rest = src;
+    // Advance over `n` bytes of `rest`, keeping line/column in step.
+    let advance = |rest: &mut &str, n: usize, line: &mut usize, col: &mut usize| {
+        for c in rest[..n].chars() {
+            if c == '\n' {
+                *line += 1;
+                *col = 1;
+            } else {
+                *col += 1;
+            }
+        }
+        *rest = &rest[n..];
+    };
+    while let Some(c) = rest.chars().next() {
+        let pos = Pos { line, col: Some(col) };
+        if c.is_whitespace() {
+            advance(&mut rest, c.len_utf8(), &mut line, &mut col);
+        } else if rest.starts_with("//") {
+            let n = rest.find('\n').unwrap_or(rest.len());
+            advance(&mut rest, n, &mut line, &mut col);
+        } else if rest.starts_with("/*") {
+            match rest.find("*/") {
+                Some(n) => advance(&mut rest, n + 2, &mut line, &mut col),
+                None => return Err(format!("{}: unterminated comment", pos)),
+            }
+        } else if c == '"' {
+            match rest[1..].find('"') {
+                Some(n) => {
+                    tokens.push((Token::Word(rest[1..n + 1].to_string()), pos));
+                    advance(&mut rest, n + 2, &mut line, &mut col);
+                }
+                None => return Err(format!("{}: unterminated string", pos)),
+            }
+        } else if is_word_char(c) {
+            let n = rest.find(|c| !is_word_char(c)).unwrap_or(rest.len());
+            tokens.push((Token::Word(rest[..n].to_string()), pos));
+            advance(&mut rest, n, &mut line, &mut col);
+        } else if let Some(p) = PUNCT.iter().find(|p| rest.starts_with(**p)) {
+            tokens.push((Token::Punct(p), pos));
+            advance(&mut rest, p.len(), &mut line, &mut col);
+        } else {
+            return Err(format!("{}: unexpected character `{}`", pos, c));
+        }
+    }
+    Ok(tokens)
+}
+
+/// Cursor over the tokens of one document.
+pub(crate) struct Tokens {
+    tokens: Vec<(Token, Pos)>,
+    i: usize,
+    end: Pos,
+}
+
+impl Tokens {
+    pub fn new(src: &str) -> Result<Self, String> {
+        let line = src.matches('\n').count() + 1;
+        let col = src.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+        Ok(Tokens {
+            tokens: tokenize(src)?,
+            i: 0,
+            end: Pos { line, col: Some(col) },
+        })
+    }
+
+    pub fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.i).map(|(t, _)| t)
+    }
+
+    pub fn peek_at(&self, k: usize) -> Option<&Token> {
+        self.tokens.get(self.i + k).map(|(t, _)| t)
+    }
+
+    /// Position of the next token (end of input if none).
+    pub fn pos(&self) -> Pos {
+        self.tokens.get(self.i).map(|(_, p)| *p).unwrap_or(self.end)
+    }
+
+    pub fn next(&mut self) -> Option<Token> {
+        let t = self.tokens.get(self.i).map(|(t, _)| t.clone());
+        self.i += 1;
+        t
+    }
+
+    /// Error "expected `what`" at the next token.
+    pub fn unexpected<T>(&self, what: &str) -> Result<T, String> {
+        match self.peek() {
+            Some(t) => Err(format!("{}: expected {}, found {}", self.pos(), what, t)),
+            None => Err(format!("{}: expected {}, found end of input", self.pos(), what)),
+        }
+    }
+
+    pub fn eat(&mut self, p: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Punct(q)) if *q == p) {
+            self.i += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn expect(&mut self, p: &str) -> Result<(), String> {
+        if self.eat(p) {
+            Ok(())
+        } else {
+            self.unexpected(&format!("`{}`", p))
+        }
+    }
+
+    pub fn word(&mut self, what: &str) -> Result<(String, Pos), String> {
+        let pos = self.pos();
+        match self.peek() {
+            Some(Token::Word(w)) => {
+                let w = w.clone();
+                self.i += 1;
+                Ok((w, pos))
+            }
+            _ => self.unexpected(what),
+        }
+    }
+
+    pub fn number<T: std::str::FromStr>(&mut self, what: &str) -> Result<T, String> {
+        let (w, pos) = self.word(what)?;
+        w.parse::<T>()
+            .map_err(|_| format!("{}: expected {}, found `{}`", pos, what, w))
+    }
+}
@@ -0,0 +1,151 @@
+//! Galileo fault trees.
+//!
+//! The text format of the Galileo DFT tool, static subset. Names may be `"quoted"`; every
+//! statement ends with `;`:
+//!
+//! ```text
+//! toplevel "System";
+//! "System" or "PumpA" "PumpB";
+//! "PumpA" and "A1" "A2";
+//! "PumpB" 2of3 "B1" "B2" "B3";
+//! "A1" prob=0.01;
+//! "B1" lambda=1.0e-4 dorm=0;   // 1 - exp(-lambda t) at the mission time
+//! ```
+//!
+//! Gates are `and`, `or` and `KofM` voting gates. Basic events take `prob=` or `lambda=`
+//! (exponential, evaluated at the mission time); other attributes (`dorm`, `repair`, …) only
+//! matter to dynamic analysis and are ignored. Dynamic gates (`pand`, `seq`, spares, `fdep`,
+//! …) are rejected.
+
+use common::error::RelibError;
+
+use crate::bdd::BddMgr;
+use crate::fault_tree::{self, Definitions, FaultTree, Formula, Op, Pos, Token, Tokens};
+
+const DYNAMIC_GATES: [&str; 11] = [
+    "pand", "por", "sand", "seq", "fdep", "pdep", "spare", "wsp", "csp", "hsp", "mutex",
+];
+
+fn gate_kind(word: &str) -> Option<Op> {
+    match word {
+        "and" => Some(Op::And),
+        "or" => Some(Op::Or),
+        _ => {
+            let (k, _) = word.split_once("of")?;
+            k.parse::<usize>().ok().map(Op::AtLeast)
+        }
+    }
+}
+
+/// Imports a Galileo fault tree into `mgr`.
+///
+/// `mission_time` evaluates `lambda=` basic events (needed only if one is present). The top
+/// gate is the `toplevel` one, or else the first gate no other gate references.
+///
+/// # Errors
+///
+/// Returns `Err` with the line and column on syntax errors, dynamic gates, `KofM` gates whose
+/// `M` does not match the number of inputs or whose `K` is not in `1..=M`, redefined or
+/// circular gates, a `lambda=` without a mission time and a probability outside `[0, 1]`.
+///
+/// # Example
+///
+/// ```
+/// use bss::prelude::*;
+///
+/// let src = r#"toplevel "T"; "T" 2of3 "A" "B" "C"; "A" prob=0.1; "B" prob=0.1; "C" prob=0.1;"#;
+/// let mut bss = BssMgr::new();
+/// let ft = bss.read_galileo(src, None).unwrap();
+/// assert!((ft.top.prob(&ft.probs, &[true]) - 0.028).abs() < 1e-12);
+/// ```
+pub fn read_galileo(
+    mgr: &mut BddMgr,
+    src: &str,
+    mission_time: Option<f64>,
+) -> Result<FaultTree, RelibError> {
+    let mut t = Tokens::new(src)?;
+    let mut defs = Definitions::default();
+    let mut top: Option<(String, Pos)> = None;
+    while t.peek().is_some() {
+        let (name, pos) = t.word("a name or `toplevel`")?;
+        if name == "toplevel" {
+            let (gate, gpos) = t.word("the top gate name")?;
+            if let Some((prev, ppos)) = &top {
+                return Err(RelibError::Invalid(format!("{}: toplevel {} already given on {}", gpos, prev, ppos)));
+            }
+            top = Some((gate, gpos));
+        } else if let Some(Token::Word(kind)) = t.peek().cloned() {
+            if t.peek_at(1) == Some(&Token::Punct("=")) {
+                basic_event(&mut t, &name, mission_time, &mut defs)?;
+            } else {
+                let kpos = t.pos();
+                t.next();
+                if DYNAMIC_GATES.contains(&kind.to_lowercase().as_str()) {
+                    return Err(RelibError::Invalid(format!("{}: dynamic gate `{}` is not supported", kpos, kind)));
+                }
+                let op = gate_kind(&kind.to_lowercase())
+                    .ok_or_else(|| RelibError::Invalid(format!("{}: unknown gate type `{}`", kpos, kind)))?;
+                let mut args = Vec::new();
+                while let Some(Token::Word(_)) = t.peek() {
+                    let (x, xpos) = t.word("an input name")?;
+                    args.push(Formula::Event(x, xpos));
+                }
+                if let Op::AtLeast(k) = op {
+                    let m = kind.to_lowercase().split_once("of").unwrap().1.to_string();
+                    if m.parse::<usize>().ok() != Some(args.len()) {
+                        return Err(RelibError::Invalid(format!(
+                            "{}: voting gate `{}` has {} inputs",
+                            kpos,
+                            kind,
+                            args.len()
+                        )));
+                    }
+                    if k == 0 || k > args.len() {
+                        return Err(RelibError::Invalid(format!("{}: voting gate `{}` needs 1 <= K <= M", kpos, kind)));
+                    }
+                }
+                defs.define_gate(&name, Formula::Op(op, args, kpos), pos)?;
+            }
+        }
+        t.expect(";")?;
+    }
+    fault_tree::build(mgr, defs, top.as_ref().map(|(n, p)| (n.as_str(), *p)))
+        .map_err(RelibError::Invalid)
+}
+
+/// `name attr=value ...` up to (not including) the `;`.
+fn basic_event(
+    t: &mut Tokens,
+    name: &str,
+    mission_time: Option<f64>,
+    defs: &mut Definitions,
+) -> Result<(), String> {
+    let mut prob = None;
+    let mut lambda = None;
+    while let Some(Token::Word(_)) = t.peek() {
+        let (attr, apos) = t.word("an attribute")?;
+        t.expect("=")?;
+        match attr.as_str() {
+            "prob" => prob = Some((t.number::<f64>("a probability")?, apos)),
+            "lambda" => lambda = Some((t.number::<f64>("a failure rate")?, apos)),
+            _ => {
+                t.word("a value")?;
+            }
+        }
+    }
+    let (p, apos) = match (prob, lambda) {
+        (Some(p), _) => p,
+        (None, Some((l, apos))) => match mission_time {
+            Some(time) => (1.0 - (-l * time).exp(), apos),
+            None => {
+                return Err(format!("{}: `lambda` of {} needs a mission time", apos, name))
+            }
+        },
+        (None, None) => return Ok(()),
+    };
+    if !(0.0..=1.0).contains(&p) {
+        return Err(format!("{}: probability {} of {} is not in [0, 1]", apos, p, name));
+    }
+    defs.probs.insert(name.to_string(), p);
+    Ok(())
+}
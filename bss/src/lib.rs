@@ -46,10 +46,20 @@
 pub mod bdd;
 pub mod bdd_path;
 pub mod bdd_minsol;
+pub mod bdd_coherence;
 pub mod bdd_dual;
 pub mod bdd_prob;
 pub mod bdd_count;
 pub mod bdd_kofn;
+pub mod bdd_parse;
+pub mod bdd_ccf;
+pub mod fault_tree;
+pub mod mef;
+pub mod aralia;
+pub mod galileo;
+pub mod network;
+pub mod rbd;
+pub mod phased;
 pub mod bss;
 pub mod zdd;
 pub mod zdd_count;
@@ -61,9 +71,18 @@ pub mod prelude {
     pub use crate::bdd::*;
     pub use crate::bdd_path::*;
     pub use crate::bdd_minsol::*;
+    pub use crate::bdd_coherence::*;
     pub use crate::bdd_dual::*;
     pub use crate::bdd_prob::*;
     pub use crate::bdd_count::*;
+    pub use crate::bdd_ccf::*;
+    pub use crate::fault_tree::FaultTree;
+    pub use crate::mef::*;
+    pub use crate::aralia::*;
+    pub use crate::galileo::*;
+    pub use crate::network::*;
+    pub use crate::rbd::*;
+    pub use crate::phased::*;
     pub use crate::bss::*;
     pub use crate::zdd::*;
     pub use crate::zdd_path::*;
@@ -0,0 +1,516 @@
+//! Open-PSA Model Exchange Format (MEF) fault trees.
+//!
+//! [`read_mef`] imports the static fault-tree subset of MEF XML into a [`BddMgr`]:
+//!
+//! - `define-gate` with the connectives `and`, `or`, `atleast` (built with
+//!   [`BddMgr::kofn`]), `not`, `xor`, `nand`, `nor`, and the references `gate`,
+//!   `basic-event`, `house-event`, `event` and `constant`;
+//! - `define-basic-event` with a probability expression: `float`/`int`, `parameter`
+//!   (a `define-parameter`), `system-mission-time`, `exponential` (`1 − e^{−λt}`) and the
+//!   arithmetic `add`/`sub`/`mul`/`div`/`neg`;
+//! - `define-house-event` (a `constant`, `false` when omitted).
+//!
+//! Definitions are collected from anywhere in the document (`define-fault-tree`,
+//! `define-component`, `model-data`), so gates may be referenced before they are defined.
+//! Basic events become variables in the order the top gate first reaches them.
+//!
+//! [`write_mef`] goes the other way: it writes a [`BddNode`] out as an MEF fault tree with one
+//! gate per BDD node (`x·high + ¬x·low`), so any diagram can be handed back to a MEF tool.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+use common::error::RelibError;
+
+use crate::bdd::{BddMgr, BddNode};
+use crate::fault_tree::{self, Definitions, FaultTree, Formula, Op, Pos};
+
+/// A fault tree read by [`read_mef`]: the [`FaultTree`] the Aralia and Galileo readers
+/// also produce.
+pub type MefModel = FaultTree;
+
+// --- a minimal XML reader (elements, attributes, comments; text is ignored) -----------------
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    attrs: Vec<(String, String)>,
+    children: Vec<Element>,
+    line: usize,
+}
+
+impl Element {
+    fn attr(&self, key: &str) -> Option<&str> {
+        self.attrs.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    fn req_attr(&self, key: &str) -> Result<&str, String> {
+        self.attr(key)
+            .ok_or_else(|| format!("line {}: <{}> needs a `{}` attribute", self.line, self.name, key))
+    }
+
+    /// The children that carry meaning (labels and attributes are documentation).
+    fn args(&self) -> impl Iterator<Item = &Element> {
+        self.children
+            .iter()
+            .filter(|x| x.name != "label" && x.name != "attributes")
+    }
+}
+
+struct XmlReader<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> XmlReader<'a> {
+    fn line(&self) -> usize {
+        self.src[..self.pos].matches('\n').count() + 1
+    }
+
+    fn err<T>(&self, msg: &str) -> Result<T, String> {
+        Err(format!("line {}: {}", self.line(), msg))
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn skip_ws(&mut self) {
+        let n = self.rest().len() - self.rest().trim_start().len();
+        self.pos += n;
+    }
+
+    /// Skip text, comments, processing instructions and declarations up to the next tag.
+    fn skip_misc(&mut self) -> Result<(), String> {
+        loop {
+            match self.rest().find('<') {
+                None => {
+                    self.pos = self.src.len();
+                    return Ok(());
+                }
+                Some(i) => self.pos += i,
+            }
+            let (open, close) = if self.rest().starts_with("<!--") {
+                ("<!--", "-->")
+            } else if self.rest().starts_with("<?") {
+                ("<?", "?>")
+            } else if self.rest().starts_with("<!") {
+                ("<!", ">")
+            } else {
+                return Ok(());
+            };
+            match self.rest()[open.len()..].find(close) {
+                Some(i) => self.pos += open.len() + i + close.len(),
+                None => return self.err(&format!("unterminated `{}`", open)),
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        let n = self
+            .rest()
+            .find(|c: char| c.is_whitespace() || c == '>' || c == '/' || c == '=')
+            .unwrap_or(self.rest().len());
+        if n == 0 {
+            return self.err("expected a name");
+        }
+        let name = self.rest()[..n].to_string();
+        self.pos += n;
+        Ok(name)
+    }
+
+    fn element(&mut self) -> Result<Element, String> {
+        let line = self.line();
+        if !self.rest().starts_with('<') {
+            return self.err("expected an element");
+        }
+        self.pos += 1;
+        let name = self.name()?;
+        let mut attrs = Vec::new();
+        loop {
+            self.skip_ws();
+            if self.rest().starts_with("/>") {
+                self.pos += 2;
+                return Ok(Element { name, attrs, children: Vec::new(), line });
+            }
+            if self.rest().starts_with('>') {
+                self.pos += 1;
+                break;
+            }
+            if self.rest().is_empty() {
+                return self.err(&format!("unterminated <{}>", name));
+            }
+            let key = self.name()?;
+            self.skip_ws();
+            if !self.rest().starts_with('=') {
+                return self.err(&format!("attribute `{}` of <{}> has no value", key, name));
+            }
+            self.pos += 1;
+            self.skip_ws();
+            let quote = match self.rest().chars().next() {
+                Some(q @ ('"' | '\'')) => q,
+                _ => return self.err(&format!("attribute `{}` of <{}> is not quoted", key, name)),
+            };
+            self.pos += 1;
+            let end = match self.rest().find(quote) {
+                Some(i) => i,
+                None => return self.err("unterminated attribute value"),
+            };
+            let value = unescape(&self.rest()[..end]);
+            self.pos += end + 1;
+            attrs.push((key, value));
+        }
+        let mut children = Vec::new();
+        loop {
+            self.skip_misc()?;
+            if self.rest().is_empty() {
+                return self.err(&format!("<{}> opened on line {} is never closed", name, line));
+            }
+            if self.rest().starts_with("</") {
+                self.pos += 2;
+                let close = self.name()?;
+                if close != name {
+                    return self.err(&format!(
+                        "</{}> closes <{}> opened on line {}",
+                        close, name, line
+                    ));
+                }
+                self.skip_ws();
+                if !self.rest().starts_with('>') {
+                    return self.err(&format!("malformed </{}>", close));
+                }
+                self.pos += 1;
+                return Ok(Element { name, attrs, children, line });
+            }
+            children.push(self.element()?);
+        }
+    }
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn parse_xml(src: &str) -> Result<Element, String> {
+    let mut r = XmlReader { src, pos: 0 };
+    r.skip_misc()?;
+    let root = r.element()?;
+    r.skip_misc()?;
+    if !r.rest().is_empty() {
+        return r.err("content after the root element");
+    }
+    Ok(root)
+}
+
+// --- MEF -> BDD --------------------------------------------------------------------------
+
+fn pos(e: &Element) -> Pos {
+    Pos { line: e.line, col: None }
+}
+
+fn formula_of(def: &Element) -> Result<&Element, String> {
+    let mut args = def.args();
+    match (args.next(), args.next()) {
+        (Some(f), None) => Ok(f),
+        _ => Err(format!(
+            "line {}: <{}> must hold exactly one formula",
+            def.line, def.name
+        )),
+    }
+}
+
+fn parse_bool(e: &Element) -> Result<bool, String> {
+    match e.req_attr("value")? {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        x => Err(format!("line {}: `{}` is not a boolean constant", e.line, x)),
+    }
+}
+
+fn parse_f64(e: &Element) -> Result<f64, String> {
+    let v = e.req_attr("value")?;
+    v.trim()
+        .parse::<f64>()
+        .map_err(|_| format!("line {}: `{}` is not a number", e.line, v))
+}
+
+fn formula(e: &Element) -> Result<Formula, String> {
+    let op = match e.name.as_str() {
+        "and" => Op::And,
+        "or" => Op::Or,
+        "xor" => Op::Xor,
+        "nand" => Op::Nand,
+        "nor" => Op::Nor,
+        "not" => Op::Not,
+        "atleast" => {
+            let k = e.req_attr("min")?;
+            let k = k
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| format!("line {}: `{}` is not a valid `min`", e.line, k))?;
+            Op::AtLeast(k)
+        }
+        "constant" => return Ok(Formula::Const(parse_bool(e)?)),
+        "gate" => return Ok(Formula::Gate(e.req_attr("name")?.to_string(), pos(e))),
+        "basic-event" => return Ok(Formula::Basic(e.req_attr("name")?.to_string())),
+        "house-event" => return Ok(Formula::House(e.req_attr("name")?.to_string(), pos(e))),
+        "event" => return Ok(Formula::Event(e.req_attr("name")?.to_string(), pos(e))),
+        x => return Err(format!("line {}: unsupported formula <{}>", e.line, x)),
+    };
+    let args = e.args().map(formula).collect::<Result<Vec<_>, _>>()?;
+    Ok(Formula::Op(op, args, pos(e)))
+}
+
+/// Basic events and parameters, whose expressions are evaluated once everything is known.
+#[derive(Default)]
+struct Quantities<'a> {
+    basic_events: Vec<(&'a str, &'a Element)>,
+    parameters: HashMap<&'a str, &'a Element>,
+}
+
+fn collect<'a>(
+    e: &'a Element,
+    defs: &mut Definitions,
+    q: &mut Quantities<'a>,
+) -> Result<(), String> {
+    match e.name.as_str() {
+        "define-gate" => {
+            defs.define_gate(e.req_attr("name")?, formula(formula_of(e)?)?, pos(e))?;
+        }
+        "define-basic-event" => {
+            q.basic_events.push((e.req_attr("name")?, e));
+        }
+        "define-house-event" => {
+            let value = match e.args().next() {
+                None => false,
+                Some(c) if c.name == "constant" => parse_bool(c)?,
+                Some(c) => {
+                    return Err(format!(
+                        "line {}: house event {} must be a <constant>",
+                        c.line,
+                        e.req_attr("name")?
+                    ))
+                }
+            };
+            defs.house_events.insert(e.req_attr("name")?.to_string(), value);
+        }
+        "define-parameter" => {
+            q.parameters.insert(e.req_attr("name")?, formula_of(e)?);
+        }
+        _ => {
+            for c in e.children.iter() {
+                collect(c, defs, q)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn eval_expr(
+    e: &Element,
+    q: &Quantities,
+    mission_time: Option<f64>,
+    depth: usize,
+) -> Result<f64, String> {
+    if depth > q.parameters.len() + 1 {
+        return Err(format!("line {}: circular parameter definition", e.line));
+    }
+    let args = |e: &Element| -> Result<Vec<f64>, String> {
+        e.args()
+            .map(|x| eval_expr(x, q, mission_time, depth))
+            .collect()
+    };
+    let nargs = |e: &Element, n: usize| -> Result<Vec<f64>, String> {
+        let v = args(e)?;
+        if v.len() != n {
+            return Err(format!("line {}: <{}> takes {} arguments", e.line, e.name, n));
+        }
+        Ok(v)
+    };
+    match e.name.as_str() {
+        "float" | "int" => parse_f64(e),
+        "bool" => Ok(if parse_bool(e)? { 1.0 } else { 0.0 }),
+        "system-mission-time" => mission_time.ok_or_else(|| {
+            format!("line {}: <system-mission-time> used but no mission time given", e.line)
+        }),
+        "parameter" => {
+            let name = e.req_attr("name")?;
+            match q.parameters.get(name) {
+                Some(p) => eval_expr(p, q, mission_time, depth + 1),
+                None => Err(format!("line {}: unknown parameter {}", e.line, name)),
+            }
+        }
+        "exponential" => {
+            let v = nargs(e, 2)?;
+            Ok(1.0 - (-v[0] * v[1]).exp())
+        }
+        "add" => Ok(args(e)?.iter().sum()),
+        "mul" => Ok(args(e)?.iter().product()),
+        "sub" => {
+            let v = args(e)?;
+            match v.split_first() {
+                Some((first, rest)) => Ok(rest.iter().fold(*first, |acc, x| acc - x)),
+                None => Err(format!("line {}: <sub> needs arguments", e.line)),
+            }
+        }
+        "div" => {
+            let v = args(e)?;
+            match v.split_first() {
+                Some((first, rest)) => Ok(rest.iter().fold(*first, |acc, x| acc / x)),
+                None => Err(format!("line {}: <div> needs arguments", e.line)),
+            }
+        }
+        "neg" => Ok(-nargs(e, 1)?[0]),
+        x => Err(format!("line {}: unsupported expression <{}>", e.line, x)),
+    }
+}
+
+/// Imports an Open-PSA MEF fault tree into `mgr`.
+///
+/// `mission_time` is the value of `<system-mission-time>` (needed only if an expression uses
+/// it). The top gate is the first defined gate that no other gate references; every other
+/// gate is available in [`MefModel::gates`](FaultTree::gates).
+///
+/// # Errors
+///
+/// Returns `Err` (with the line number) on malformed XML, unknown gates, house events or
+/// parameters, circular gate definitions, unsupported connectives or expressions, and a
+/// document without gates.
+///
+/// # Example
+///
+/// ```
+/// use bss::prelude::*;
+///
+/// let xml = r#"
+/// <opsa-mef>
+///   <define-fault-tree name="FT">
+///     <define-gate name="TOP">
+///       <atleast min="2">
+///         <basic-event name="A"/> <basic-event name="B"/> <basic-event name="C"/>
+///       </atleast>
+///     </define-gate>
+///   </define-fault-tree>
+///   <model-data>
+///     <define-basic-event name="A"><float value="0.1"/></define-basic-event>
+///     <define-basic-event name="B"><float value="0.1"/></define-basic-event>
+///     <define-basic-event name="C"><float value="0.1"/></define-basic-event>
+///   </model-data>
+/// </opsa-mef>"#;
+///
+/// let mut bss = BssMgr::new();
+/// let ft = bss.read_mef(xml, None).unwrap();
+/// assert_eq!(ft.top_gate, "TOP");
+/// let p = ft.top.prob(&ft.probs, &[true]);
+/// assert!((p - 0.028).abs() < 1e-12);
+/// assert_eq!(bss.minpath(&ft.top).unwrap().count(&[true]), 3);
+/// ```
+pub fn read_mef(mgr: &mut BddMgr, xml: &str, mission_time: Option<f64>) -> Result<MefModel, RelibError> {
+    let root = parse_xml(xml)?;
+    let mut defs = Definitions::default();
+    let mut q = Quantities::default();
+    collect(&root, &mut defs, &mut q)?;
+    for (name, e) in q.basic_events.iter() {
+        if let Some(expr) = e.args().next() {
+            let p = eval_expr(expr, &q, mission_time, 0)?;
+            defs.probs.insert(name.to_string(), p);
+        }
+    }
+    fault_tree::build(mgr, defs, None)
+        .map_err(RelibError::Invalid)
+}
+
+// --- BDD -> MEF --------------------------------------------------------------------------
+
+/// Writes `node` as an Open-PSA MEF fault tree named `name`.
+///
+/// Every BDD node on variable `x` becomes a gate `x·high + ¬x·low` (terminal children
+/// simplified away); the top gate is `name`. Each variable becomes a `define-basic-event`,
+/// with a `float` probability when `pv` has one. The result reads back with [`read_mef`] to
+/// the same structure function.
+pub fn write_mef(node: &BddNode, name: &str, pv: &HashMap<String, f64>) -> String {
+    let gate_name = |n: &BddNode, top: bool| {
+        if top {
+            name.to_string()
+        } else {
+            format!("{}-n{}", name, n.get_id())
+        }
+    };
+    let mut gates = String::new();
+    let mut events: Vec<String> = Vec::new();
+    let mut seen = HashSet::new();
+    let mut stack = vec![(node.clone(), true)];
+    while let Some((n, top)) = stack.pop() {
+        if !seen.insert(n.get_id()) {
+            continue;
+        }
+        let formula = match n.get_children() {
+            None => format!("<constant value=\"{}\"/>", n.is_one()),
+            Some((low, high)) => {
+                let x = n.get_label().unwrap();
+                if !events.contains(&x) {
+                    events.push(x.clone());
+                }
+                let var = format!("<basic-event name=\"{}\"/>", escape(&x));
+                let mut term = |child: &BddNode, lit: String| {
+                    if child.is_zero() {
+                        None
+                    } else if child.is_one() {
+                        Some(lit)
+                    } else {
+                        stack.push((child.clone(), false));
+                        Some(format!(
+                            "<and>{}<gate name=\"{}\"/></and>",
+                            lit,
+                            escape(&gate_name(child, false))
+                        ))
+                    }
+                };
+                let hi = term(&high, var.clone());
+                let lo = term(&low, format!("<not>{}</not>", var));
+                match (hi, lo) {
+                    (Some(a), Some(b)) => format!("<or>{}{}</or>", a, b),
+                    (Some(a), None) | (None, Some(a)) => a,
+                    (None, None) => "<constant value=\"false\"/>".to_string(),
+                }
+            }
+        };
+        writeln!(
+            gates,
+            "    <define-gate name=\"{}\">\n      {}\n    </define-gate>",
+            escape(&gate_name(&n, top)),
+            formula
+        )
+        .unwrap();
+    }
+    let mut out = String::new();
+    writeln!(out, "<?xml version=\"1.0\"?>\n<opsa-mef>").unwrap();
+    writeln!(out, "  <define-fault-tree name=\"{}\">", escape(name)).unwrap();
+    out.push_str(&gates);
+    writeln!(out, "  </define-fault-tree>\n  <model-data>").unwrap();
+    for x in events.iter() {
+        match pv.get(x) {
+            Some(p) => writeln!(
+                out,
+                "    <define-basic-event name=\"{}\"><float value=\"{:e}\"/></define-basic-event>",
+                escape(x),
+                p
+            ),
+            None => writeln!(out, "    <define-basic-event name=\"{}\"/>", escape(x)),
+        }
+        .unwrap();
+    }
+    writeln!(out, "  </model-data>\n</opsa-mef>").unwrap();
+    out
+}
@@ -0,0 +1,275 @@
+//! Phased-mission systems.
+//!
+//! A phased mission runs through consecutive phases (take-off, cruise, landing, …), each
+//! with its own failure logic and component failure probabilities. Components are not
+//! repaired: a component that fails in phase `j` stays failed in every later phase, and the
+//! mission fails if the system fails in any phase.
+//!
+//! [`PhasedMission::new`] defines one variable per component and phase, `c.phase`, meaning
+//! "`c` has failed by the end of `phase`" (`true` = failed). The copies of a component are
+//! defined consecutively, first phase first, so they are adjacent in the variable order with
+//! the last phase on top: the backward phase-dependent ordering of the phase algebra. A
+//! phase failure function is written over the copies of its phase, either directly with
+//! [`BddMgr::parse`] on the `c.phase` names or once over the component variables and moved
+//! to a phase with [`PhasedMission::in_phase`]. [`PhasedMission::mission_failure`] joins the
+//! phase failure functions.
+//!
+//! The copies of one component are dependent (`c.1 ⇒ c.2 ⇒ …`), so the ordinary `prob`,
+//! which treats variables as independent, does not apply to the mission function.
+//! [`PhasedMission::unreliability`] evaluates it under the non-repair constraint instead:
+//! while the traversal crosses the copies of a component it narrows the range of phases
+//! the failure can fall in, and when it leaves them the path is weighted with the
+//! probability of that range. Inconsistent paths (failed by phase 1 but working at the end
+//! of phase 2) get an empty range and weight zero.
+
+use std::collections::HashMap;
+use std::ops::{Add, Mul, Sub};
+
+use bddcore::prelude::*;
+
+use crate::bdd::{BddMgr, BddNode};
+
+/// Per-phase copies of the component variables of a phased mission.
+#[derive(Debug, Clone)]
+pub struct PhasedMission {
+    components: Vec<String>,
+    phases: Vec<String>,
+    vars: Vec<Vec<BddNode>>,
+    index: HashMap<String, (usize, usize)>,
+}
+
+/// The component being crossed and the range `lo..=hi` of phases its failure can fall in
+/// (`hi == phases` = it survives the mission).
+type Pending = Option<(usize, usize, usize)>;
+
+struct Eval<'a, T> {
+    mission: &'a PhasedMission,
+    dist: Vec<Vec<T>>,
+    memo: HashMap<(NodeId, Pending), T>,
+}
+
+impl<T> Eval<'_, T>
+where
+    T: Add<Output = T> + Mul<Output = T> + Copy + From<f64>,
+{
+    fn range(&self, c: usize, lo: usize, hi: usize) -> T {
+        self.dist[c][lo + 1..=hi]
+            .iter()
+            .fold(self.dist[c][lo], |acc, &p| acc + p)
+    }
+
+    fn rec(&mut self, f: &BddNode, pending: Pending) -> Result<T, RelibError> {
+        let key = (f.get_id(), pending);
+        if let Some(&x) = self.memo.get(&key) {
+            return Ok(x);
+        }
+        let var = match f.get_label() {
+            Some(label) => Some(
+                *self
+                    .mission
+                    .index
+                    .get(&label)
+                    .ok_or_else(|| RelibError::Invalid(format!("variable {} is not a phase copy of the mission", label)))?,
+            ),
+            None => None,
+        };
+        let result = match (pending, var) {
+            (Some((g, lo, hi)), var) if var.map(|(c, _)| c) != Some(g) => {
+                self.range(g, lo, hi) * self.rec(f, None)?
+            }
+            (_, None) => T::from(if f.is_one() { 1.0 } else { 0.0 }),
+            (pending, Some((c, j))) => {
+                let (lo, hi) = pending.map_or((0, self.mission.phases.len()), |(_, lo, hi)| (lo, hi));
+                let (f0, f1) = f.get_children().unwrap();
+                let mut x = T::from(0.0);
+                if lo <= j {
+                    x = x + self.rec(&f1, Some((c, lo, hi.min(j))))?;
+                }
+                if j < hi {
+                    x = x + self.rec(&f0, Some((c, lo.max(j + 1), hi)))?;
+                }
+                x
+            }
+        };
+        self.memo.insert(key, result);
+        Ok(result)
+    }
+}
+
+impl PhasedMission {
+    /// Defines the variables `c.phase` for every component `c` and phase, in the order
+    /// described in the [module docs](self).
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if there are no phases, a name is repeated, or a `c.phase` variable is
+    /// already defined in `mgr` (the copies of a component must be adjacent in the order).
+    pub fn new(mgr: &mut BddMgr, components: &[&str], phases: &[&str]) -> Result<Self, RelibError> {
+        if phases.is_empty() {
+            return Err(RelibError::Invalid("a phased mission needs at least one phase".to_string()));
+        }
+        let defined = mgr.get_varorder();
+        let mut index = HashMap::new();
+        let mut vars = Vec::new();
+        for (c, comp) in components.iter().enumerate() {
+            let mut copies = Vec::new();
+            for (j, phase) in phases.iter().enumerate() {
+                let name = format!("{}.{}", comp, phase);
+                if defined.contains(&name) || index.insert(name.clone(), (c, j)).is_some() {
+                    return Err(RelibError::Invalid(format!("variable {} is already defined", name)));
+                }
+                copies.push(mgr.defvar(&name));
+            }
+            vars.push(copies);
+        }
+        Ok(PhasedMission {
+            components: components.iter().map(|x| x.to_string()).collect(),
+            phases: phases.iter().map(|x| x.to_string()).collect(),
+            vars,
+            index,
+        })
+    }
+
+    pub fn components(&self) -> &[String] {
+        &self.components
+    }
+
+    pub fn phases(&self) -> &[String] {
+        &self.phases
+    }
+
+    /// The variable "`component` has failed by the end of `phase`".
+    pub fn var(&self, component: &str, phase: &str) -> Option<BddNode> {
+        let c = self.components.iter().position(|x| x == component)?;
+        let j = self.phases.iter().position(|x| x == phase)?;
+        Some(self.vars[c][j].clone())
+    }
+
+    /// `f` with every component variable `c` replaced by its copy `c.phase`; phase copies
+    /// already in `f` are kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if `phase` is not a phase of the mission, or if `f` depends on a
+    /// variable that is neither a component nor a phase copy, which
+    /// [`unreliability`](Self::unreliability) could not evaluate.
+    pub fn in_phase(&self, mgr: &mut BddMgr, f: &BddNode, phase: &str) -> Result<BddNode, RelibError> {
+        let j = self
+            .phases
+            .iter()
+            .position(|x| x == phase)
+            .ok_or_else(|| RelibError::Invalid(format!("unknown phase {}", phase)))?;
+        let mut memo = HashMap::new();
+        self.substitute(mgr, f, j, &mut memo)
+    }
+
+    fn substitute(
+        &self,
+        mgr: &mut BddMgr,
+        f: &BddNode,
+        j: usize,
+        memo: &mut HashMap<NodeId, BddNode>,
+    ) -> Result<BddNode, RelibError> {
+        if let Some(x) = memo.get(&f.get_id()) {
+            return Ok(x.clone());
+        }
+        let result = match (f.get_label(), f.get_children()) {
+            (Some(label), Some((f0, f1))) => {
+                let x = match self.components.iter().position(|c| *c == label) {
+                    Some(c) => self.vars[c][j].clone(),
+                    None if self.index.contains_key(&label) => mgr.defvar(&label),
+                    None => return Err(RelibError::Invalid(format!("variable {} is not a component of the mission", label))),
+                };
+                let g0 = self.substitute(mgr, &f0, j, memo)?;
+                let g1 = self.substitute(mgr, &f1, j, memo)?;
+                x.ite(&g1, &g0)
+            }
+            _ => f.clone(),
+        };
+        memo.insert(f.get_id(), result.clone());
+        Ok(result)
+    }
+
+    /// The mission failure function: the OR of the phase failure functions, given in phase
+    /// order over the `c.phase` variables.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` unless there is exactly one function per phase.
+    pub fn mission_failure(&self, mgr: &BddMgr, phase_failures: &[BddNode]) -> Result<BddNode, RelibError> {
+        if phase_failures.len() != self.phases.len() {
+            return Err(RelibError::Invalid(format!(
+                "expected {} phase failure functions, got {}",
+                self.phases.len(),
+                phase_failures.len()
+            )));
+        }
+        Ok(mgr.or(phase_failures))
+    }
+
+    /// Probability that `mission` (a function of the `c.phase` variables, usually from
+    /// [`mission_failure`](Self::mission_failure)) is true when no component is repaired.
+    ///
+    /// `q[c][j]` is the probability that component `c` fails during phase `j` given that it
+    /// works at the start of the phase (`1 − exp(−λⱼ·τⱼ)` for a constant failure rate
+    /// `λⱼ` over a phase of duration `τⱼ`).
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if a component has no entry in `q`, an entry does not have one value per
+    /// phase, or `mission` depends on a variable that is not a phase copy of this mission.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bss::prelude::*;
+    /// use std::collections::HashMap;
+    ///
+    /// // Both engines are needed for take-off, either one for cruise.
+    /// let mut bss = BssMgr::new();
+    /// let pm = bss.phased_mission(&["E1", "E2"], &["takeoff", "cruise"]).unwrap();
+    /// let takeoff = bss.parse("E1.takeoff | E2.takeoff").unwrap();
+    /// let cruise = bss.parse("E1.cruise & E2.cruise").unwrap();
+    /// let mission = pm.mission_failure(bss.bdd(), &[takeoff, cruise]).unwrap();
+    ///
+    /// let mut q = HashMap::new();
+    /// q.insert("E1".to_string(), vec![0.1_f64, 0.2]);
+    /// q.insert("E2".to_string(), vec![0.1, 0.2]);
+    /// let u = pm.unreliability(&mission, &q).unwrap();
+    /// // Take-off fails with 1 − 0.9², otherwise both engines fail in cruise: (0.9² · 0.2²).
+    /// assert!((u - (1.0 - 0.81 + 0.81 * 0.04)).abs() < 1e-12);
+    /// ```
+    pub fn unreliability<T>(&self, mission: &BddNode, q: &HashMap<String, Vec<T>>) -> Result<T, RelibError>
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Copy + From<f64>,
+    {
+        let mut dist = Vec::new();
+        for comp in self.components.iter() {
+            let qc = q
+                .get(comp)
+                .ok_or_else(|| RelibError::MissingProbability(comp.clone()))?;
+            if qc.len() != self.phases.len() {
+                return Err(RelibError::Invalid(format!(
+                    "{} has {} phase failure probabilities, expected {}",
+                    comp,
+                    qc.len(),
+                    self.phases.len()
+                )));
+            }
+            let mut survive = T::from(1.0);
+            let mut d = Vec::new();
+            for &p in qc.iter() {
+                d.push(survive * p);
+                survive = survive * (T::from(1.0) - p);
+            }
+            d.push(survive);
+            dist.push(d);
+        }
+        let mut eval = Eval {
+            mission: self,
+            dist,
+            memo: HashMap::new(),
+        };
+        eval.rec(mission, None)
+    }
+}
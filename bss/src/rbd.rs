@@ -0,0 +1,192 @@
+//! Reliability block diagrams.
+//!
+//! A [`Block`] is a component or a composition of blocks: series, parallel, k-out-of-n,
+//! the five-block bridge, a standby group, or a reference to a named sub-diagram. An [`Rbd`]
+//! holds the named sub-diagrams and compiles a block into the structure function of the
+//! system (`true` = working) through a [`BddMgr`]:
+//!
+//! | block | structure function |
+//! |---|---|
+//! | `component(x)` | the variable `x` |
+//! | `series([b…])` | `∧ b` |
+//! | `parallel([b…])` | `∨ b` |
+//! | `kofn(k, [b…])` | at least `k` of the `b` work ([`BddMgr::kofn`]) |
+//! | `bridge(a, b, c, d, e)` | `a`, `b` from the input, `d`, `e` to the output, `c` across: `c·(a+b)(d+e) + ¬c·(ad + be)` |
+//! | `standby(active, [spare…])` | placeholder, compiled as `parallel`: switching and dormancy are not modelled in a static structure function |
+//! | `sub(name)` | the sub-diagram defined as `name`, compiled once per call |
+
+use std::collections::{HashMap, HashSet};
+
+use common::error::RelibError;
+
+use crate::bdd::{BddMgr, BddNode};
+
+/// A block of a reliability block diagram; see the [module docs](self).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    Component(String),
+    Series(Vec<Block>),
+    Parallel(Vec<Block>),
+    KofN(usize, Vec<Block>),
+    Bridge(Box<[Block; 5]>),
+    Standby(Box<Block>, Vec<Block>),
+    Sub(String),
+}
+
+impl Block {
+    pub fn component(name: &str) -> Block {
+        Block::Component(name.to_string())
+    }
+
+    pub fn series(blocks: Vec<Block>) -> Block {
+        Block::Series(blocks)
+    }
+
+    pub fn parallel(blocks: Vec<Block>) -> Block {
+        Block::Parallel(blocks)
+    }
+
+    pub fn kofn(k: usize, blocks: Vec<Block>) -> Block {
+        Block::KofN(k, blocks)
+    }
+
+    /// Bridge with `a`, `b` on the input side, `d`, `e` on the output side (`a`–`d` and
+    /// `b`–`e` are the two straight paths) and `c` across the middle.
+    pub fn bridge(a: Block, b: Block, c: Block, d: Block, e: Block) -> Block {
+        Block::Bridge(Box::new([a, b, c, d, e]))
+    }
+
+    pub fn standby(active: Block, spares: Vec<Block>) -> Block {
+        Block::Standby(Box::new(active), spares)
+    }
+
+    pub fn sub(name: &str) -> Block {
+        Block::Sub(name.to_string())
+    }
+}
+
+/// Named sub-diagrams and the compiler for [`Block`]s.
+#[derive(Debug, Clone, Default)]
+pub struct Rbd {
+    subs: HashMap<String, Block>,
+}
+
+struct Compiler<'a> {
+    rbd: &'a Rbd,
+    mgr: &'a mut BddMgr,
+    done: HashMap<&'a str, BddNode>,
+    visiting: HashSet<&'a str>,
+}
+
+impl<'a> Compiler<'a> {
+    fn all(&mut self, what: &str, blocks: &'a [Block]) -> Result<Vec<BddNode>, RelibError> {
+        if blocks.is_empty() {
+            return Err(RelibError::Invalid(format!("{} block has no children", what)));
+        }
+        blocks.iter().map(|b| self.compile(b)).collect()
+    }
+
+    fn compile(&mut self, block: &'a Block) -> Result<BddNode, RelibError> {
+        match block {
+            Block::Component(x) => Ok(self.mgr.defvar(x)),
+            Block::Series(bs) => {
+                let v = self.all("series", bs)?;
+                Ok(self.mgr.and(&v))
+            }
+            Block::Parallel(bs) => {
+                let v = self.all("parallel", bs)?;
+                Ok(self.mgr.or(&v))
+            }
+            Block::KofN(k, bs) => {
+                let v = self.all("k-out-of-n", bs)?;
+                if *k == 0 || *k > v.len() {
+                    return Err(RelibError::Invalid(format!("k-out-of-n block needs 1 <= k <= {}, got {}", v.len(), k)));
+                }
+                Ok(self.mgr.kofn(*k, &v))
+            }
+            Block::Bridge(bs) => {
+                let v = self.all("bridge", &bs[..])?;
+                let (a, b, c, d, e) = (&v[0], &v[1], &v[2], &v[3], &v[4]);
+                let through = a.or(b).and(&d.or(e));
+                let straight = a.and(d).or(&b.and(e));
+                Ok(c.ite(&through, &straight))
+            }
+            Block::Standby(active, spares) => {
+                let mut v = vec![self.compile(active)?];
+                for s in spares {
+                    v.push(self.compile(s)?);
+                }
+                Ok(self.mgr.or(&v))
+            }
+            Block::Sub(name) => {
+                if let Some(x) = self.done.get(name.as_str()) {
+                    return Ok(x.clone());
+                }
+                let (key, body) = self
+                    .rbd
+                    .subs
+                    .get_key_value(name)
+                    .ok_or_else(|| RelibError::Invalid(format!("unknown sub-diagram {}", name)))?;
+                if !self.visiting.insert(key) {
+                    return Err(RelibError::Invalid(format!("sub-diagram {} contains itself", name)));
+                }
+                let x = self.compile(body)?;
+                self.visiting.remove(key.as_str());
+                self.done.insert(key, x.clone());
+                Ok(x)
+            }
+        }
+    }
+}
+
+impl Rbd {
+    pub fn new() -> Self {
+        Rbd::default()
+    }
+
+    /// Defines the sub-diagram `name`, usable as [`Block::sub`]`(name)` in other blocks.
+    pub fn define(&mut self, name: &str, block: Block) -> Result<(), RelibError> {
+        if self.subs.contains_key(name) {
+            return Err(RelibError::Invalid(format!("sub-diagram {} is already defined", name)));
+        }
+        self.subs.insert(name.to_string(), block);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Block> {
+        self.subs.get(name)
+    }
+
+    /// Compiles `block` into its structure function.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if a [`Block::sub`] names an undefined or self-containing sub-diagram,
+    /// a composite block has no children, or a k-out-of-n block has `k` outside `1..=n`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bss::prelude::*;
+    ///
+    /// let mut rbd = Rbd::new();
+    /// rbd.define("pumps", Block::kofn(2, vec![
+    ///     Block::component("P1"), Block::component("P2"), Block::component("P3"),
+    /// ])).unwrap();
+    /// let system = Block::series(vec![Block::component("V"), Block::sub("pumps")]);
+    ///
+    /// let mut bss = BssMgr::new();
+    /// let f = bss.compile_rbd(&rbd, &system).unwrap();
+    /// let g = bss.rpn("V P1 P2 & P1 P3 & | P2 P3 & | &").unwrap();
+    /// assert_eq!(f.get_id(), g.get_id());
+    /// ```
+    pub fn compile(&self, mgr: &mut BddMgr, block: &Block) -> Result<BddNode, RelibError> {
+        let mut c = Compiler {
+            rbd: self,
+            mgr,
+            done: HashMap::new(),
+            visiting: HashSet::new(),
+        };
+        c.compile(block)
+    }
+}
@@ -184,7 +184,27 @@ impl ZddNode {
     }
 
     pub fn get_mgr(&self) -> Rc<RefCell<ZddManager>> {
-        self.parent.upgrade().unwrap()
+        self.try_get_mgr().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`get_mgr`](Self::get_mgr): `Err` if the manager has been dropped.
+    pub fn try_get_mgr(&self) -> Result<Rc<RefCell<ZddManager>>, RelibError> {
+        self.mgr_of(&[])
+    }
+
+    /// The manager of `self` and `others`.
+    fn mgr_of(&self, others: &[&ZddNode]) -> Result<Rc<RefCell<ZddManager>>, RelibError> {
+        let zdd = self.parent.upgrade().ok_or(RelibError::ManagerDropped)?;
+        for x in others {
+            if !x.parent.ptr_eq(&self.parent) {
+                return Err(if x.parent.strong_count() == 0 {
+                    RelibError::ManagerDropped
+                } else {
+                    RelibError::ManagerMismatch
+                });
+            }
+        }
+        Ok(zdd)
     }
 
     pub fn get_id(&self) -> NodeId {
@@ -192,46 +212,51 @@ impl ZddNode {
     }
 
     pub fn get_header(&self) -> Option<HeaderId> {
-        let mgr = self.parent.upgrade().unwrap();
+        let mgr = self.get_mgr();
         let zdd = mgr.borrow();
         zdd.get_node(&self.node)?.headerid()
     }
 
     pub fn get_level(&self) -> Option<Level> {
-        let mgr = self.parent.upgrade().unwrap();
+        let mgr = self.get_mgr();
         let zdd = mgr.borrow();
         let hid = zdd.get_node(&self.node)?.headerid()?;
         Some(zdd.get_header(&hid)?.level())
     }
 
     pub fn get_label(&self) -> Option<String> {
-        let mgr = self.parent.upgrade().unwrap();
+        let mgr = self.get_mgr();
         let zdd = mgr.borrow();
         let hid = zdd.get_node(&self.node)?.headerid()?;
         Some(zdd.get_header(&hid)?.label().to_string())
     }
 
     pub fn get_children(&self) -> Option<(ZddNode, ZddNode)> {
-        let mgr = self.parent.upgrade().unwrap();
+        self.try_get_children().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`get_children`](Self::get_children): `Err` if the manager has been dropped.
+    pub fn try_get_children(&self) -> Result<Option<(ZddNode, ZddNode)>, RelibError> {
+        let mgr = self.mgr_of(&[])?;
         let zdd = mgr.borrow();
-        match zdd.get_node(&self.node)? {
-            Node::Zero | Node::One | Node::Undet => None,
-            Node::NonTerminal(fnode) => {
+        Ok(match zdd.get_node(&self.node) {
+            Some(Node::NonTerminal(fnode)) => {
                 let f0 = ZddNode::from_weak(self.parent.clone(), self.gc.clone(), fnode.edge(0));
                 let f1 = ZddNode::from_weak(self.parent.clone(), self.gc.clone(), fnode.edge(1));
                 Some((f0, f1))
             }
-        }
+            _ => None,
+        })
     }
 
     pub fn is_zero(&self) -> bool {
-        let mgr = self.parent.upgrade().unwrap();
+        let mgr = self.get_mgr();
         let zdd = mgr.borrow();
         matches!(zdd.get_node(&self.node).unwrap(), Node::Zero)
     }
 
     pub fn is_one(&self) -> bool {
-        let mgr = self.parent.upgrade().unwrap();
+        let mgr = self.get_mgr();
         let zdd = mgr.borrow();
         matches!(zdd.get_node(&self.node).unwrap(), Node::One)
     }
@@ -243,52 +268,84 @@ impl ZddNode {
     /// Graphviz source for this family's diagram. The `0` terminal (the empty family) and the
     /// edges into it are omitted; the 0-edge is still drawn wherever it leads somewhere.
     pub fn dot(&self) -> String {
-        let zdd = self.parent.upgrade().unwrap();
+        self.try_dot().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`dot`](Self::dot): `Err` if the manager has been dropped.
+    pub fn try_dot(&self) -> Result<String, RelibError> {
+        let zdd = self.mgr_of(&[])?;
         let result = zdd.borrow().dot_string(&self.node);
-        result
+        Ok(result)
+    }
+
+    /// Fallible [`union`](Self::union): `Err` if the manager has been dropped or `other`
+    /// belongs to another manager; likewise for the other `try_*` set operations.
+    pub fn try_union(&self, other: &ZddNode) -> Result<ZddNode, RelibError> {
+        let zdd = self.mgr_of(&[other])?;
+        let result = zdd.borrow_mut().union(self.node, other.node);
+        Ok(self.rewrap(&zdd, result))
+    }
+
+    pub fn try_intersect(&self, other: &ZddNode) -> Result<ZddNode, RelibError> {
+        let zdd = self.mgr_of(&[other])?;
+        let result = zdd.borrow_mut().intersect(self.node, other.node);
+        Ok(self.rewrap(&zdd, result))
+    }
+
+    pub fn try_setdiff(&self, other: &ZddNode) -> Result<ZddNode, RelibError> {
+        let zdd = self.mgr_of(&[other])?;
+        let result = zdd.borrow_mut().setdiff(self.node, other.node);
+        Ok(self.rewrap(&zdd, result))
+    }
+
+    pub fn try_product(&self, other: &ZddNode) -> Result<ZddNode, RelibError> {
+        let zdd = self.mgr_of(&[other])?;
+        let result = zdd.borrow_mut().product(self.node, other.node);
+        Ok(self.rewrap(&zdd, result))
+    }
+
+    pub fn try_divide(&self, other: &ZddNode) -> Result<ZddNode, RelibError> {
+        let zdd = self.mgr_of(&[other])?;
+        let result = zdd.borrow_mut().divide(self.node, other.node);
+        Ok(self.rewrap(&zdd, result))
     }
 
     /// Union of two set families.
     pub fn union(&self, other: &ZddNode) -> ZddNode {
-        let zdd = self.parent.upgrade().unwrap();
-        let result = zdd.borrow_mut().union(self.node, other.node);
-        self.rewrap(&zdd, result)
+        self.try_union(other).unwrap_or_else(|e| panic!("{}", e))
     }
 
     /// Intersection of two set families.
     pub fn intersect(&self, other: &ZddNode) -> ZddNode {
-        let zdd = self.parent.upgrade().unwrap();
-        let result = zdd.borrow_mut().intersect(self.node, other.node);
-        self.rewrap(&zdd, result)
+        self.try_intersect(other).unwrap_or_else(|e| panic!("{}", e))
     }
 
     /// Set difference (`self \ other`).
     pub fn setdiff(&self, other: &ZddNode) -> ZddNode {
-        let zdd = self.parent.upgrade().unwrap();
-        let result = zdd.borrow_mut().setdiff(self.node, other.node);
-        self.rewrap(&zdd, result)
+        self.try_setdiff(other).unwrap_or_else(|e| panic!("{}", e))
     }
 
     /// Family product (all pairwise unions of a set from each family).
     pub fn product(&self, other: &ZddNode) -> ZddNode {
-        let zdd = self.parent.upgrade().unwrap();
-        let result = zdd.borrow_mut().product(self.node, other.node);
-        self.rewrap(&zdd, result)
+        self.try_product(other).unwrap_or_else(|e| panic!("{}", e))
     }
 
     /// Family quotient (`self / other`).
     pub fn divide(&self, other: &ZddNode) -> ZddNode {
-        let zdd = self.parent.upgrade().unwrap();
-        let result = zdd.borrow_mut().divide(self.node, other.node);
-        self.rewrap(&zdd, result)
+        self.try_divide(other).unwrap_or_else(|e| panic!("{}", e))
     }
 
     /// Number of sets in the family (default `ss = [true]`).
     pub fn count(&self, ss: &[bool]) -> u64 {
-        let mgr = self.parent.upgrade().unwrap();
+        self.try_count(ss).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`count`](Self::count): `Err` if the manager has been dropped.
+    pub fn try_count(&self, ss: &[bool]) -> Result<u64, RelibError> {
+        let mgr = self.mgr_of(&[])?;
         let zdd = mgr.borrow();
         let mut cache = BddHashMap::default();
-        zdd_count::zdd_count(&zdd, ss, self.node, &mut cache)
+        Ok(zdd_count::zdd_count(&zdd, ss, self.node, &mut cache))
     }
 
     /// Enumerate the sets of the family as lists of labels.
@@ -297,11 +354,16 @@ impl ZddNode {
     }
 
     pub fn size(&self) -> (u64, u64, u64) {
-        let mgr = self.parent.upgrade().unwrap();
+        self.try_size().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`size`](Self::size): `Err` if the manager has been dropped.
+    pub fn try_size(&self) -> Result<(u64, u64, u64), RelibError> {
+        let mgr = self.mgr_of(&[])?;
         let zdd = mgr.borrow();
         let mut cache = BddHashSet::default();
         let (nn, nv, ne) = zdd_count::node_count(&zdd, self.node, &mut cache);
-        (nn, nv, ne - 1)
+        Ok((nn, nv, ne - 1))
     }
 }
 
@@ -284,3 +284,933 @@ fn test_zdd_standalone() {
     assert_eq!(sorted_sets(&prod), sets(&[&["x", "y"]]));
     assert_eq!(sorted_sets(&prod.divide(&z.singleton("y"))), sets(&[&["x"]]));
 }
+
+#[test]
+fn test_prob_batch_matches_prob() {
+    let mut bss = BddMgr::new();
+    let x = bss.defvar("x");
+    let y = bss.defvar("y");
+    let z = bss.defvar("z");
+    let f = x.and(&y).or(&z.not());
+
+    let params: Vec<HashMap<String, f64>> = [(0.2, 0.3, 0.6), (0.9, 0.1, 0.5), (0.0, 1.0, 0.25)]
+        .iter()
+        .map(|&(a, b, c)| {
+            let mut pv = HashMap::new();
+            pv.insert("x".to_string(), a);
+            pv.insert("y".to_string(), b);
+            pv.insert("z".to_string(), c);
+            pv
+        })
+        .collect();
+    for ss in [&[true][..], &[false][..]] {
+        let batch = f.prob_batch(&params, ss);
+        for (pv, got) in params.iter().zip(batch.iter()) {
+            assert!((f.prob(pv, ss) - got).abs() < 1e-12);
+        }
+    }
+
+    // Columnar variant: parameters indexed by level (the defvar order).
+    assert_eq!(bss.get_varorder(), vec!["x", "y", "z"]);
+    let cols: Vec<Vec<f64>> = params
+        .iter()
+        .map(|pv| vec![pv["x"], pv["y"], pv["z"]])
+        .collect();
+    assert_eq!(f.prob_batch_levels(&cols, &[true]), f.prob_batch(&params, &[true]));
+}
+
+#[test]
+fn test_ccf_beta_factor() {
+    let mut bss = BssMgr::new();
+    bss.defccf("pumps", &["A", "B"], CcfModel::BetaFactor(0.1)).unwrap();
+    let c = bss.defvar("C");
+    // Failure logic: both pumps fail, or C fails.
+    let top = bss.rpn("A B & C |").unwrap();
+
+    let mut pv = HashMap::new();
+    pv.insert("A".to_string(), 0.01_f64);
+    pv.insert("B".to_string(), 0.01);
+    pv.insert("C".to_string(), 0.001);
+    let q = bss.ccf_prob(&pv);
+    assert!((q["pumps[A]"] - 0.009).abs() < 1e-15);
+    assert!((q["pumps[A,B]"] - 0.001).abs() < 1e-15);
+
+    let qi: f64 = 0.009;
+    let want = 1.0 - (1.0 - qi * qi) * (1.0 - 0.001) * (1.0 - 0.001);
+    assert!((top.prob(&q, &[true]) - want).abs() < 1e-15);
+
+    // The CCF event is a single-event cut set of the failure logic.
+    let cuts = bss.minpath(&top).expect("coherent");
+    assert_eq!(
+        sorted_sets(&cuts),
+        sets(&[&["C"], &["pumps[A,B]"], &["pumps[A]", "pumps[B]"]])
+    );
+    let imp = top.bmeas(&q, &[true]);
+    assert!(imp["pumps[A,B]"] > imp["pumps[A]"]);
+    drop(c);
+
+    // Members must be declared before their first use.
+    assert!(bss.defccf("late", &["C", "D"], CcfModel::BetaFactor(0.1)).is_err());
+    assert!(bss.defccf("bad", &["E", "F"], CcfModel::AlphaFactor(vec![0.9])).is_err());
+}
+
+#[test]
+fn test_ccf_models_preserve_total_probability() {
+    let models = [
+        CcfModel::BetaFactor(0.05),
+        CcfModel::AlphaFactor(vec![0.95, 0.03, 0.015, 0.005]),
+        CcfModel::Mgl(vec![0.1, 0.3, 0.5]),
+    ];
+    for model in models {
+        let mut bss = BddMgr::new();
+        bss.defccf("g", &["A", "B", "C", "D"], model.clone()).unwrap();
+        let mut pv = HashMap::new();
+        for x in ["A", "B", "C", "D"] {
+            pv.insert(x.to_string(), 1.0e-3_f64);
+        }
+        let q = bss.ccf_prob(&pv);
+        let group = &bss.ccf_groups()[0];
+        // Sum_k C(m-1,k-1) Q_k = Q_t for every member.
+        for (i, x) in group.members().iter().enumerate() {
+            let total: f64 = group
+                .events()
+                .iter()
+                .filter(|s| s.contains(&i))
+                .map(|s| q[&group.event_name(s)])
+                .sum();
+            assert!((total - pv[x]).abs() < 1e-15, "{model:?}: {x} total {total}");
+        }
+        // All four failing is at least the all-member CCF event.
+        let all = bss.rpn("A B & C & D &").unwrap();
+        assert!(all.prob(&q, &[true]) >= q["g[A,B,C,D]"]);
+    }
+}
+
+#[test]
+fn test_read_mef_gates_and_expressions() {
+    let xml = r#"<?xml version="1.0"?>
+<!-- all connectives, forward references and expressions -->
+<opsa-mef>
+  <define-fault-tree name="FT">
+    <define-gate name="TOP">
+      <label>system failure</label>
+      <or>
+        <gate name="G1"/>
+        <event name="G2"/>
+        <and><basic-event name="E"/><house-event name="H"/></and>
+      </or>
+    </define-gate>
+    <define-gate name="G1">
+      <atleast min="2">
+        <basic-event name="A"/> <basic-event name="B"/> <basic-event name="C"/>
+      </atleast>
+    </define-gate>
+    <define-gate name="G2">
+      <and><basic-event name="D"/><not><basic-event name="A"/></not></and>
+    </define-gate>
+    <define-gate name="SIDE">
+      <nor><basic-event name="A"/><basic-event name="D"/></nor>
+    </define-gate>
+    <define-house-event name="H"><constant value="false"/></define-house-event>
+  </define-fault-tree>
+  <model-data>
+    <define-parameter name="lambda"><float value="1e-3"/></define-parameter>
+    <define-basic-event name="A"><float value="0.1"/></define-basic-event>
+    <define-basic-event name="B"><float value="0.2"/></define-basic-event>
+    <define-basic-event name="C"><mul><float value="0.5"/><float value="0.6"/></mul></define-basic-event>
+    <define-basic-event name="D">
+      <exponential><parameter name="lambda"/><system-mission-time/></exponential>
+    </define-basic-event>
+    <define-basic-event name="E"><float value="0.9"/></define-basic-event>
+  </model-data>
+</opsa-mef>"#;
+    let mut mgr = BddMgr::new();
+    let model = read_mef(&mut mgr, xml, Some(100.0)).unwrap();
+    assert_eq!(model.top_gate, "TOP");
+    assert_eq!(model.gates.len(), 4);
+    assert!((model.probs["C"] - 0.3).abs() < 1e-15);
+    let pd = 1.0 - (-0.1_f64).exp();
+    assert!((model.probs["D"] - pd).abs() < 1e-15);
+
+    // The house event H is false, so E drops out.
+    let expected = mgr.rpn("A B & A C & | B C & | D A ~ & |").unwrap();
+    assert_eq!(model.top.get_id(), expected.get_id());
+    let side = mgr.rpn("A D | ~").unwrap();
+    assert_eq!(model.gates["SIDE"].get_id(), side.get_id());
+    // Basic events are ordered as the top gate reaches them.
+    assert_eq!(mgr.get_varorder(), vec!["A", "B", "C", "D", "E"]);
+
+    assert!(read_mef(&mut BddMgr::new(), xml, None)
+        .unwrap_err().to_string()
+        .contains("mission time"));
+}
+
+#[test]
+fn test_read_mef_errors() {
+    let mut mgr = BddMgr::new();
+    let unknown = r#"<opsa-mef>
+  <define-gate name="TOP"><or><gate name="G"/><basic-event name="A"/></or></define-gate>
+</opsa-mef>"#;
+    let err = read_mef(&mut mgr, unknown, None).unwrap_err().to_string();
+    assert!(err.starts_with("line 2:") && err.contains("unknown gate G"), "{err}");
+
+    let cyclic = r#"<opsa-mef>
+  <define-gate name="TOP"><gate name="G"/></define-gate>
+  <define-gate name="G"><or><gate name="H"/><basic-event name="A"/></or></define-gate>
+  <define-gate name="H"><gate name="G"/></define-gate>
+</opsa-mef>"#;
+    assert!(read_mef(&mut mgr, cyclic, None).unwrap_err().to_string().contains("itself"));
+
+    let unclosed = "<opsa-mef>\n  <define-gate name=\"TOP\">\n    <or>\n</opsa-mef>";
+    let err = read_mef(&mut mgr, unclosed, None).unwrap_err().to_string();
+    assert!(err.starts_with("line 4:"), "{err}");
+
+    let unsupported = r#"<opsa-mef><define-gate name="T"><imply/></define-gate></opsa-mef>"#;
+    assert!(read_mef(&mut mgr, unsupported, None).unwrap_err().to_string().contains("<imply>"));
+
+    for k in ["0", "3", "18446744073709551615"] {
+        let atleast = format!(
+            "<opsa-mef>\n  <define-gate name=\"T\">\n    <atleast min=\"{k}\">\n      \
+             <basic-event name=\"A\"/><basic-event name=\"B\"/>\n    </atleast>\n  \
+             </define-gate>\n</opsa-mef>"
+        );
+        let err = read_mef(&mut BddMgr::new(), &atleast, None).unwrap_err().to_string();
+        assert_eq!(err, format!("line 3: at-least gate needs 1 <= k <= 2, got {k}"));
+    }
+}
+
+#[test]
+fn test_write_mef_round_trip() {
+    let mut mgr = BddMgr::new();
+    let f = mgr.rpn("x y & z | w ^").unwrap();
+    let f = f.and(&mgr.rpn("x w |").unwrap());
+    let mut pv = HashMap::new();
+    for (x, p) in [("x", 0.1), ("y", 0.25), ("z", 0.3), ("w", 0.45)] {
+        pv.insert(x.to_string(), p);
+    }
+    let xml = write_mef(&f, "SYS", &pv);
+
+    let mut other = BddMgr::new();
+    let model = read_mef(&mut other, &xml, None).unwrap();
+    assert_eq!(model.top_gate, "SYS");
+    assert_eq!(model.probs, pv);
+    let p0 = f.prob(&pv, &[true]);
+    let p1 = model.top.prob(&model.probs, &[true]);
+    assert!((p0 - p1).abs() < 1e-15);
+    // Same structure function: re-import into the original manager.
+    let again = read_mef(&mut mgr, &xml, None).unwrap();
+    assert_eq!(again.top.get_id(), f.get_id());
+}
+
+#[test]
+fn test_read_aralia() {
+    let src = "
+// forward references, precedence and the counting operators
+TOP := G1 | e1 & ~e2 ^ G2;
+G1 := @(2, [e1, e2, e3]);
+G2 := #(1, 1, [e3, e4]);   /* exactly one */
+IMP := e1 => e4;
+p(e1) = 0.1; p(e2) = 0.2;
+p(e3) = 3e-1; p(e4) = 0.4;
+";
+    let mut mgr = BddMgr::new();
+    let ft = read_aralia(&mut mgr, src).unwrap();
+    assert_eq!(ft.top_gate, "TOP");
+    assert_eq!(ft.gates.len(), 4);
+    assert_eq!(ft.probs.len(), 4);
+    let expected = mgr
+        .rpn("e1 e2 & e1 e3 & | e2 e3 & | e1 e2 ~ & e3 e4 ^ ^ |")
+        .unwrap();
+    assert_eq!(ft.top.get_id(), expected.get_id());
+    let imp = mgr.rpn("e1 ~ e4 |").unwrap();
+    assert_eq!(ft.gates["IMP"].get_id(), imp.get_id());
+
+    let err = read_aralia(&mut BddMgr::new(), "TOP := a &\n  (b | );").unwrap_err().to_string();
+    assert_eq!(err, "line 2, column 8: expected a formula, found `)`");
+    let err = read_aralia(&mut BddMgr::new(), "T := a;\nT := b;").unwrap_err().to_string();
+    assert!(err.starts_with("line 2, column 1: gate T is already defined"), "{err}");
+    let err = read_aralia(&mut BddMgr::new(), "T := a | b").unwrap_err().to_string();
+    assert!(err.contains("expected `;`, found end of input"), "{err}");
+
+    let err = read_aralia(&mut BddMgr::new(), "T := a;\nU := @(0, [a, b]);").unwrap_err().to_string();
+    assert_eq!(err, "line 2, column 6: at-least gate needs 1 <= k <= 2, got 0");
+    let err = read_aralia(&mut BddMgr::new(), "T := @(3, [a, b]);").unwrap_err().to_string();
+    assert_eq!(err, "line 1, column 6: at-least gate needs 1 <= k <= 2, got 3");
+    let src = "T := #(0, 18446744073709551615, [a, b]);";
+    let err = read_aralia(&mut BddMgr::new(), src).unwrap_err().to_string();
+    assert!(err.starts_with("line 1, column 6: cardinality range"), "{err}");
+
+    // Open-ended ranges: at most one, at least one, and anything.
+    let mut mgr = BddMgr::new();
+    let ft = read_aralia(&mut mgr, "T := #(0, 1, [a, b]) & #(1, 2, [a, b]) | #(0, 1, [c]);")
+        .unwrap();
+    let expected = mgr.rpn("a b ^ c c ~ | |").unwrap();
+    assert_eq!(ft.top.get_id(), expected.get_id());
+}
+
+#[test]
+fn test_read_galileo() {
+    let src = r#"
+toplevel "System";
+"System" or "PumpA" "PumpB";
+"PumpA" and "A1" "A2";
+"PumpB" 2of3 "B1" "B2" "B3";
+"A1" prob=0.01;
+"A2" prob=0.02;
+"B1" lambda=1.0e-3 dorm=0;
+"B2" lambda=1.0e-3;
+"B3" prob=0.1 repair=0.5;
+"#;
+    let mut bss = BssMgr::new();
+    let ft = bss.read_galileo(src, Some(100.0)).unwrap();
+    assert_eq!(ft.top_gate, "System");
+    let qb = 1.0 - (-0.1_f64).exp();
+    assert!((ft.probs["B1"] - qb).abs() < 1e-15);
+    let pa = 0.01 * 0.02;
+    let pb = qb * qb + 2.0 * qb * 0.1 - 2.0 * qb * qb * 0.1;
+    let p = ft.top.prob(&ft.probs, &[true]);
+    assert!((p - (pa + pb - pa * pb)).abs() < 1e-15);
+    let cuts = bss.minpath(&ft.top).unwrap();
+    assert_eq!(
+        sorted_sets(&cuts),
+        sets(&[&["A1", "A2"], &["B1", "B2"], &["B1", "B3"], &["B2", "B3"]])
+    );
+
+    let err = read_galileo(&mut BddMgr::new(), src, None).unwrap_err().to_string();
+    assert_eq!(err, "line 8, column 6: `lambda` of B1 needs a mission time");
+    let err = read_galileo(&mut BddMgr::new(), "toplevel T;\nT pand A B;", None).unwrap_err().to_string();
+    assert_eq!(err, "line 2, column 3: dynamic gate `pand` is not supported");
+    let err = read_galileo(&mut BddMgr::new(), "T 2of3 A B;", None).unwrap_err().to_string();
+    assert!(err.contains("voting gate `2of3` has 2 inputs"), "{err}");
+    let err = read_galileo(&mut BddMgr::new(), "T 3of2 A B;", None).unwrap_err().to_string();
+    assert_eq!(err, "line 1, column 3: voting gate `3of2` needs 1 <= K <= M");
+    let err = read_galileo(&mut BddMgr::new(), "T 0of2 A B;", None).unwrap_err().to_string();
+    assert_eq!(err, "line 1, column 3: voting gate `0of2` needs 1 <= K <= M");
+    let err = read_galileo(&mut BddMgr::new(), "T or A B;\nA prob=1.5;", None).unwrap_err().to_string();
+    assert_eq!(err, "line 2, column 3: probability 1.5 of A is not in [0, 1]");
+    let err = read_galileo(&mut BddMgr::new(), "toplevel X; T or A;", None).unwrap_err().to_string();
+    assert_eq!(err, "line 1, column 10: unknown gate X");
+}
+
+#[test]
+fn test_parse_matches_rpn() {
+    let cases = [
+        ("A & (B | ~C)", "A B C ~ | &"),
+        ("A | B & C ^ D", "A B C & D ^ |"),
+        ("~~A & 1 | false", "A"),
+        ("atleast(2, A, B, C)", "A B & A C & | B C & |"),
+        ("ite(A, B, C & D)", "A B C D & ?"),
+        ("let x = A | B; let y = x & C; ~y ^ x", "A B | C & ~ A B | ^"),
+        ("\"let\" & \"x.1\" // keywords and odd names quoted", "let x.1 &"),
+    ];
+    for (infix, rpn) in cases {
+        let mut mgr = BddMgr::new();
+        let f = mgr.parse(infix).unwrap();
+        let g = mgr.rpn(rpn).unwrap();
+        assert_eq!(f.get_id(), g.get_id(), "{infix}");
+    }
+    let mut mgr = BddMgr::new();
+    mgr.parse("\"pump A\" | \"say \\\"hi\\\"\"").unwrap();
+    assert_eq!(mgr.get_varorder(), vec!["pump A", "say \"hi\""]);
+}
+
+#[test]
+fn test_parse_errors() {
+    let mut mgr = BddMgr::new();
+    let check = |mgr: &mut BddMgr, src: &str, span: std::ops::Range<usize>, msg: &str| {
+        let Err(RelibError::Parse(err)) = mgr.parse(src) else {
+            panic!("{src}: expected a parse error");
+        };
+        assert_eq!(err.span, span, "{src}: {err}");
+        assert!(err.message.contains(msg), "{src}: {err}");
+    };
+    check(&mut mgr, "A & min(B, C)", 4..7, "unknown function `min`");
+    check(&mut mgr, "A && B", 3..4, "expected an operand, found `&`");
+    check(&mut mgr, "(A | B", 6..6, "expected `)`, found end of input");
+    check(&mut mgr, "A B", 2..3, "expected an operator or end of input");
+    check(&mut mgr, "ite(A, B)", 0..9, "`ite` takes 3 arguments");
+    check(&mut mgr, "let ite = A; ite", 4..7, "`ite` is a keyword");
+    check(&mut mgr, "A & 2", 4..5, "not a boolean constant");
+    check(&mut mgr, "A $ B", 2..3, "unexpected character `$`");
+    check(&mut mgr, "let x = A x", 10..11, "expected `;`");
+    check(&mut mgr, "atleast(0, A, B)", 8..9, "needs 1 <= k <= 2, found 0");
+    check(&mut mgr, "atleast(3, A, B)", 8..9, "needs 1 <= k <= 2, found 3");
+    let huge = "atleast(18446744073709551615, A)";
+    check(&mut mgr, huge, 8..28, "needs 1 <= k <= 1");
+    let Err(RelibError::Parse(err)) = mgr.parse("A &\n  | B") else {
+        panic!("expected a parse error");
+    };
+    assert_eq!(err.line_col("A &\n  | B"), (2, 3));
+    // Nothing was half-declared by the failed parses except plain variables.
+    assert!(!mgr.get_varorder().contains(&"min".to_string()));
+}
+
+/// Brute-force check of `Network::k_terminal` on every assignment of a small network.
+fn check_network(net: &Network, terminals: &[usize]) {
+    let mut mgr = BddMgr::new();
+    let f = net.k_terminal(&mut mgr, terminals).unwrap();
+    let n = net.vertex_count();
+    let vars = mgr.get_varorder();
+    for mask in 0u32..(1 << vars.len()) {
+        let on = |x: &str| {
+            let i = vars.iter().position(|y| y == x).unwrap();
+            (mask >> i) & 1 == 1
+        };
+        let pv: HashMap<String, f64> = vars
+            .iter()
+            .map(|x| (x.clone(), if on(x) { 1.0 } else { 0.0 }))
+            .collect();
+        // Union-find over working edges between working vertices.
+        let up: Vec<bool> = (0..n).map(|v| net.vertex_var(v).map_or(true, on)).collect();
+        let mut parent: Vec<usize> = (0..n).collect();
+        fn find(p: &mut Vec<usize>, x: usize) -> usize {
+            if p[x] != x {
+                let r = find(p, p[x]);
+                p[x] = r;
+            }
+            p[x]
+        }
+        for (u, v, x) in net.edges() {
+            if up[*u] && up[*v] && x.as_deref().map_or(true, on) {
+                let (a, b) = (find(&mut parent, *u), find(&mut parent, *v));
+                parent[a] = b;
+            }
+        }
+        let root = find(&mut parent, terminals[0]);
+        let expected = terminals
+            .iter()
+            .all(|&t| up[t] && find(&mut parent, t) == root);
+        let got = f.prob(&pv, &[true]);
+        assert_eq!(got, if expected { 1.0 } else { 0.0 }, "mask {mask:b} terminals {terminals:?}");
+    }
+}
+
+#[test]
+fn test_network_bruteforce() {
+    // Bridge with a perfect edge and unreliable inner vertices.
+    let mut bridge = Network::new(4);
+    for (u, v, x) in [(0, 1, Some("a")), (0, 2, Some("b")), (1, 2, None), (1, 3, Some("d")), (2, 3, Some("e"))] {
+        bridge.add_edge(u, v, x).unwrap();
+    }
+    bridge.set_vertex_var(1, "v1").unwrap();
+    bridge.set_vertex_var(2, "v2").unwrap();
+    check_network(&bridge, &[0, 3]);
+    check_network(&bridge, &[0, 1, 3]);
+    check_network(&bridge, &[0, 1, 2, 3]);
+    check_network(&bridge, &[2]);
+
+    // 2x4 grid, row-by-row edge order, plus an isolated vertex 8; a shared variable `s`
+    // on two edges.
+    let mut grid = Network::new(9);
+    let mut k = 0;
+    for c in 0..4 {
+        if c > 0 {
+            for r in 0..2 {
+                let (u, v) = (r * 4 + c - 1, r * 4 + c);
+                let x = if k == 2 || k == 4 { "s".to_string() } else { format!("h{k}") };
+                grid.add_edge(u, v, Some(&x)).unwrap();
+                k += 1;
+            }
+        }
+        grid.add_edge(c, 4 + c, Some(&format!("v{c}"))).unwrap();
+    }
+    check_network(&grid, &[0, 7]);
+    check_network(&grid, &[0, 3, 4, 7]);
+    check_network(&grid, &(0..8).collect::<Vec<_>>());
+
+    let mut mgr = BddMgr::new();
+    assert!(grid.all_terminal(&mut mgr).unwrap().is_zero());
+    assert!(grid.k_terminal(&mut mgr, &[]).is_err());
+    assert!(grid.k_terminal(&mut mgr, &[0, 0]).is_err());
+    assert!(grid.add_edge(0, 9, None).is_err());
+}
+
+#[test]
+fn test_network_mincut_and_size() {
+    // A 3 x 12 grid: s-t connectivity through the frontier method stays small.
+    let (rows, cols) = (3, 12);
+    let mut net = Network::new(rows * cols);
+    for c in 0..cols {
+        for r in 0..rows {
+            let v = r * cols + c;
+            if c > 0 {
+                net.add_edge(v - 1, v, Some(&format!("h{r}_{c}"))).unwrap();
+            }
+            if r > 0 {
+                net.add_edge(v - cols, v, Some(&format!("v{r}_{c}"))).unwrap();
+            }
+        }
+    }
+    let mut bss = BssMgr::new();
+    let f = bss.st_connectivity(&net, 0, rows * cols - 1).unwrap();
+    assert!(f.size().0 < 5000, "{:?}", f.size());
+    // Minimal cuts of a grid between opposite corners include the two corner stars.
+    let cuts = sorted_sets(&bss.mincut(&f).unwrap());
+    assert!(cuts.contains(&vec!["h0_1".to_string(), "v1_0".to_string()]));
+    let p: HashMap<String, f64> = bss.get_varorder().into_iter().map(|x| (x, 0.5)).collect();
+    let r = f.prob(&p, &[true]);
+    assert!(r > 0.0 && r < 1.0);
+}
+
+#[test]
+fn test_rbd_compile() {
+    let mut rbd = Rbd::new();
+    rbd.define(
+        "train",
+        Block::series(vec![Block::component("pump"), Block::component("valve")]),
+    )
+    .unwrap();
+    rbd.define(
+        "core",
+        Block::bridge(
+            Block::component("a"),
+            Block::component("b"),
+            Block::component("c"),
+            Block::component("d"),
+            Block::component("e"),
+        ),
+    )
+    .unwrap();
+    assert!(rbd.define("core", Block::component("x")).is_err());
+
+    let mut bss = BssMgr::new();
+    // The bridge block is s-t connectivity of the bridge network.
+    let core = bss.compile_rbd(&rbd, &Block::sub("core")).unwrap();
+    let mut net = Network::new(4);
+    for (u, v, x) in [(0, 1, "a"), (0, 2, "b"), (1, 2, "c"), (1, 3, "d"), (2, 3, "e")] {
+        net.add_edge(u, v, Some(x)).unwrap();
+    }
+    assert_eq!(core.get_id(), bss.st_connectivity(&net, 0, 3).unwrap().get_id());
+
+    // A sub-diagram used twice compiles to the same node; standby is parallel.
+    let system = Block::series(vec![
+        Block::sub("core"),
+        Block::standby(Block::sub("train"), vec![Block::component("spare")]),
+        Block::kofn(1, vec![Block::sub("core"), Block::component("bypass")]),
+    ]);
+    let f = bss.compile_rbd(&rbd, &system).unwrap();
+    let expected = core.and(&core.or(&bss.rpn("bypass").unwrap())).and(&bss.rpn("pump valve & spare |").unwrap());
+    assert_eq!(f.get_id(), expected.get_id());
+
+    assert_eq!(
+        bss.compile_rbd(&rbd, &Block::sub("pump")).unwrap_err().to_string(),
+        "unknown sub-diagram pump"
+    );
+    rbd.define("loop", Block::parallel(vec![Block::component("x"), Block::sub("loop")]))
+        .unwrap();
+    assert!(bss.compile_rbd(&rbd, &Block::sub("loop")).unwrap_err().to_string().contains("contains itself"));
+
+    let comps: Vec<Block> = ["a", "b", "c"].iter().map(|x| Block::component(x)).collect();
+    let mut err = |b: Block| bss.compile_rbd(&rbd, &b).unwrap_err().to_string();
+    assert_eq!(err(Block::series(vec![])), "series block has no children");
+    assert_eq!(err(Block::parallel(vec![])), "parallel block has no children");
+    assert_eq!(err(Block::kofn(2, vec![])), "k-out-of-n block has no children");
+    assert_eq!(err(Block::kofn(4, comps.clone())), "k-out-of-n block needs 1 <= k <= 3, got 4");
+    assert_eq!(err(Block::kofn(0, comps)), "k-out-of-n block needs 1 <= k <= 3, got 0");
+}
+
+type Oracle = Box<dyn Fn(&[bool]) -> bool>;
+
+#[test]
+fn test_weighted_threshold_and_consecutive_bruteforce() {
+    let n = 7;
+    let mut bss = BssMgr::new();
+    let names: Vec<String> = (0..n).map(|i| format!("x{i}")).collect();
+    let xs: Vec<BddNode> = names.iter().map(|x| bss.defvar(x)).collect();
+    let weights = [3, -1, 2, 5, 1, -2, 4];
+    let cases: Vec<(BddNode, Oracle)> = vec![
+        (
+            bss.weighted_threshold(&weights, 6, &xs),
+            Box::new(move |s: &[bool]| (0..n).filter(|&i| s[i]).map(|i| weights[i]).sum::<i64>() >= 6),
+        ),
+        (
+            bss.weighted_threshold(&weights, -3, &xs),
+            Box::new(move |s: &[bool]| (0..n).filter(|&i| s[i]).map(|i| weights[i]).sum::<i64>() >= -3),
+        ),
+        (
+            bss.consecutive_kofn(3, &xs, false),
+            Box::new(move |s: &[bool]| (0..n - 2).any(|i| s[i] && s[i + 1] && s[i + 2])),
+        ),
+        (
+            bss.consecutive_kofn(3, &xs, true),
+            Box::new(move |s: &[bool]| (0..n).any(|i| (0..3).all(|j| s[(i + j) % n]))),
+        ),
+        (bss.consecutive_kofn(8, &xs, true), Box::new(|_: &[bool]| false)),
+    ];
+    for bits in 0..1u32 << n {
+        let s: Vec<bool> = (0..n).map(|i| bits >> i & 1 == 1).collect();
+        let pv: HashMap<String, f64> = names
+            .iter()
+            .zip(&s)
+            .map(|(x, &b)| (x.clone(), if b { 1.0 } else { 0.0 }))
+            .collect();
+        for (k, (f, want)) in cases.iter().enumerate() {
+            assert_eq!(f.prob(&pv, &[true]) == 1.0, want(&s), "case {k}, {s:?}");
+        }
+    }
+
+    // Unit weights and k = 1 reduce to the existing builders.
+    assert_eq!(bss.weighted_threshold(&[1; 7], 3, &xs).get_id(), bss.kofn(3, &xs).get_id());
+    assert_eq!(bss.consecutive_kofn(1, &xs, true).get_id(), bss.or(&xs).get_id());
+    assert_eq!(bss.consecutive_kofn(7, &xs, false).get_id(), bss.and(&xs).get_id());
+
+    // Weights near the i64 range: the sums would overflow in i64.
+    let big = [i64::MAX, i64::MAX, i64::MIN];
+    let f = bss.weighted_threshold(&big, i64::MAX, &xs[..3]);
+    assert_eq!(f.get_id(), bss.rpn("x0 x1 | x2 ~ &").unwrap().get_id());
+    let f = bss.weighted_threshold(&big, i64::MIN, &xs[..3]);
+    assert_eq!(f.get_id(), bss.one().get_id());
+}
+
+#[test]
+fn test_posterior_and_mpe_bruteforce() {
+    let mut bss = BssMgr::new();
+    let names = ["a", "b", "c", "d", "e"];
+    for x in names {
+        bss.defvar(x);
+    }
+    // `b` and `d` are skipped on some paths.
+    let top = bss.rpn("a b & c | d e ~ & &").unwrap();
+    let probs = [0.3, 0.6, 0.2, 0.9, 0.45];
+    let pv: HashMap<String, f64> = names.iter().zip(probs).map(|(x, p)| (x.to_string(), p)).collect();
+
+    for ss in [[true], [false]] {
+        let mut total = 0.0;
+        let mut joint = [0.0; 5];
+        let mut best = (0.0, 0);
+        for bits in 0..1u32 << 5 {
+            let point: HashMap<String, f64> = names
+                .iter()
+                .enumerate()
+                .map(|(i, x)| (x.to_string(), (bits >> i & 1) as f64))
+                .collect();
+            if (top.prob(&point, &[true]) == 1.0) != ss[0] {
+                continue;
+            }
+            let w: f64 = (0..5).map(|i| if bits >> i & 1 == 1 { probs[i] } else { 1.0 - probs[i] }).product();
+            total += w;
+            for (i, j) in joint.iter_mut().enumerate() {
+                if bits >> i & 1 == 1 {
+                    *j += w;
+                }
+            }
+            if w > best.0 {
+                best = (w, bits);
+            }
+        }
+        let post = top.posterior(&pv, &ss);
+        assert_eq!(post.len(), 5);
+        for (i, x) in names.iter().enumerate() {
+            assert!((post[*x] - joint[i] / total).abs() < 1e-12, "{x} given {ss:?}");
+        }
+        let (p, assignment) = top.mpe(&pv, &ss).unwrap();
+        assert!((p - best.0).abs() < 1e-12);
+        for (i, x) in names.iter().enumerate() {
+            assert_eq!(assignment[*x], best.1 >> i & 1 == 1, "{x} given {ss:?}");
+        }
+    }
+
+    assert!(bss.zero().mpe(&pv, &[true]).is_none());
+}
+
+#[test]
+fn test_prob_given() {
+    let mut bss = BssMgr::new();
+    for x in ["a", "b", "c", "d"] {
+        bss.defvar(x);
+    }
+    let top = bss.rpn("a b & c |").unwrap();
+    let pv: HashMap<String, f64> = [("a", 0.3), ("b", 0.6), ("c", 0.2), ("d", 0.7)]
+        .iter()
+        .map(|&(x, p)| (x.to_string(), p))
+        .collect();
+
+    // No evidence: plain prob.
+    let (joint, cond) = top.prob_given(&pv, &[true], &HashMap::new()).unwrap();
+    assert_eq!(joint, cond);
+    assert!((cond - top.prob(&pv, &[true])).abs() < 1e-12);
+
+    // b observed working, c observed failed, d (outside the diagram) observed.
+    let evidence = HashMap::from([
+        ("b".to_string(), true),
+        ("c".to_string(), false),
+        ("d".to_string(), true),
+    ]);
+    let (joint, cond) = top.prob_given(&pv, &[true], &evidence).unwrap();
+    assert!((cond - 0.3).abs() < 1e-12);
+    assert!((joint - 0.3 * 0.6 * 0.8 * 0.7).abs() < 1e-12);
+    let (joint0, cond0) = top.prob_given(&pv, &[false], &evidence).unwrap();
+    assert!((cond + cond0 - 1.0).abs() < 1e-12);
+    assert!((joint + joint0 - 0.6 * 0.8 * 0.7).abs() < 1e-12);
+
+    // An observed variable needs a prior, as in the MDD version.
+    let unknown = HashMap::from([("e".to_string(), true)]);
+    assert_eq!(
+        top.prob_given(&pv, &[true], &unknown).unwrap_err(),
+        RelibError::MissingProbability("e".to_string())
+    );
+}
+
+#[test]
+fn test_prob_interval() {
+    let mut bss = BssMgr::new();
+    for x in ["a", "b", "c"] {
+        bss.defvar(x);
+    }
+    let pv = HashMap::from([
+        ("a".to_string(), Interval::new(0.2, 0.4)),
+        ("b".to_string(), Interval::new(0.5, 0.9)),
+        ("c".to_string(), Interval::new(0.1, 0.3)),
+    ]);
+    // Every corner of the box, for the true range (multilinear in each probability).
+    let corners: Vec<HashMap<String, f64>> = (0..8)
+        .map(|bits: u32| {
+            pv.iter()
+                .enumerate()
+                .map(|(i, (x, p))| (x.clone(), if bits >> i & 1 == 1 { p.hi } else { p.lo }))
+                .collect()
+        })
+        .collect();
+    let range = |f: &BddNode, ss: &[bool]| {
+        let ps: Vec<f64> = corners.iter().map(|pv| f.prob(pv, ss)).collect();
+        let lo = ps.iter().copied().fold(f64::INFINITY, f64::min);
+        let hi = ps.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        (lo, hi)
+    };
+
+    // Coherent: exact, from BssMgr and from the node-wise pass alike.
+    let f = bss.rpn("a b & c |").unwrap();
+    for ss in [&[true][..], &[false]] {
+        let (lo, hi) = range(&f, ss);
+        for b in [bss.prob_interval(&f, &pv, ss), f.prob_interval(&pv, ss)] {
+            assert!((b.lo - lo).abs() < 1e-12 && (b.hi - hi).abs() < 1e-12, "{b} vs [{lo}, {hi}]");
+        }
+    }
+
+    // Not coherent: sound, and tighter than plain interval arithmetic.
+    let g = bss.rpn("a b ^ c &").unwrap();
+    let (lo, hi) = range(&g, &[true]);
+    let b = bss.prob_interval(&g, &pv, &[true]);
+    assert!(b.lo <= lo + 1e-12 && hi <= b.hi + 1e-12, "{b} vs [{lo}, {hi}]");
+    let naive = g.prob(&pv, &[true]);
+    assert!(naive.lo <= b.lo && b.hi <= naive.hi, "{naive} vs {b}");
+}
+
+#[test]
+fn test_numeric_types() {
+    let mut bss = BssMgr::new();
+    let names: Vec<String> = (0..30).map(|i| format!("x{i}")).collect();
+    let xs: Vec<BddNode> = names.iter().map(|x| bss.defvar(x)).collect();
+
+    // Exact rationals: P(ab + c) = 0.02 + 0.3 - 0.006, Birnbaum of c = 1 - P(ab).
+    let f = bss.or(&[xs[0].and(&xs[1]), xs[2].clone()]);
+    let pv: HashMap<String, Rational> = (0..3).map(|i| (names[i].clone(), Rational::new(i as i128 + 1, 10))).collect();
+    assert_eq!(f.prob(&pv, &[true]), Rational::new(157, 500));
+    assert_eq!(f.bmeas(&pv, &[true])["x2"], Rational::new(49, 50));
+    let (p, q) = f.prob_with_complement(&pv, &[true]);
+    assert_eq!(p + q, Rational::from(1.0));
+
+    // Log space: 30 events of probability 1e-20 all occurring, 1e-600, underflows f64.
+    let all = bss.and(&xs);
+    let pv: HashMap<String, LogProb> = names.iter().map(|x| (x.clone(), LogProb::from(1e-20))).collect();
+    let p = all.prob(&pv, &[true]);
+    assert!((p.ln() - 30.0 * 1e-20_f64.ln()).abs() < 1e-9);
+    let (_, q) = all.not().prob_with_complement(&pv, &[true]);
+    assert!((q.ln() - p.ln()).abs() < 1e-9);
+    let pf: HashMap<String, f64> = names.iter().map(|x| (x.clone(), 1e-20)).collect();
+    assert_eq!(all.prob(&pf, &[true]), 0.0);
+
+    // Double-double: P(neither of two 1e-10 events) = 1 - 2e-10 + 1e-20; the 1e-20 is below
+    // f64 resolution at 1 but kept in the complement and in a double-double.
+    let either = xs[0].or(&xs[1]);
+    let pv: HashMap<String, DoubleDouble> = names[..2].iter().map(|x| (x.clone(), DoubleDouble::from(1e-10))).collect();
+    let (none, some) = either.prob_with_complement(&pv, &[false]);
+    let exact_some = DoubleDouble::from(2e-10) - DoubleDouble::from(1e-20);
+    assert!((some - exact_some).to_f64().abs() < 1e-35);
+    assert!((DoubleDouble::from(1.0) - none - exact_some).to_f64().abs() < 1e-30);
+    let (none, _) = either.prob_with_complement(&pf, &[false]);
+    assert_ne!(1.0 - none, 2e-10 - 1e-20);
+}
+
+#[test]
+fn test_phased_mission_bruteforce() {
+    let comps = ["A", "B", "C"];
+    let phases = ["p1", "p2", "p3"];
+    let mut bss = BssMgr::new();
+    let pm = bss.phased_mission(&comps, &phases).unwrap();
+    assert!(bss.phased_mission(&["A"], &["p2"]).is_err());
+    assert!(bss.phased_mission(&["D"], &[]).is_err());
+
+    // Phase logic written over the component variables and moved into each phase.
+    let logic = ["A | B", "A & B | C", "A ^ C"];
+    let mut fs = Vec::new();
+    for (src, phase) in logic.iter().zip(phases.iter()) {
+        let f = bss.parse(src).unwrap();
+        fs.push(bss.in_phase(&pm, &f, phase).unwrap());
+    }
+    assert_eq!(fs[1].get_id(), bss.parse("A.p2 & B.p2 | C.p2").unwrap().get_id());
+    let mission = pm.mission_failure(bss.bdd(), &fs).unwrap();
+    assert!(pm.mission_failure(bss.bdd(), &fs[..2]).is_err());
+
+    let q: HashMap<String, Vec<f64>> = [
+        ("A".to_string(), vec![0.1, 0.3, 0.2]),
+        ("B".to_string(), vec![0.05, 0.4, 0.1]),
+        ("C".to_string(), vec![0.2, 0.1, 0.5]),
+    ]
+    .into_iter()
+    .collect();
+    let u = pm.unreliability(&mission, &q).unwrap();
+
+    // Enumerate the phase each component fails in (3 = survives the mission).
+    let mut expected = 0.0;
+    for code in 0..64 {
+        let t: Vec<usize> = (0..3).map(|c| (code >> (2 * c)) & 3).collect();
+        let mut weight = 1.0;
+        let mut pv = HashMap::new();
+        for (c, comp) in comps.iter().enumerate() {
+            let qc = &q[*comp];
+            let mut p = 1.0;
+            for (j, &qj) in qc.iter().enumerate() {
+                if j < t[c] {
+                    p *= 1.0 - qj;
+                } else if j == t[c] {
+                    p *= qj;
+                }
+                let failed = if t[c] <= j { 1.0 } else { 0.0 };
+                pv.insert(format!("{}.{}", comp, phases[j]), failed);
+            }
+            weight *= p;
+        }
+        expected += weight * mission.prob(&pv, &[true]);
+    }
+    assert!((u - expected).abs() < 1e-12, "{} vs {}", u, expected);
+
+    let mut missing = q.clone();
+    missing.remove("C");
+    assert_eq!(
+        pm.unreliability(&mission, &missing).unwrap_err(),
+        RelibError::MissingProbability("C".to_string())
+    );
+    let other = bss.defvar("other");
+    assert!(pm.unreliability(&mission.or(&other), &q).is_err());
+    assert!(bss.in_phase(&pm, &fs[0], "p4").is_err());
+    // A variable outside the mission is rejected when moved into a phase, not only when
+    // the mission is evaluated; phase copies are kept.
+    let f = bss.parse("A | other").unwrap();
+    let err = bss.in_phase(&pm, &f, "p1").unwrap_err().to_string();
+    assert_eq!(err, "variable other is not a component of the mission");
+    let f = bss.parse("A & B.p1").unwrap();
+    let g = bss.in_phase(&pm, &f, "p2").unwrap();
+    assert_eq!(g.get_id(), bss.parse("A.p2 & B.p1").unwrap().get_id());
+}
+
+#[test]
+fn test_try_variants() {
+    let mut bss = BssMgr::new();
+    let f = bss.rpn("A B & C |").unwrap();
+    let pv: HashMap<String, f64> = [("A", 0.1), ("B", 0.2), ("C", 0.3)]
+        .iter()
+        .map(|(x, p)| (x.to_string(), *p))
+        .collect();
+    assert_eq!(f.try_prob(&pv, &[true]).unwrap(), f.prob(&pv, &[true]));
+    assert_eq!(f.try_bmeas(&pv, &[true]).unwrap(), f.bmeas(&pv, &[true]));
+    assert!(bss.try_minpath(&f).unwrap().is_some());
+
+    // Malformed rpn is an error, not a panic.
+    assert_eq!(
+        bss.try_rpn("A &").unwrap_err(),
+        RelibError::Invalid("missing operand for &".to_string())
+    );
+    assert_eq!(bss.rpn("A B ?").unwrap_err(), "missing operand for ?");
+
+    // Nodes of another manager.
+    let mut other = BddMgr::new();
+    let x = other.defvar("x");
+    assert_eq!(f.try_and(&x).unwrap_err(), RelibError::ManagerMismatch);
+    assert_eq!(f.try_ite(&f, &x).unwrap_err(), RelibError::ManagerMismatch);
+
+    // A node that outlives its manager.
+    drop(other);
+    assert_eq!(x.try_not().unwrap_err(), RelibError::ManagerDropped);
+    assert_eq!(x.try_prob(&pv, &[true]).unwrap_err(), RelibError::ManagerDropped);
+    assert_eq!(f.try_or(&x).unwrap_err(), RelibError::ManagerDropped);
+    assert_eq!(bss.try_mincut(&x).unwrap_err(), RelibError::ManagerDropped);
+    assert_eq!(RelibError::ManagerDropped.to_string(), "the manager of the node has been dropped");
+
+    // Every evaluation and conversion entry point has a fallible form.
+    let dropped = RelibError::ManagerDropped;
+    let ipv: HashMap<String, Interval> = pv.iter().map(|(x, &p)| (x.clone(), Interval::from(p))).collect();
+    assert_eq!(f.try_prob_with_complement(&pv, &[true]).unwrap(), f.prob_with_complement(&pv, &[true]));
+    assert_eq!(f.try_prob_batch(std::slice::from_ref(&pv), &[true]).unwrap(), vec![f.prob(&pv, &[true])]);
+    assert_eq!(f.try_posterior(&pv, &[true]).unwrap(), f.posterior(&pv, &[true]));
+    assert_eq!(f.try_mpe(&pv, &[true]).unwrap(), f.mpe(&pv, &[true]));
+    assert_eq!(f.try_dual().unwrap().get_id(), f.dual().get_id());
+    assert_eq!(f.try_size().unwrap(), f.size());
+    assert_eq!(x.try_prob_with_complement(&pv, &[true]).unwrap_err(), dropped);
+    assert_eq!(x.try_prob_interval(&ipv, &[true]).unwrap_err(), dropped);
+    assert_eq!(x.try_prob_batch(std::slice::from_ref(&pv), &[true]).unwrap_err(), dropped);
+    assert_eq!(x.try_prob_batch_levels(&[vec![0.5]], &[true]).unwrap_err(), dropped);
+    assert_eq!(x.try_posterior(&pv, &[true]).unwrap_err(), dropped);
+    assert_eq!(x.try_mpe(&pv, &[true]).unwrap_err(), dropped);
+    assert_eq!(x.try_check_coherence().unwrap_err(), dropped);
+    assert_eq!(x.try_irrelevant().unwrap_err(), dropped);
+    assert_eq!(x.try_dual().unwrap_err(), dropped);
+    assert_eq!(x.try_bdd_count(&[true]).unwrap_err(), dropped);
+    assert_eq!(x.try_size().unwrap_err(), dropped);
+    assert_eq!(x.try_dot().unwrap_err(), dropped);
+    assert!(x.try_get_children().is_err());
+
+    // ZDD families: set operations across managers and after a drop.
+    let paths = bss.minpath(&f).unwrap();
+    let cuts = bss.mincut(&f).unwrap();
+    assert_eq!(paths.try_count(&[true]).unwrap(), 2);
+    assert_eq!(paths.try_union(&paths).unwrap().get_id(), paths.get_id());
+    let mut lone = BssMgr::new();
+    let g = lone.rpn("A B |").unwrap();
+    let foreign = lone.minpath(&g).unwrap();
+    assert_eq!(paths.try_union(&foreign).unwrap_err(), RelibError::ManagerMismatch);
+    assert!(cuts.try_intersect(&paths).is_ok());
+    drop(lone);
+    assert_eq!(foreign.try_count(&[true]).unwrap_err(), dropped);
+    assert_eq!(foreign.try_dot().unwrap_err(), dropped);
+    assert_eq!(paths.try_setdiff(&foreign).unwrap_err(), dropped);
+}
+
+#[test]
+fn test_check_coherence_bruteforce() {
+    let names = ["A", "B", "C", "D"];
+    let exprs = ["A B & C |", "A B ~ & C |", "A B ^", "A C &", "B C ~ & A C & |", "A B C ? D |", "C ~"];
+    for expr in exprs {
+        let mut bss = BssMgr::new();
+        for x in names {
+            bss.defvar(x);
+        }
+        let f = bss.rpn(expr).unwrap();
+        let mut eval = |x: &HashMap<String, bool>| -> bool {
+            let pv: HashMap<String, f64> = names
+                .iter()
+                .map(|n| (n.to_string(), if x.get(*n).copied().unwrap_or(false) { 1.0 } else { 0.0 }))
+                .collect();
+            f.prob(&pv, &[true]) == 1.0
+        };
+        let vectors: Vec<HashMap<String, bool>> = (0..16)
+            .map(|b| names.iter().enumerate().map(|(i, n)| (n.to_string(), b >> i & 1 == 1)).collect())
+            .collect();
+        let values: Vec<bool> = vectors.iter().map(&mut eval).collect();
+        let monotone = (0..16).all(|b| (0..4).all(|i| b >> i & 1 == 1 || values[b] <= values[b | 1 << i]));
+        let relevant: Vec<bool> = (0..4).map(|i| (0..16).any(|b| values[b] != values[b ^ 1 << i])).collect();
+
+        match f.check_coherence() {
+            Coherence::Coherent => assert!(monotone, "{}", expr),
+            Coherence::Decreasing { var, low, high } => {
+                assert!(!monotone, "{}", expr);
+                assert!(!low[&var] && high[&var], "{}", expr);
+                assert!(low.keys().all(|x| *x == var || low[x] == high[x]));
+                assert!(eval(&low) && !eval(&high), "{}", expr);
+            }
+        }
+        assert_eq!(bss.minpath(&f).is_some(), monotone, "{}", expr);
+        let irrelevant: Vec<String> = (0..4).filter(|&i| !relevant[i]).map(|i| names[i].to_string()).collect();
+        assert_eq!(f.irrelevant(), irrelevant, "{}", expr);
+    }
+}
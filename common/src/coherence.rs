@@ -0,0 +1,27 @@
+//! The result of the coherence (monotonicity) checks of `relib-bss` and `relib-mss`.
+//!
+//! `minpath` / `mincut` return `None` for a structure function that is not monotone;
+//! `check_coherence` says why, with a counterexample. `S` is the type of a component state:
+//! `bool` for binary components, `usize` for multi-state ones.
+
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Coherence<S> {
+    /// The structure function is nondecreasing in every variable.
+    Coherent,
+    /// A counterexample: `low` and `high` differ only in `var`, which is one state higher in
+    /// `high`, and the function is lower at `high` than at `low`. The vectors give a state
+    /// to every variable of the diagram.
+    Decreasing {
+        var: String,
+        low: HashMap<String, S>,
+        high: HashMap<String, S>,
+    },
+}
+
+impl<S> Coherence<S> {
+    pub fn is_coherent(&self) -> bool {
+        matches!(self, Coherence::Coherent)
+    }
+}
@@ -0,0 +1,71 @@
+//! The error type of the fallible (`try_*`) APIs.
+//!
+//! The value-style APIs of `relib-bss` and `relib-mss` panic on misuse: a node whose
+//! manager has been dropped, an undetermined node reaching a probability pass, an
+//! expression naming an unknown variable. Each of them has a `try_*` counterpart that
+//! reports the problem as a [`RelibError`] instead, for callers that must not abort.
+
+use std::fmt;
+
+use crate::parse::ParseError;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RelibError {
+    /// The manager that owns the node has been dropped.
+    ManagerDropped,
+    /// The operands belong to different managers.
+    ManagerMismatch,
+    /// An `Undet` node was reached where a defined value is needed.
+    Undetermined,
+    /// A name that is not a defined variable.
+    UnknownVariable(String),
+    /// A variable of the diagram without (enough) state probabilities.
+    MissingProbability(String),
+    /// An operand of the wrong kind, e.g. a boolean node where a value is expected.
+    KindMismatch(String),
+    /// A malformed infix expression.
+    Parse(ParseError),
+    /// Any other invalid input.
+    Invalid(String),
+}
+
+impl fmt::Display for RelibError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RelibError::ManagerDropped => write!(f, "the manager of the node has been dropped"),
+            RelibError::ManagerMismatch => write!(f, "the nodes belong to different managers"),
+            RelibError::Undetermined => write!(f, "undetermined node"),
+            RelibError::UnknownVariable(x) => write!(f, "unknown variable {}", x),
+            RelibError::MissingProbability(x) => write!(f, "no probabilities for {}", x),
+            RelibError::KindMismatch(msg) | RelibError::Invalid(msg) => write!(f, "{}", msg),
+            RelibError::Parse(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for RelibError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RelibError::Parse(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ParseError> for RelibError {
+    fn from(e: ParseError) -> Self {
+        RelibError::Parse(e)
+    }
+}
+
+impl From<String> for RelibError {
+    fn from(msg: String) -> Self {
+        RelibError::Invalid(msg)
+    }
+}
+
+impl From<RelibError> for String {
+    fn from(e: RelibError) -> String {
+        e.to_string()
+    }
+}
@@ -0,0 +1,116 @@
+//! Closed intervals of reals, for probabilities that are only known within bounds.
+//!
+//! [`Interval`] implements the arithmetic the probability passes are generic over
+//! (`Add`, `Sub`, `Mul`, `Div`, `From<f64>`), so it can be used directly as `T` in `prob` /
+//! `bmeas` of `relib-bss` and `relib-mss`. Plain interval arithmetic treats every occurrence
+//! of a quantity as independent, so such results are guaranteed enclosures but can be wide;
+//! the `prob_interval` passes of those crates give tighter bounds. Endpoints are computed in
+//! `f64` without directed rounding.
+
+use std::fmt;
+use std::ops::{Add, Div, Mul, Sub};
+
+/// The closed interval `[lo, hi]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Interval {
+    pub lo: f64,
+    pub hi: f64,
+}
+
+impl Interval {
+    /// `[lo, hi]`; panics if `lo > hi`.
+    pub fn new(lo: f64, hi: f64) -> Self {
+        assert!(lo <= hi, "empty interval [{}, {}]", lo, hi);
+        Interval { lo, hi }
+    }
+
+    /// The degenerate interval `[x, x]`.
+    pub fn point(x: f64) -> Self {
+        Interval { lo: x, hi: x }
+    }
+
+    pub fn width(&self) -> f64 {
+        self.hi - self.lo
+    }
+
+    pub fn contains(&self, x: f64) -> bool {
+        self.lo <= x && x <= self.hi
+    }
+
+    /// The interval clipped to `[0, 1]`, the range of any probability.
+    pub fn clamp_prob(&self) -> Self {
+        Interval {
+            lo: self.lo.clamp(0.0, 1.0),
+            hi: self.hi.clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl From<f64> for Interval {
+    fn from(x: f64) -> Self {
+        Interval::point(x)
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}, {}]", self.lo, self.hi)
+    }
+}
+
+impl Add for Interval {
+    type Output = Interval;
+
+    fn add(self, other: Interval) -> Interval {
+        Interval {
+            lo: self.lo + other.lo,
+            hi: self.hi + other.hi,
+        }
+    }
+}
+
+impl Sub for Interval {
+    type Output = Interval;
+
+    fn sub(self, other: Interval) -> Interval {
+        Interval {
+            lo: self.lo - other.hi,
+            hi: self.hi - other.lo,
+        }
+    }
+}
+
+impl Mul for Interval {
+    type Output = Interval;
+
+    fn mul(self, other: Interval) -> Interval {
+        let p = [
+            self.lo * other.lo,
+            self.lo * other.hi,
+            self.hi * other.lo,
+            self.hi * other.hi,
+        ];
+        Interval {
+            lo: p.iter().copied().fold(f64::INFINITY, f64::min),
+            hi: p.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+}
+
+impl Div for Interval {
+    type Output = Interval;
+
+    /// Division by an interval containing `0` gives `[-∞, ∞]`.
+    fn div(self, other: Interval) -> Interval {
+        if other.contains(0.0) {
+            return Interval {
+                lo: f64::NEG_INFINITY,
+                hi: f64::INFINITY,
+            };
+        }
+        self * Interval {
+            lo: 1.0 / other.hi,
+            hi: 1.0 / other.lo,
+        }
+    }
+}
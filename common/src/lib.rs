@@ -7,7 +7,13 @@
 //! the core traits ([`Terminal`](nodes::Terminal), [`NonTerminal`](nodes::NonTerminal),
 //! [`NodeHeader`](nodes::NodeHeader), [`DDForest`](nodes::DDForest), [`Dot`](dot::Dot))
 //! that the DD managers implement. It also provides the shared, direct-mapped
-//! [`ComputeCache`](compute_cache::ComputeCache) used to memoize `apply` results.
+//! [`ComputeCache`](compute_cache::ComputeCache) used to memoize `apply` results, and the
+//! tokenizer and [`ParseError`](parse::ParseError) behind the infix expression parsers, and
+//! the probability types [`Interval`](interval::Interval) (bounded evaluation) and
+//! [`LogProb`](numeric::LogProb), [`DoubleDouble`](numeric::DoubleDouble),
+//! [`Rational`](numeric::Rational) (accurate evaluation of rare events), the error type
+//! [`RelibError`](error::RelibError) of the fallible `try_*` APIs, and the result
+//! [`Coherence`](coherence::Coherence) of the monotonicity checks.
 //!
 //! **This crate is not meant to be used directly.** Depend on one of the crates built on
 //! top of it instead:
@@ -26,17 +32,27 @@
 //! Part of the Rust engine behind the
 //! [`relibmss`](https://github.com/MssReliab/relibmss) Python package.
 
+pub mod coherence;
 pub mod common;
 pub mod compute_cache;
 pub mod dot;
+pub mod error;
+pub mod interval;
 pub mod nodes;
+pub mod numeric;
+pub mod parse;
 
 pub mod prelude {
     pub use std::ops::Index;
     pub use std::slice::Iter;
     pub use crate::common::{BddHashSet, BddHashMap};
     pub use crate::common::{HeaderId, Level, NodeId, OperationId};
+    pub use crate::coherence::Coherence;
     pub use crate::compute_cache::ComputeCache;
     pub use crate::nodes::{NonTerminal, Terminal, NodeHeader, DDForest};
     pub use crate::dot::Dot;
+    pub use crate::error::RelibError;
+    pub use crate::interval::Interval;
+    pub use crate::numeric::{DoubleDouble, LogProb, Rational};
+    pub use crate::parse::{ParseError, Span};
 }
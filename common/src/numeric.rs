@@ -0,0 +1,359 @@
+//! Number types for probabilities that `f64` cannot carry accurately.
+//!
+//! Each type implements the arithmetic the probability passes are generic over (`Add`,
+//! `Sub`, `Mul`, `Div`, `From<f64>`, `Copy`), so it can be used directly as `T` in `prob` /
+//! `bmeas` of `relib-bss` and `relib-mss`:
+//!
+//! - [`LogProb`] stores `ln |x|` and a sign: products of many small probabilities neither
+//!   underflow nor lose relative precision, and `1 − p` is `ln_1p(−p)`.
+//! - [`DoubleDouble`] is an unevaluated sum `hi + lo` of two `f64` (about 32 significant
+//!   digits), so a reliability of `1 − 1e-20` and its complement are both representable.
+//! - [`Rational`] is an exact fraction of `i128`s, for checking small models exactly;
+//!   arithmetic panics on overflow rather than rounding.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// A real number stored as its sign and the natural logarithm of its magnitude.
+///
+/// Zero is `ln = −∞`. Addition of two terms costs one `exp` and one `ln_1p`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LogProb {
+    neg: bool,
+    ln: f64,
+}
+
+impl LogProb {
+    /// The number `exp(ln)`.
+    pub fn from_ln(ln: f64) -> Self {
+        LogProb::make(false, ln)
+    }
+
+    fn make(neg: bool, ln: f64) -> Self {
+        LogProb {
+            neg: neg && ln != f64::NEG_INFINITY,
+            ln,
+        }
+    }
+
+    /// `ln |x|`.
+    pub fn ln(&self) -> f64 {
+        self.ln
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.neg
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        let m = self.ln.exp();
+        if self.neg {
+            -m
+        } else {
+            m
+        }
+    }
+}
+
+impl From<f64> for LogProb {
+    fn from(x: f64) -> Self {
+        LogProb::make(x < 0.0, x.abs().ln())
+    }
+}
+
+impl fmt::Display for LogProb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}exp({})", if self.neg { "-" } else { "" }, self.ln)
+    }
+}
+
+impl Neg for LogProb {
+    type Output = LogProb;
+
+    fn neg(self) -> LogProb {
+        LogProb::make(!self.neg, self.ln)
+    }
+}
+
+impl Add for LogProb {
+    type Output = LogProb;
+
+    fn add(self, other: LogProb) -> LogProb {
+        let (big, small) = if self.ln >= other.ln { (self, other) } else { (other, self) };
+        if small.ln == f64::NEG_INFINITY {
+            return big;
+        }
+        let r = (small.ln - big.ln).exp();
+        if big.neg == small.neg {
+            LogProb::make(big.neg, big.ln + r.ln_1p())
+        } else {
+            LogProb::make(big.neg, big.ln + (-r).ln_1p())
+        }
+    }
+}
+
+impl Sub for LogProb {
+    type Output = LogProb;
+
+    fn sub(self, other: LogProb) -> LogProb {
+        self + (-other)
+    }
+}
+
+impl Mul for LogProb {
+    type Output = LogProb;
+
+    fn mul(self, other: LogProb) -> LogProb {
+        LogProb::make(self.neg != other.neg, self.ln + other.ln)
+    }
+}
+
+impl Div for LogProb {
+    type Output = LogProb;
+
+    fn div(self, other: LogProb) -> LogProb {
+        LogProb::make(self.neg != other.neg, self.ln - other.ln)
+    }
+}
+
+/// A double-double number: the unevaluated sum `hi + lo` with `|lo| <= ulp(hi) / 2`.
+///
+/// Addition and multiplication use the error-free transformations `two_sum` and `two_prod`
+/// (the latter through `f64::mul_add`), giving about 106 bits of precision.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DoubleDouble {
+    pub hi: f64,
+    pub lo: f64,
+}
+
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let s = a + b;
+    let bb = s - a;
+    (s, (a - (s - bb)) + (b - bb))
+}
+
+fn quick_two_sum(a: f64, b: f64) -> (f64, f64) {
+    let s = a + b;
+    (s, b - (s - a))
+}
+
+fn two_prod(a: f64, b: f64) -> (f64, f64) {
+    let p = a * b;
+    (p, a.mul_add(b, -p))
+}
+
+impl DoubleDouble {
+    pub fn to_f64(&self) -> f64 {
+        self.hi + self.lo
+    }
+}
+
+impl From<f64> for DoubleDouble {
+    fn from(x: f64) -> Self {
+        DoubleDouble { hi: x, lo: 0.0 }
+    }
+}
+
+impl fmt::Display for DoubleDouble {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} + {}", self.hi, self.lo)
+    }
+}
+
+impl Neg for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn neg(self) -> DoubleDouble {
+        DoubleDouble {
+            hi: -self.hi,
+            lo: -self.lo,
+        }
+    }
+}
+
+impl Add for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn add(self, other: DoubleDouble) -> DoubleDouble {
+        let (s, e) = two_sum(self.hi, other.hi);
+        let (t, f) = two_sum(self.lo, other.lo);
+        let (s, e) = quick_two_sum(s, e + t);
+        let (hi, lo) = quick_two_sum(s, e + f);
+        DoubleDouble { hi, lo }
+    }
+}
+
+impl Sub for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn sub(self, other: DoubleDouble) -> DoubleDouble {
+        self + (-other)
+    }
+}
+
+impl Mul for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn mul(self, other: DoubleDouble) -> DoubleDouble {
+        let (p, e) = two_prod(self.hi, other.hi);
+        let (hi, lo) = quick_two_sum(p, e + (self.hi * other.lo + self.lo * other.hi));
+        DoubleDouble { hi, lo }
+    }
+}
+
+impl Div for DoubleDouble {
+    type Output = DoubleDouble;
+
+    /// Long division: three `f64` quotient digits, each correcting the remainder.
+    fn div(self, other: DoubleDouble) -> DoubleDouble {
+        let q1 = self.hi / other.hi;
+        let r = self - other * DoubleDouble::from(q1);
+        let q2 = r.hi / other.hi;
+        let r = r - other * DoubleDouble::from(q2);
+        let q3 = r.hi / other.hi;
+        let (hi, lo) = quick_two_sum(q1, q2);
+        DoubleDouble { hi, lo } + DoubleDouble::from(q3)
+    }
+}
+
+/// An exact fraction `num / den` in lowest terms with `den > 0`.
+///
+/// Meant for validating small models exactly: give probabilities with [`Rational::new`]
+/// (`Rational::new(1, 10)`, not `Rational::from(0.1)`, which is the exact but unwieldy
+/// binary value of the `f64`). Every operation panics if an intermediate overflows `i128`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    num: i128,
+    den: i128,
+}
+
+fn gcd(mut a: i128, mut b: i128) -> i128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.abs()
+}
+
+fn checked(x: Option<i128>) -> i128 {
+    x.expect("rational arithmetic overflowed i128")
+}
+
+impl Rational {
+    /// `num / den` in lowest terms; panics if `den == 0`.
+    pub fn new(num: i128, den: i128) -> Self {
+        assert!(den != 0, "rational with zero denominator");
+        let g = gcd(num, den).max(1);
+        let s = if den < 0 { -1 } else { 1 };
+        Rational {
+            num: s * num / g,
+            den: s * den / g,
+        }
+    }
+
+    pub fn numer(&self) -> i128 {
+        self.num
+    }
+
+    pub fn denom(&self) -> i128 {
+        self.den
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+}
+
+impl From<f64> for Rational {
+    /// The exact value of `x`; panics if it is not finite or does not fit in `i128`.
+    fn from(x: f64) -> Self {
+        assert!(x.is_finite(), "{} is not a rational number", x);
+        if x == 0.0 {
+            return Rational { num: 0, den: 1 };
+        }
+        let bits = x.to_bits();
+        let exp = ((bits >> 52) & 0x7ff) as i32;
+        let frac = (bits & ((1 << 52) - 1)) as i128;
+        let (mantissa, exp) = if exp == 0 { (frac, -1074) } else { (frac | 1 << 52, exp - 1075) };
+        let mantissa = if x < 0.0 { -mantissa } else { mantissa };
+        if exp >= 0 {
+            assert!(exp <= 73, "{} is too large for an i128 rational", x);
+            Rational::new(mantissa << exp, 1)
+        } else {
+            assert!(exp >= -126, "{} is too small for an i128 rational", x);
+            Rational::new(mantissa, 1 << -exp)
+        }
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.num, self.den)
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Rational) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Rational) -> Ordering {
+        let a = checked(self.num.checked_mul(other.den));
+        let b = checked(other.num.checked_mul(self.den));
+        a.cmp(&b)
+    }
+}
+
+impl Neg for Rational {
+    type Output = Rational;
+
+    fn neg(self) -> Rational {
+        Rational {
+            num: -self.num,
+            den: self.den,
+        }
+    }
+}
+
+impl Add for Rational {
+    type Output = Rational;
+
+    fn add(self, other: Rational) -> Rational {
+        let g = gcd(self.den, other.den);
+        let a = checked(self.num.checked_mul(other.den / g));
+        let b = checked(other.num.checked_mul(self.den / g));
+        Rational::new(checked(a.checked_add(b)), checked((self.den / g).checked_mul(other.den)))
+    }
+}
+
+impl Sub for Rational {
+    type Output = Rational;
+
+    fn sub(self, other: Rational) -> Rational {
+        self + (-other)
+    }
+}
+
+impl Mul for Rational {
+    type Output = Rational;
+
+    fn mul(self, other: Rational) -> Rational {
+        let g1 = gcd(self.num, other.den).max(1);
+        let g2 = gcd(other.num, self.den).max(1);
+        Rational::new(
+            checked((self.num / g1).checked_mul(other.num / g2)),
+            checked((self.den / g2).checked_mul(other.den / g1)),
+        )
+    }
+}
+
+impl Div for Rational {
+    type Output = Rational;
+
+    fn div(self, other: Rational) -> Rational {
+        assert!(other.num != 0, "rational division by zero");
+        self * Rational::new(other.den, other.num)
+    }
+}
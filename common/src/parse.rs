@@ -0,0 +1,288 @@
+//! Tokenizer and error type shared by the infix expression parsers (`BddMgr::parse` in
+//! `relib-bss`, `MddMgr::parse` in `relib-mss`).
+//!
+//! Tokens and errors carry a [`Span`]: the byte range of the offending text in the source,
+//! which [`ParseError::line_col`] turns into a 1-based line and column.
+
+use std::fmt;
+use std::ops::Range;
+
+/// Byte range in the parsed source.
+pub type Span = Range<usize>;
+
+/// A parse error with the source span it refers to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl ParseError {
+    pub fn new(message: impl Into<String>, span: Span) -> Self {
+        ParseError {
+            message: message.into(),
+            span,
+        }
+    }
+
+    /// 1-based line and column (in characters) of the start of the span in `src`.
+    pub fn line_col(&self, src: &str) -> (usize, usize) {
+        let start = self.span.start.min(src.len());
+        let before = &src[..start];
+        let line = before.matches('\n').count() + 1;
+        let col = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+        (line, col)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (at {}..{})", self.message, self.span.start, self.span.end)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<ParseError> for String {
+    fn from(e: ParseError) -> String {
+        e.to_string()
+    }
+}
+
+/// A token of an infix expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    /// Identifier: `[A-Za-z_][A-Za-z0-9_.]*`.
+    Ident(String),
+    /// `"quoted"` identifier (`\"` and `\\` escape); never a keyword.
+    Quoted(String),
+    /// Unsigned number literal as written (`12`, `0.5`, `1e-3`).
+    Number(String),
+    /// One of the punctuation strings given to [`tokenize`].
+    Punct(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(s) | Token::Number(s) => write!(f, "`{}`", s),
+            Token::Quoted(s) => write!(f, "`\"{}\"`", s),
+            Token::Punct(p) => write!(f, "`{}`", p),
+        }
+    }
+}
+
+/// Splits `src` into tokens. `puncts` lists the operators of the grammar; the longest match
+/// wins. Whitespace separates tokens and `//` starts a comment that runs to the end of the
+/// line.
+pub fn tokenize(src: &str, puncts: &[&'static str]) -> Result<Vec<(Token, Span)>, ParseError> {
+    let bytes = src.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < src.len() {
+        let c = src[i..].chars().next().unwrap();
+        let start = i;
+        if c.is_whitespace() {
+            i += c.len_utf8();
+        } else if src[i..].starts_with("//") {
+            i = src[i..].find('\n').map_or(src.len(), |n| i + n);
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            while i < src.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_' || bytes[i] == b'.') {
+                i += 1;
+            }
+            tokens.push((Token::Ident(src[start..i].to_string()), start..i));
+        } else if c.is_ascii_digit() {
+            while i < src.len() && bytes[i].is_ascii_digit() {
+                i += 1;
+            }
+            if i + 1 < src.len() && bytes[i] == b'.' && bytes[i + 1].is_ascii_digit() {
+                i += 1;
+                while i < src.len() && bytes[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            if i < src.len() && (bytes[i] == b'e' || bytes[i] == b'E') {
+                let mut j = i + 1;
+                if j < src.len() && (bytes[j] == b'+' || bytes[j] == b'-') {
+                    j += 1;
+                }
+                if j < src.len() && bytes[j].is_ascii_digit() {
+                    i = j;
+                    while i < src.len() && bytes[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            tokens.push((Token::Number(src[start..i].to_string()), start..i));
+        } else if c == '"' {
+            let mut name = String::new();
+            i += 1;
+            loop {
+                match src[i..].chars().next() {
+                    None => return Err(ParseError::new("unterminated quoted identifier", start..src.len())),
+                    Some('"') => {
+                        i += 1;
+                        break;
+                    }
+                    Some('\\') if matches!(src[i + 1..].chars().next(), Some('"' | '\\')) => {
+                        name.push(bytes[i + 1] as char);
+                        i += 2;
+                    }
+                    Some(ch) => {
+                        name.push(ch);
+                        i += ch.len_utf8();
+                    }
+                }
+            }
+            tokens.push((Token::Quoted(name), start..i));
+        } else {
+            let p = puncts
+                .iter()
+                .filter(|p| src[i..].starts_with(**p))
+                .max_by_key(|p| p.len())
+                .ok_or_else(|| {
+                    ParseError::new(format!("unexpected character `{}`", c), start..start + c.len_utf8())
+                })?;
+            i += p.len();
+            tokens.push((Token::Punct(p), start..i));
+        }
+    }
+    Ok(tokens)
+}
+
+/// Cursor over a token list for recursive-descent parsing.
+pub struct TokenStream {
+    tokens: Vec<(Token, Span)>,
+    pos: usize,
+    end: usize,
+}
+
+impl TokenStream {
+    pub fn new(src: &str, puncts: &[&'static str]) -> Result<Self, ParseError> {
+        Ok(TokenStream {
+            tokens: tokenize(src, puncts)?,
+            pos: 0,
+            end: src.len(),
+        })
+    }
+
+    pub fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    pub fn peek_at(&self, k: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + k).map(|(t, _)| t)
+    }
+
+    /// Span of the next token (an empty span at the end of input if none).
+    pub fn span(&self) -> Span {
+        self.tokens
+            .get(self.pos)
+            .map(|(_, s)| s.clone())
+            .unwrap_or(self.end..self.end)
+    }
+
+    /// End offset of the last consumed token.
+    pub fn last_end(&self) -> usize {
+        if self.pos == 0 {
+            0
+        } else {
+            self.tokens[self.pos - 1].1.end
+        }
+    }
+
+    pub fn is_end(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    pub fn bump(&mut self) -> Option<(Token, Span)> {
+        let t = self.tokens.get(self.pos).cloned();
+        if t.is_some() {
+            self.pos += 1;
+        }
+        t
+    }
+
+    /// "expected `what`, found ..." at the next token.
+    pub fn unexpected<T>(&self, what: &str) -> Result<T, ParseError> {
+        let found = match self.peek() {
+            Some(t) => t.to_string(),
+            None => "end of input".to_string(),
+        };
+        Err(ParseError::new(format!("expected {}, found {}", what, found), self.span()))
+    }
+
+    pub fn is_punct(&self, p: &str) -> bool {
+        matches!(self.peek(), Some(Token::Punct(q)) if *q == p)
+    }
+
+    pub fn is_ident(&self, word: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(w)) if w == word)
+    }
+
+    /// Consumes the punctuation `p` if it is next.
+    pub fn eat(&mut self, p: &str) -> bool {
+        let found = self.is_punct(p);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    /// Consumes the (unquoted) keyword `word` if it is next.
+    pub fn eat_ident(&mut self, word: &str) -> bool {
+        let found = self.is_ident(word);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    pub fn expect(&mut self, p: &str) -> Result<Span, ParseError> {
+        let span = self.span();
+        if self.eat(p) {
+            Ok(span)
+        } else {
+            self.unexpected(&format!("`{}`", p))
+        }
+    }
+
+    pub fn expect_ident(&mut self, word: &str) -> Result<Span, ParseError> {
+        let span = self.span();
+        if self.eat_ident(word) {
+            Ok(span)
+        } else {
+            self.unexpected(&format!("`{}`", word))
+        }
+    }
+
+    /// Consumes an identifier (quoted or not) that is not one of `reserved`.
+    pub fn name(&mut self, reserved: &[&str]) -> Result<(String, Span), ParseError> {
+        let span = self.span();
+        match self.peek().cloned() {
+            Some(Token::Ident(w)) if reserved.contains(&w.as_str()) => Err(ParseError::new(
+                format!("`{}` is a keyword; quote it to use it as a name", w),
+                span,
+            )),
+            Some(Token::Ident(w)) | Some(Token::Quoted(w)) => {
+                self.pos += 1;
+                Ok((w, span))
+            }
+            _ => self.unexpected("a name"),
+        }
+    }
+
+    /// Consumes a number literal and parses it as `T`.
+    pub fn number<T: std::str::FromStr>(&mut self, what: &str) -> Result<(T, Span), ParseError> {
+        let span = self.span();
+        match self.peek().cloned() {
+            Some(Token::Number(w)) => {
+                self.pos += 1;
+                w.parse::<T>()
+                    .map(|v| (v, span.clone()))
+                    .map_err(|_| ParseError::new(format!("`{}` is not {}", w, what), span))
+            }
+            _ => self.unexpected(what),
+        }
+    }
+}
@@ -41,6 +41,7 @@ pub mod mtmdd_ops;
 pub mod mtmdd2;
 pub mod mtmdd2_ops;
 pub mod mtmdd2_dot;
+pub mod mtmdd2_dpld;
 
 pub mod zmdd;
 pub mod zmdd_dot;
@@ -0,0 +1,124 @@
+//! Cofactors and direct partial logic derivatives (DPLD) of MTMDD2 functions.
+//!
+//! The DPLD `∂φ(j→h)/∂x(s→r)` of a multi-state function `φ` is the boolean function of the
+//! other variables that is `1` exactly where changing `x` from state `s` to `r` changes `φ`
+//! from `j` to `h`:
+//!
+//! ```text
+//! ∂φ(j→h)/∂x(s→r) = (φ|x=s == j) ∧ (φ|x=r == h)
+//! ```
+//!
+//! Both cofactors `φ|x=s` are built by [`restrict`](MtMdd2Manager::restrict); the
+//! comparisons go through the cross-forest `veq`, so a value function yields a node of the
+//! bool forest. [`dpld`](MtMdd2Manager::dpld) takes sets of system states on both sides,
+//! which gives the integrated DPLDs (`j → any h < j`, …) in one diagram.
+
+use common::prelude::*;
+use crate::mdd;
+use crate::mtmdd;
+use crate::mtmdd2::*;
+use crate::nodes::*;
+
+type VNode<V> = mtmdd::Node<V>;
+type BNode = mdd::Node;
+
+impl<V> MtMdd2Manager<V>
+where
+    V: MddValue,
+{
+    /// The cofactor of `f` with the variable at `level` fixed to state `s`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `s` is not a state of a node of `f` at `level`.
+    pub fn restrict(&mut self, f: Node, level: Level, s: usize) -> Node {
+        let mut memo = BddHashMap::default();
+        match f {
+            Node::Value(fnode) => Node::Value(self.vrestrict(fnode, level, s, &mut memo)),
+            Node::Bool(fnode) => Node::Bool(self.brestrict(fnode, level, s, &mut memo)),
+        }
+    }
+
+    fn vrestrict(&mut self, f: NodeId, level: Level, s: usize, memo: &mut BddHashMap<NodeId, NodeId>) -> NodeId {
+        match self.mtmdd().level(&f) {
+            Some(l) if l >= level => (),
+            _ => return f,
+        }
+        if let Some(&x) = memo.get(&f) {
+            return x;
+        }
+        let node = match self.mtmdd().get_node(&f).unwrap() {
+            VNode::NonTerminal(fnode) => {
+                let headerid = fnode.headerid();
+                let fnodeid: Vec<_> = fnode.iter().collect();
+                if self.mtmdd().level(&f) == Some(level) {
+                    fnodeid[s]
+                } else {
+                    let nodes: Vec<_> = fnodeid.into_iter().map(|f| self.vrestrict(f, level, s, memo)).collect();
+                    self.mtmdd_mut().create_node(headerid, &nodes)
+                }
+            }
+            _ => f,
+        };
+        memo.insert(f, node);
+        node
+    }
+
+    fn brestrict(&mut self, f: NodeId, level: Level, s: usize, memo: &mut BddHashMap<NodeId, NodeId>) -> NodeId {
+        match self.mdd().level(&f) {
+            Some(l) if l >= level => (),
+            _ => return f,
+        }
+        if let Some(&x) = memo.get(&f) {
+            return x;
+        }
+        let node = match self.mdd().get_node(&f).unwrap() {
+            BNode::NonTerminal(fnode) => {
+                let headerid = fnode.headerid();
+                let fnodeid: Vec<_> = fnode.iter().collect();
+                if self.mdd().level(&f) == Some(level) {
+                    fnodeid[s]
+                } else {
+                    let nodes: Vec<_> = fnodeid.into_iter().map(|f| self.brestrict(f, level, s, memo)).collect();
+                    self.mdd_mut().create_node(headerid, &nodes)
+                }
+            }
+            _ => f,
+        };
+        memo.insert(f, node);
+        node
+    }
+
+    /// The boolean function "`f` takes one of `values`". A boolean `f` has the values
+    /// `V::from(0)` and `V::from(1)`; any other value never matches.
+    pub fn member(&mut self, f: Node, values: &[V]) -> Node {
+        let mut result = self.zero();
+        for &v in values.iter() {
+            let c = match f {
+                Node::Value(_) => self.value(v),
+                Node::Bool(_) if v == V::from(0) => self.zero(),
+                Node::Bool(_) if v == V::from(1) => self.one(),
+                Node::Bool(_) => continue,
+            };
+            let tmp = self.eq(f, c);
+            result = self.or(result, tmp);
+        }
+        result
+    }
+
+    /// The (integrated) DPLD of `f` for the change of the variable at `level` from state `s`
+    /// to `r`: `1` where `f|x=s` is in `from` and `f|x=r` is in `to`. With one value on each
+    /// side this is `∂f(j→h)/∂x(s→r)`. The result is a bool node that does not depend on
+    /// the variable itself.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `s` or `r` is not a state of the variable.
+    pub fn dpld(&mut self, f: Node, level: Level, s: usize, r: usize, from: &[V], to: &[V]) -> Node {
+        let fs = self.restrict(f, level, s);
+        let fr = self.restrict(f, level, r);
+        let before = self.member(fs, from);
+        let after = self.member(fr, to);
+        self.and(before, after)
+    }
+}
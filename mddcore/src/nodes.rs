@@ -80,7 +80,23 @@ pub trait MddValue:
     + Rem<Output = Self>
     + From<i32>
 {
+    /// The value as an `f64`, for moments of a performance distribution.
+    ///
+    /// The default parses the `Display` output (`NaN` if that is not a number); numeric
+    /// types override it with a cast.
+    fn to_f64(self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+}
+
+impl MddValue for i32 {
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
 }
 
-impl MddValue for i32 {}
-impl MddValue for i64 {}
+impl MddValue for i64 {
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
@@ -1,9 +1,16 @@
-//! Label-wise set operations on a [`ZmddManager`]: `intersect` (`(f∩g)_r = f_r ∩ g_r`) and
-//! `setdiff` (`(f−g)_r = f_r − g_r`). Both preserve the disjoint/partition form (each vector
-//! stays in at most one label), so they are closed on a single multi-terminal ZMDD. (`union`
-//! does NOT preserve it — a vector can land in two labels — and is deferred.)
+//! Set operations on a [`ZmddManager`] family `f: R → 2^S`.
 //!
-//! The recursion mirrors an apply, but respects zero-suppression: when one operand has a
+//! - **Label-wise** `intersect` (`(f∩g)_r = f_r ∩ g_r`) and `setdiff` (`(f−g)_r = f_r − g_r`)
+//!   preserve the disjoint/partition form (each vector stays in at most one label).
+//! - `union` (`f_r ∪ g_r`) and `product` (`{s ∨ t : s ∈ f_r, t ∈ g_r}`, the component-wise
+//!   join of sparse vectors) can put one vector under two labels; a single multi-terminal
+//!   ZMDD holds one, so such a vector keeps the **greater** label.
+//! - `minimal` / `maximal` keep the vectors of each `f_r` that no other vector of `f_r` lies
+//!   below / above component-wise, built from `nonsup` / `nonsub` (drop the vectors of `f`
+//!   that dominate / are dominated by a vector of `g` with the same label).
+//! - `relabel` maps every terminal label, dropping the vectors whose label maps to `None`.
+//!
+//! The recursions mirror an apply, but respect zero-suppression: when one operand has a
 //! variable `X` the other lacks, that operand's family has `X=0`, so we descend the
 //! **0-edge** only (same principle as `bss::bdd_minsol::without`'s level-mismatch arm) rather
 //! than every edge. Getting this wrong reproduces the non-minimal bug class.
@@ -13,10 +20,18 @@ use crate::mtmdd::Node;
 use crate::nodes::*;
 use common::prelude::*;
 
+use std::collections::HashMap;
+
 #[derive(Debug, PartialEq, Eq, Hash)]
 pub enum ZmddOperation {
     Intersect,
     Setdiff,
+    Union,
+    Product,
+    Minimal,
+    Maximal,
+    NonSup,
+    NonSub,
 }
 
 impl ZmddOperation {
@@ -25,6 +40,12 @@ impl ZmddOperation {
         match self {
             ZmddOperation::Intersect => 0,
             ZmddOperation::Setdiff => 1,
+            ZmddOperation::Union => 2,
+            ZmddOperation::Product => 3,
+            ZmddOperation::Minimal => 4,
+            ZmddOperation::Maximal => 5,
+            ZmddOperation::NonSup => 6,
+            ZmddOperation::NonSub => 7,
         }
     }
 }
@@ -142,4 +163,332 @@ where
         self.cache_put(key, node);
         node
     }
+
+    /// Label-wise union: `(f ∪ g)_r = f_r ∪ g_r`; a vector of both families under different
+    /// labels keeps the greater one.
+    pub fn union(&mut self, mut f: NodeId, mut g: NodeId) -> NodeId {
+        if f > g {
+            std::mem::swap(&mut f, &mut g);
+        }
+        let key = (ZmddOperation::Union, f, g);
+        if let Some(x) = self.cache_get(&key) {
+            return x;
+        }
+        let node = match (self.get_node(&f).unwrap(), self.get_node(&g).unwrap()) {
+            (Node::Undet, _) => g,
+            (_, Node::Undet) => f,
+            (Node::Terminal(fnode), Node::Terminal(gnode)) => {
+                let v = fnode.value().max(gnode.value());
+                self.value(v)
+            }
+            // f has the higher variable X; g's family has X=0, so it joins f's 0-edge only.
+            (Node::NonTerminal(fnode), Node::Terminal(_)) => {
+                let h = fnode.headerid();
+                let mut ch: Vec<NodeId> = fnode.iter().collect();
+                ch[0] = self.union(ch[0], g);
+                self.create_node(h, &ch)
+            }
+            (Node::Terminal(_), Node::NonTerminal(gnode)) => {
+                let h = gnode.headerid();
+                let mut ch: Vec<NodeId> = gnode.iter().collect();
+                ch[0] = self.union(f, ch[0]);
+                self.create_node(h, &ch)
+            }
+            (Node::NonTerminal(fnode), Node::NonTerminal(_)) if self.level(&f) > self.level(&g) => {
+                let h = fnode.headerid();
+                let mut ch: Vec<NodeId> = fnode.iter().collect();
+                ch[0] = self.union(ch[0], g);
+                self.create_node(h, &ch)
+            }
+            (Node::NonTerminal(_), Node::NonTerminal(gnode)) if self.level(&f) < self.level(&g) => {
+                let h = gnode.headerid();
+                let mut ch: Vec<NodeId> = gnode.iter().collect();
+                ch[0] = self.union(f, ch[0]);
+                self.create_node(h, &ch)
+            }
+            (Node::NonTerminal(fnode), Node::NonTerminal(gnode)) => {
+                let h = fnode.headerid();
+                let fv: Vec<NodeId> = fnode.iter().collect();
+                let gv: Vec<NodeId> = gnode.iter().collect();
+                let ch: Vec<NodeId> = fv
+                    .iter()
+                    .zip(gv.iter())
+                    .map(|(&a, &b)| self.union(a, b))
+                    .collect();
+                self.create_node(h, &ch)
+            }
+        };
+        self.cache_put(key, node);
+        node
+    }
+
+    /// Label-wise product: `(f ⊔ g)_r = { s ∨ t : s ∈ f_r, t ∈ g_r }`, where `s ∨ t` takes
+    /// the greater state of every component. Joins reached from several labels keep the
+    /// greater one, as in [`union`](Self::union).
+    pub fn product(&mut self, mut f: NodeId, mut g: NodeId) -> NodeId {
+        if f > g {
+            std::mem::swap(&mut f, &mut g);
+        }
+        let key = (ZmddOperation::Product, f, g);
+        if let Some(x) = self.cache_get(&key) {
+            return x;
+        }
+        let node = match (self.get_node(&f).unwrap(), self.get_node(&g).unwrap()) {
+            (Node::Undet, _) | (_, Node::Undet) => self.undet(),
+            (Node::Terminal(fnode), Node::Terminal(gnode)) => {
+                let (a, b) = (fnode.value(), gnode.value());
+                if a == b {
+                    self.value(a)
+                } else {
+                    self.undet()
+                }
+            }
+            // f has the higher variable X and g's vectors have X=0: the join keeps f's state.
+            (Node::NonTerminal(fnode), Node::Terminal(_)) => {
+                let h = fnode.headerid();
+                let fv: Vec<NodeId> = fnode.iter().collect();
+                let ch: Vec<NodeId> = fv.into_iter().map(|a| self.product(a, g)).collect();
+                self.create_node(h, &ch)
+            }
+            (Node::Terminal(_), Node::NonTerminal(gnode)) => {
+                let h = gnode.headerid();
+                let gv: Vec<NodeId> = gnode.iter().collect();
+                let ch: Vec<NodeId> = gv.into_iter().map(|b| self.product(f, b)).collect();
+                self.create_node(h, &ch)
+            }
+            (Node::NonTerminal(fnode), Node::NonTerminal(_)) if self.level(&f) > self.level(&g) => {
+                let h = fnode.headerid();
+                let fv: Vec<NodeId> = fnode.iter().collect();
+                let ch: Vec<NodeId> = fv.into_iter().map(|a| self.product(a, g)).collect();
+                self.create_node(h, &ch)
+            }
+            (Node::NonTerminal(_), Node::NonTerminal(gnode)) if self.level(&f) < self.level(&g) => {
+                let h = gnode.headerid();
+                let gv: Vec<NodeId> = gnode.iter().collect();
+                let ch: Vec<NodeId> = gv.into_iter().map(|b| self.product(f, b)).collect();
+                self.create_node(h, &ch)
+            }
+            // Same X: the join has X = max(i, j).
+            (Node::NonTerminal(fnode), Node::NonTerminal(gnode)) => {
+                let h = fnode.headerid();
+                let fv: Vec<NodeId> = fnode.iter().collect();
+                let gv: Vec<NodeId> = gnode.iter().collect();
+                let mut ch = vec![self.undet(); fv.len()];
+                for (i, &a) in fv.iter().enumerate() {
+                    for (j, &b) in gv.iter().enumerate() {
+                        let tmp = self.product(a, b);
+                        ch[i.max(j)] = self.union(ch[i.max(j)], tmp);
+                    }
+                }
+                self.create_node(h, &ch)
+            }
+        };
+        self.cache_put(key, node);
+        node
+    }
+
+    /// The vectors of each `f_r` that have no other vector of `f_r` below them
+    /// component-wise.
+    pub fn minimal(&mut self, f: NodeId) -> NodeId {
+        let key = (ZmddOperation::Minimal, f, f);
+        if let Some(x) = self.cache_get(&key) {
+            return x;
+        }
+        let node = match self.get_node(&f).unwrap() {
+            Node::Undet | Node::Terminal(_) => f,
+            // A vector with X=i is dominated by one with X=j<i and the rest below it.
+            Node::NonTerminal(fnode) => {
+                let h = fnode.headerid();
+                let fv: Vec<NodeId> = fnode.iter().collect();
+                let mut ch = Vec::with_capacity(fv.len());
+                for i in 0..fv.len() {
+                    let mut c = self.minimal(fv[i]);
+                    for &lower in fv[..i].iter() {
+                        c = self.nonsup(c, lower);
+                    }
+                    ch.push(c);
+                }
+                self.create_node(h, &ch)
+            }
+        };
+        self.cache_put(key, node);
+        node
+    }
+
+    /// The vectors of each `f_r` that have no other vector of `f_r` above them
+    /// component-wise.
+    pub fn maximal(&mut self, f: NodeId) -> NodeId {
+        let key = (ZmddOperation::Maximal, f, f);
+        if let Some(x) = self.cache_get(&key) {
+            return x;
+        }
+        let node = match self.get_node(&f).unwrap() {
+            Node::Undet | Node::Terminal(_) => f,
+            Node::NonTerminal(fnode) => {
+                let h = fnode.headerid();
+                let fv: Vec<NodeId> = fnode.iter().collect();
+                let mut ch = Vec::with_capacity(fv.len());
+                for i in 0..fv.len() {
+                    let mut c = self.maximal(fv[i]);
+                    for &upper in fv[i + 1..].iter() {
+                        c = self.nonsub(c, upper);
+                    }
+                    ch.push(c);
+                }
+                self.create_node(h, &ch)
+            }
+        };
+        self.cache_put(key, node);
+        node
+    }
+
+    /// The vectors `s` of `f_r` with no `t <= s` (component-wise) in `g_r`.
+    pub fn nonsup(&mut self, f: NodeId, g: NodeId) -> NodeId {
+        let key = (ZmddOperation::NonSup, f, g);
+        if let Some(x) = self.cache_get(&key) {
+            return x;
+        }
+        let node = match (self.get_node(&f).unwrap(), self.get_node(&g).unwrap()) {
+            (Node::Undet, _) => self.undet(),
+            (_, Node::Undet) => f,
+            (Node::Terminal(fnode), Node::Terminal(gnode)) => {
+                if fnode.value() == gnode.value() {
+                    self.undet()
+                } else {
+                    f
+                }
+            }
+            // f has the higher variable X and g's vectors have X=0 <= i: diff every edge.
+            (Node::NonTerminal(fnode), Node::Terminal(_)) => {
+                let h = fnode.headerid();
+                let fv: Vec<NodeId> = fnode.iter().collect();
+                let ch: Vec<NodeId> = fv.into_iter().map(|a| self.nonsup(a, g)).collect();
+                self.create_node(h, &ch)
+            }
+            // f's vectors have X=0, so only g's vectors with X=0 can lie below them.
+            (Node::Terminal(_), Node::NonTerminal(gnode)) => {
+                let gv: Vec<NodeId> = gnode.iter().collect();
+                self.nonsup(f, gv[0])
+            }
+            (Node::NonTerminal(fnode), Node::NonTerminal(_)) if self.level(&f) > self.level(&g) => {
+                let h = fnode.headerid();
+                let fv: Vec<NodeId> = fnode.iter().collect();
+                let ch: Vec<NodeId> = fv.into_iter().map(|a| self.nonsup(a, g)).collect();
+                self.create_node(h, &ch)
+            }
+            (Node::NonTerminal(_), Node::NonTerminal(gnode)) if self.level(&f) < self.level(&g) => {
+                let gv: Vec<NodeId> = gnode.iter().collect();
+                self.nonsup(f, gv[0])
+            }
+            (Node::NonTerminal(fnode), Node::NonTerminal(gnode)) => {
+                let h = fnode.headerid();
+                let fv: Vec<NodeId> = fnode.iter().collect();
+                let gv: Vec<NodeId> = gnode.iter().collect();
+                let mut ch = Vec::with_capacity(fv.len());
+                for (i, &a) in fv.iter().enumerate() {
+                    let mut c = a;
+                    for &b in gv[..=i].iter() {
+                        c = self.nonsup(c, b);
+                    }
+                    ch.push(c);
+                }
+                self.create_node(h, &ch)
+            }
+        };
+        self.cache_put(key, node);
+        node
+    }
+
+    /// The vectors `s` of `f_r` with no `t >= s` (component-wise) in `g_r`.
+    pub fn nonsub(&mut self, f: NodeId, g: NodeId) -> NodeId {
+        let key = (ZmddOperation::NonSub, f, g);
+        if let Some(x) = self.cache_get(&key) {
+            return x;
+        }
+        let node = match (self.get_node(&f).unwrap(), self.get_node(&g).unwrap()) {
+            (Node::Undet, _) => self.undet(),
+            (_, Node::Undet) => f,
+            (Node::Terminal(fnode), Node::Terminal(gnode)) => {
+                if fnode.value() == gnode.value() {
+                    self.undet()
+                } else {
+                    f
+                }
+            }
+            // f has the higher variable X and g's vectors have X=0: only f's 0-edge can lie
+            // below them.
+            (Node::NonTerminal(fnode), Node::Terminal(_)) => {
+                let h = fnode.headerid();
+                let mut ch: Vec<NodeId> = fnode.iter().collect();
+                ch[0] = self.nonsub(ch[0], g);
+                self.create_node(h, &ch)
+            }
+            // f's vectors have X=0, below g's vectors with any X.
+            (Node::Terminal(_), Node::NonTerminal(gnode)) => {
+                let gv: Vec<NodeId> = gnode.iter().collect();
+                gv.into_iter().fold(f, |c, b| self.nonsub(c, b))
+            }
+            (Node::NonTerminal(fnode), Node::NonTerminal(_)) if self.level(&f) > self.level(&g) => {
+                let h = fnode.headerid();
+                let mut ch: Vec<NodeId> = fnode.iter().collect();
+                ch[0] = self.nonsub(ch[0], g);
+                self.create_node(h, &ch)
+            }
+            (Node::NonTerminal(_), Node::NonTerminal(gnode)) if self.level(&f) < self.level(&g) => {
+                let gv: Vec<NodeId> = gnode.iter().collect();
+                gv.into_iter().fold(f, |c, b| self.nonsub(c, b))
+            }
+            (Node::NonTerminal(fnode), Node::NonTerminal(gnode)) => {
+                let h = fnode.headerid();
+                let fv: Vec<NodeId> = fnode.iter().collect();
+                let gv: Vec<NodeId> = gnode.iter().collect();
+                let mut ch = Vec::with_capacity(fv.len());
+                for (i, &a) in fv.iter().enumerate() {
+                    let mut c = a;
+                    for &b in gv[i..].iter() {
+                        c = self.nonsub(c, b);
+                    }
+                    ch.push(c);
+                }
+                self.create_node(h, &ch)
+            }
+        };
+        self.cache_put(key, node);
+        node
+    }
+
+    /// `f` with every terminal label `v` replaced by `map(v)`, or its vectors dropped when
+    /// `map(v)` is `None`. Not cached (the map is not part of the key), but memoized within
+    /// the call.
+    pub fn relabel<F>(&mut self, f: NodeId, map: &F) -> NodeId
+    where
+        F: Fn(V) -> Option<V>,
+    {
+        let mut memo = HashMap::new();
+        self.relabel_rec(f, map, &mut memo)
+    }
+
+    fn relabel_rec<F>(&mut self, f: NodeId, map: &F, memo: &mut HashMap<NodeId, NodeId>) -> NodeId
+    where
+        F: Fn(V) -> Option<V>,
+    {
+        if let Some(&x) = memo.get(&f) {
+            return x;
+        }
+        let node = match self.get_node(&f).unwrap() {
+            Node::Undet => f,
+            Node::Terminal(fnode) => match map(fnode.value()) {
+                Some(v) => self.value(v),
+                None => self.undet(),
+            },
+            Node::NonTerminal(fnode) => {
+                let h = fnode.headerid();
+                let fv: Vec<NodeId> = fnode.iter().collect();
+                let ch: Vec<NodeId> = fv.into_iter().map(|a| self.relabel_rec(a, map, memo)).collect();
+                self.create_node(h, &ch)
+            }
+        };
+        memo.insert(f, node);
+        node
+    }
 }
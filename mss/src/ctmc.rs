@@ -0,0 +1,170 @@
+//! Continuous-time Markov chain (CTMC) components.
+//!
+//! A multi-state component that degrades and is repaired is a small CTMC over its states: a
+//! generator `Q` (`Q[i][j]` the rate from state `i` to `j`, rows summing to 0) and an initial
+//! distribution `p(0)`. [`Ctmc::transient`] gives `p(t) = p(0)·exp(Qt)` by
+//! **uniformization**: with `Λ >= max_i |Q[i][i]|` and the stochastic matrix `P = I + Q/Λ`,
+//!
+//! ```text
+//! p(t) = Σ_k e^{-Λt} (Λt)^k / k! · p(0)·P^k
+//! ```
+//!
+//! truncated past the mode once the Poisson weights fall below `1e-17` of their sum; the
+//! weights are taken in log space, so large `Λt` does not underflow, and renormalized.
+//! [`Ctmc::steady_state`] solves `πQ = 0`, `Σπ = 1` directly.
+//!
+//! [`MddMgr::defvar_ctmc`](crate::mdd::MddMgr::defvar_ctmc) binds a chain to a variable;
+//! the manager then produces the state probabilities of its bound variables at any time
+//! for `prob` (`MddMgr::availability`, `MddMgr::steady_availability`).
+
+use common::error::RelibError;
+
+/// A CTMC over the states `0..n` of one component; see the [module docs](self).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ctmc {
+    generator: Vec<Vec<f64>>,
+    initial: Vec<f64>,
+}
+
+const TOL: f64 = 1e-9;
+
+impl Ctmc {
+    /// A chain with generator `generator` started from `initial`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the generator is empty or not square, has a negative off-diagonal
+    /// rate or a row that does not sum to 0, or if `initial` has the wrong length or is not
+    /// a probability distribution.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mss::prelude::*;
+    ///
+    /// // Fails at rate 0.1, is repaired at rate 0.9; starts working.
+    /// let c = Ctmc::new(vec![vec![-0.9, 0.9], vec![0.1, -0.1]], vec![0.0, 1.0]).unwrap();
+    /// let p = c.transient(2.0);
+    /// assert!((p[1] - (0.9 + 0.1 * (-2.0_f64).exp())).abs() < 1e-12);
+    /// assert!((c.steady_state().unwrap()[1] - 0.9).abs() < 1e-12);
+    /// ```
+    pub fn new(generator: Vec<Vec<f64>>, initial: Vec<f64>) -> Result<Self, RelibError> {
+        let n = generator.len();
+        if n == 0 {
+            return Err(RelibError::Invalid("a CTMC needs at least one state".to_string()));
+        }
+        for (i, row) in generator.iter().enumerate() {
+            if row.len() != n {
+                return Err(RelibError::Invalid(format!("row {} of the generator has {} entries, expected {}", i, row.len(), n)));
+            }
+            if let Some(j) = (0..n).find(|&j| j != i && row[j] < 0.0) {
+                return Err(RelibError::Invalid(format!("negative rate {} from state {} to {}", row[j], i, j)));
+            }
+            let sum: f64 = row.iter().sum();
+            if sum.abs() > TOL * row.iter().map(|x| x.abs()).sum::<f64>().max(1.0) {
+                return Err(RelibError::Invalid(format!("row {} of the generator sums to {}, not 0", i, sum)));
+            }
+        }
+        if initial.len() != n {
+            return Err(RelibError::Invalid(format!("initial distribution has {} entries, expected {}", initial.len(), n)));
+        }
+        let total: f64 = initial.iter().sum();
+        if initial.iter().any(|&p| p < 0.0) || (total - 1.0).abs() > TOL {
+            return Err(RelibError::Invalid(format!("initial distribution {:?} is not a probability distribution", initial)));
+        }
+        Ok(Ctmc { generator, initial })
+    }
+
+    /// The number of states.
+    pub fn states(&self) -> usize {
+        self.initial.len()
+    }
+
+    pub fn generator(&self) -> &[Vec<f64>] {
+        &self.generator
+    }
+
+    pub fn initial(&self) -> &[f64] {
+        &self.initial
+    }
+
+    /// The state probabilities `p(t)` at time `t >= 0`, by uniformization.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `t` is negative or not finite.
+    pub fn transient(&self, t: f64) -> Vec<f64> {
+        assert!(t.is_finite() && t >= 0.0, "time {} is not a finite non-negative number", t);
+        let n = self.states();
+        let rate = (0..n).map(|i| -self.generator[i][i]).fold(0.0, f64::max);
+        if rate == 0.0 || t == 0.0 {
+            return self.initial.clone();
+        }
+        let lt = rate * t;
+        let mut term = self.initial.clone();
+        let mut result = vec![0.0; n];
+        let mut covered = 0.0;
+        let mut log_w = -lt;
+        let mut k = 0usize;
+        loop {
+            let w = log_w.exp();
+            for (r, &x) in result.iter_mut().zip(term.iter()) {
+                *r += w * x;
+            }
+            covered += w;
+            if k as f64 > lt && w < 1e-17 * covered {
+                break;
+            }
+            // term <- term · (I + Q/Λ)
+            let mut next = term.clone();
+            for (i, &x) in term.iter().enumerate() {
+                for (j, q) in self.generator[i].iter().enumerate() {
+                    next[j] += x * q / rate;
+                }
+            }
+            term = next;
+            k += 1;
+            log_w += lt.ln() - (k as f64).ln();
+        }
+        // Rounding in the running log weight drifts over many terms; the weights sum to 1.
+        result.iter().map(|r| r / covered).collect()
+    }
+
+    /// The stationary distribution `π` with `πQ = 0` and `Σπ = 1`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if it is not unique (the chain has more than one closed class).
+    pub fn steady_state(&self) -> Result<Vec<f64>, RelibError> {
+        let n = self.states();
+        // Qᵀπ = 0 with the last equation replaced by Σπ = 1.
+        let mut a: Vec<Vec<f64>> = (0..n)
+            .map(|j| {
+                let mut row: Vec<f64> = (0..n).map(|i| self.generator[i][j]).collect();
+                row.push(0.0);
+                row
+            })
+            .collect();
+        a[n - 1] = vec![1.0; n + 1];
+        for c in 0..n {
+            let pivot = (c..n)
+                .max_by(|&i, &j| a[i][c].abs().total_cmp(&a[j][c].abs()))
+                .unwrap();
+            let scale = a.iter().map(|row| row[c].abs()).fold(0.0, f64::max);
+            if a[pivot][c].abs() <= TOL * scale {
+                return Err(RelibError::Invalid("the stationary distribution is not unique".to_string()));
+            }
+            a.swap(c, pivot);
+            for i in 0..n {
+                if i != c {
+                    let f = a[i][c] / a[c][c];
+                    let pivot_row = a[c].clone();
+                    for (x, p) in a[i].iter_mut().zip(pivot_row) {
+                        *x -= f * p;
+                    }
+                }
+            }
+        }
+        Ok((0..n).map(|i| (a[i][n] / a[i][i]).max(0.0)).collect())
+    }
+}
@@ -38,22 +38,38 @@
 //! The `mdd_prob` / `mdd_path` / `mdd_minsol` / `mdd_count` modules serve as reference
 //! implementations.
 
+pub mod ctmc;
 pub mod mdd;
 pub mod mdd_path;
 pub mod mdd_prob;
+pub mod mdd_importance;
+pub mod mdd_dpld;
 pub mod mdd_count;
+pub mod mdd_kofn;
+pub mod mdd_parse;
 pub mod mdd_minsol;
+pub mod mdd_coherence;
 pub mod mss;
+pub mod rbd;
+pub mod network;
+pub mod ugf;
 pub mod zmdd;
 pub(crate) mod zmdd_convert;
 
 pub mod prelude {
     pub use mddcore::prelude::*;
+    pub use crate::ctmc::*;
     pub use crate::mdd::*;
     pub use crate::mdd_path::*;
     pub use crate::mdd_minsol::*;
+    pub use crate::mdd_coherence::*;
     pub use crate::mdd_prob::*;
+    pub use crate::mdd_importance::*;
+    pub use crate::mdd_dpld::*;
     pub use crate::mdd_count::*;
     pub use crate::mss::*;
+    pub use crate::rbd::*;
+    pub use crate::network::*;
+    pub use crate::ugf::Ugf;
     pub use crate::zmdd::*;
 }
@@ -1,17 +1,26 @@
 use mddcore::prelude::*;
 
 use std::cell::RefCell;
-use std::collections::{HashMap, HashSet};
-use std::ops::{Add, Mul, Sub};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::{Add, Div, Mul, Sub};
 use std::rc::{Rc, Weak};
 
+use crate::ctmc::Ctmc;
+use crate::mdd_kofn;
 use crate::mdd_prob;
+use crate::mdd_dpld;
+use crate::mdd_importance::{self, MssImportance};
+use crate::mdd_coherence;
 use crate::mdd_count;
+use crate::mdd_parse;
 use crate::mdd_path::MddPath;
 
 /// Minimum live-node count at which automatic gc may fire.
 const GC_FLOOR: usize = 1 << 16;
 
+/// The result of [`MddNode::mpe`]: the probability and the state of every variable.
+type Mpe<T> = Option<(T, HashMap<String, usize>)>;
+
 /// State shared between an `MddMgr` and all of its `MddNode` handles, enabling
 /// reference-counted gc roots. Keyed by the tagged `Node` (the value and bool
 /// sub-forests have independent id spaces). Not generic over `V`.
@@ -54,6 +63,7 @@ pub struct MddMgr<V> {
     mdd: Rc<RefCell<MtMdd2Manager<V>>>,
     gc: Rc<RefCell<GcState>>,
     vars: HashMap<String, MddNode<V>>,
+    ctmcs: HashMap<String, Ctmc>,
 }
 
 /// A handle to a node in an [`MddMgr`]'s forest.
@@ -133,6 +143,7 @@ where
                 floor: GC_FLOOR,
             })),
             vars: HashMap::new(),
+            ctmcs: HashMap::new(),
         }
     }
 
@@ -227,6 +238,151 @@ where
         result
     }
 
+    /// Defines the variable `label` with one state per state of `ctmc` and binds the chain
+    /// to it: [`availability`](Self::availability) and
+    /// [`steady_availability`](Self::steady_availability) take its state probabilities from
+    /// the chain. Binding an existing variable replaces its chain.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if `label` is already defined with a different number of states.
+    pub fn defvar_ctmc(&mut self, label: &str, ctmc: Ctmc) -> Result<MddNode<V>, RelibError> {
+        if let Some((_, m)) = self.get_varorder().into_iter().find(|(x, _)| x == label) {
+            if m != ctmc.states() {
+                return Err(RelibError::Invalid(format!("{} has {} states, but the CTMC has {}", label, m, ctmc.states())));
+            }
+        }
+        let node = self.defvar(label, ctmc.states());
+        self.ctmcs.insert(label.to_string(), ctmc);
+        Ok(node)
+    }
+
+    /// The chain bound to `label` by [`defvar_ctmc`](Self::defvar_ctmc).
+    pub fn ctmc(&self, label: &str) -> Option<&Ctmc> {
+        self.ctmcs.get(label)
+    }
+
+    /// The transient state probabilities at time `t` of every variable bound to a CTMC.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `t` is negative or not finite.
+    pub fn ctmc_probabilities(&self, t: f64) -> HashMap<String, Vec<f64>> {
+        self.ctmcs.iter().map(|(x, c)| (x.clone(), c.transient(t))).collect()
+    }
+
+    /// The stationary state probabilities of every variable bound to a CTMC.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the stationary distribution of a chain is not unique.
+    pub fn ctmc_steady_probabilities(&self) -> Result<HashMap<String, Vec<f64>>, RelibError> {
+        self.ctmcs
+            .iter()
+            .map(|(x, c)| c.steady_state().map(|p| (x.clone(), p)).map_err(|e| RelibError::Invalid(format!("{}: {}", x, e))))
+            .collect()
+    }
+
+    /// The availability curve `P(φ(t) ∈ ss)` at each of `times`, for independent components:
+    /// the variables bound to a CTMC take their transient probabilities, the others their
+    /// fixed probabilities in `pv`. Only the chains of the diagram's variables are solved,
+    /// and the diagram is linearized once for all time points ([`MddNode::prob_batch`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if `pv` has an entry for a variable of the diagram that is bound to a
+    /// CTMC (the chain gives its probabilities), or [`RelibError::MissingProbability`] if a
+    /// variable of the diagram is neither bound to a CTMC nor in `pv`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a time is negative or not finite.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mss::prelude::*;
+    /// use std::collections::HashMap;
+    ///
+    /// // A pump that degrades 2 -> 1 -> 0 and is repaired from 0 to 2, in series with a
+    /// // valve that is up (state 1) with probability 0.99.
+    /// let mut mgr: MddMgr<i32> = MddMgr::new();
+    /// let q = vec![
+    ///     vec![-0.5, 0.0, 0.5],
+    ///     vec![0.2, -0.2, 0.0],
+    ///     vec![0.0, 0.1, -0.1],
+    /// ];
+    /// let pump = mgr.defvar_ctmc("pump", Ctmc::new(q, vec![0.0, 0.0, 1.0]).unwrap()).unwrap();
+    /// let valve = mgr.defvar("valve", 2);
+    /// let f = pump.min(&valve.mul(&mgr.value(2)));
+    /// let pv = HashMap::from([("valve".to_string(), vec![0.01, 0.99])]);
+    ///
+    /// let curve = mgr.availability(&f, &[1, 2], &[0.0, 1.0, 100.0], &pv).unwrap();
+    /// assert!((curve[0] - 0.99).abs() < 1e-12);
+    /// assert!(curve[1] < curve[0]);
+    /// let steady = mgr.steady_availability(&f, &[1, 2], &pv).unwrap();
+    /// assert!((curve[2] - steady).abs() < 1e-9);
+    /// ```
+    pub fn availability(
+        &self,
+        node: &MddNode<V>,
+        ss: &[V],
+        times: &[f64],
+        pv: &HashMap<String, Vec<f64>>,
+    ) -> Result<Vec<f64>, RelibError> {
+        let chains = self.bound_chains(node, pv)?;
+        let params: Vec<HashMap<String, Vec<f64>>> = times
+            .iter()
+            .map(|&t| {
+                let mut p = pv.clone();
+                p.extend(chains.iter().map(|(x, c)| (x.to_string(), c.transient(t))));
+                p
+            })
+            .collect();
+        node.try_prob_batch(&params, ss)
+    }
+
+    /// The steady-state availability `P(φ ∈ ss)` with the variables bound to a CTMC at their
+    /// stationary distributions and the others at `pv`. Only the chains of the diagram's
+    /// variables are solved.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the stationary distribution of a chain is not unique, or as
+    /// [`availability`](Self::availability).
+    pub fn steady_availability(
+        &self,
+        node: &MddNode<V>,
+        ss: &[V],
+        pv: &HashMap<String, Vec<f64>>,
+    ) -> Result<f64, RelibError> {
+        let mut p = pv.clone();
+        for (x, c) in self.bound_chains(node, pv)? {
+            let steady = c.steady_state().map_err(|e| RelibError::Invalid(format!("{}: {}", x, e)))?;
+            p.insert(x.to_string(), steady);
+        }
+        Ok(node.try_prob_batch(&[p], ss)?[0])
+    }
+
+    /// The chains bound to the variables of `node`, refusing a `pv` entry they would shadow.
+    fn bound_chains<'a>(
+        &'a self,
+        node: &MddNode<V>,
+        pv: &HashMap<String, Vec<f64>>,
+    ) -> Result<Vec<(&'a str, &'a Ctmc)>, RelibError> {
+        let labels = mdd_coherence::diagram_labels(&self.mdd.borrow(), &node.get_node());
+        let mut chains = Vec::new();
+        for x in labels {
+            if let Some((x, c)) = self.ctmcs.get_key_value(&x) {
+                if pv.contains_key(x) {
+                    return Err(RelibError::Invalid(format!("{} is bound to a CTMC and also has probabilities in pv", x)));
+                }
+                chains.push((x.as_str(), c));
+            }
+        }
+        Ok(chains)
+    }
+
     pub fn get_varorder(&self) -> Vec<(String, usize)> {
         let mut result = vec![("?".to_string(), 0); self.vars.len()];
         for (k, v) in self.vars.iter() {
@@ -272,13 +428,10 @@ where
     ///
     /// # Errors
     ///
-    /// Returns `Err` if `load(id)` names something never saved, if `save(id)` is used on
+    /// Returns `Err` if a token is neither an integer nor a key of `vars`, if an operator
+    /// lacks operands, if `load(id)` names something never saved, if `save(id)` is used on
     /// an empty stack, or if the expression does not reduce to exactly one node.
     ///
-    /// # Panics
-    ///
-    /// Panics if a token is neither an integer nor a key of `vars`.
-    ///
     /// # Example
     ///
     /// ```
@@ -295,119 +448,129 @@ where
     /// assert!(node.is_boolean());
     /// ```
     pub fn rpn(&mut self, rpn: &str, vars: &HashMap<String, usize>) -> Result<MddNode<V>, String> {
+        self.try_rpn(rpn, vars).map_err(String::from)
+    }
+
+    /// [`rpn`](Self::rpn) with the error as a [`RelibError`].
+    pub fn try_rpn(&mut self, rpn: &str, vars: &HashMap<String, usize>) -> Result<MddNode<V>, RelibError> {
+        fn pop(stack: &mut Vec<Node>, token: &str) -> Result<Node, RelibError> {
+            stack
+                .pop()
+                .ok_or_else(|| RelibError::Invalid(format!("missing operand for {}", token)))
+        }
         let mut stack = Vec::new();
         let mut cache = HashMap::new();
         for token in rpn.split_whitespace() {
             match token {
                 "+" => {
                     let mut mdd = self.mdd.borrow_mut();
-                    let b = stack.pop().unwrap();
-                    let a = stack.pop().unwrap();
+                    let b = pop(&mut stack, token)?;
+                    let a = pop(&mut stack, token)?;
                     let tmp = mdd.add(a, b);
                     stack.push(tmp);
                 }
                 "-" => {
                     let mut mdd = self.mdd.borrow_mut();
-                    let b = stack.pop().unwrap();
-                    let a = stack.pop().unwrap();
+                    let b = pop(&mut stack, token)?;
+                    let a = pop(&mut stack, token)?;
                     let tmp = mdd.sub(a, b);
                     stack.push(tmp);
                 }
                 "*" => {
                     let mut mdd = self.mdd.borrow_mut();
-                    let b = stack.pop().unwrap();
-                    let a = stack.pop().unwrap();
+                    let b = pop(&mut stack, token)?;
+                    let a = pop(&mut stack, token)?;
                     let tmp = mdd.mul(a, b);
                     stack.push(tmp);
                 }
                 "/" => {
                     let mut mdd = self.mdd.borrow_mut();
-                    let b = stack.pop().unwrap();
-                    let a = stack.pop().unwrap();
+                    let b = pop(&mut stack, token)?;
+                    let a = pop(&mut stack, token)?;
                     let tmp = mdd.div(a, b);
                     stack.push(tmp);
                 }
                 "min" => {
                     let mut mdd = self.mdd.borrow_mut();
-                    let b = stack.pop().unwrap();
-                    let a = stack.pop().unwrap();
+                    let b = pop(&mut stack, token)?;
+                    let a = pop(&mut stack, token)?;
                     let tmp = mdd.min(a, b);
                     stack.push(tmp);
                 }
                 "max" => {
                     let mut mdd = self.mdd.borrow_mut();
-                    let b = stack.pop().unwrap();
-                    let a = stack.pop().unwrap();
+                    let b = pop(&mut stack, token)?;
+                    let a = pop(&mut stack, token)?;
                     let tmp = mdd.max(a, b);
                     stack.push(tmp);
                 }
                 "==" => {
                     let mut mdd = self.mdd.borrow_mut();
-                    let b = stack.pop().unwrap();
-                    let a = stack.pop().unwrap();
+                    let b = pop(&mut stack, token)?;
+                    let a = pop(&mut stack, token)?;
                     let tmp = mdd.eq(a, b);
                     stack.push(tmp);
                 }
                 "!=" => {
                     let mut mdd = self.mdd.borrow_mut();
-                    let b = stack.pop().unwrap();
-                    let a = stack.pop().unwrap();
+                    let b = pop(&mut stack, token)?;
+                    let a = pop(&mut stack, token)?;
                     let tmp = mdd.neq(a, b);
                     stack.push(tmp);
                 }
                 "<" => {
                     let mut mdd = self.mdd.borrow_mut();
-                    let b = stack.pop().unwrap();
-                    let a = stack.pop().unwrap();
+                    let b = pop(&mut stack, token)?;
+                    let a = pop(&mut stack, token)?;
                     let tmp = mdd.lt(a, b);
                     stack.push(tmp);
                 }
                 "<=" => {
                     let mut mdd = self.mdd.borrow_mut();
-                    let b = stack.pop().unwrap();
-                    let a = stack.pop().unwrap();
+                    let b = pop(&mut stack, token)?;
+                    let a = pop(&mut stack, token)?;
                     let tmp = mdd.lte(a, b);
                     stack.push(tmp);
                 }
                 ">" => {
                     let mut mdd = self.mdd.borrow_mut();
-                    let b = stack.pop().unwrap();
-                    let a = stack.pop().unwrap();
+                    let b = pop(&mut stack, token)?;
+                    let a = pop(&mut stack, token)?;
                     let tmp = mdd.gt(a, b);
                     stack.push(tmp);
                 }
                 ">=" => {
                     let mut mdd = self.mdd.borrow_mut();
-                    let b = stack.pop().unwrap();
-                    let a = stack.pop().unwrap();
+                    let b = pop(&mut stack, token)?;
+                    let a = pop(&mut stack, token)?;
                     let tmp = mdd.gte(a, b);
                     stack.push(tmp);
                 }
                 "&&" => {
                     let mut mdd = self.mdd.borrow_mut();
-                    let b = stack.pop().unwrap();
-                    let a = stack.pop().unwrap();
+                    let b = pop(&mut stack, token)?;
+                    let a = pop(&mut stack, token)?;
                     let tmp = mdd.and(a, b);
                     stack.push(tmp);
                 }
                 "||" => {
                     let mut mdd = self.mdd.borrow_mut();
-                    let b = stack.pop().unwrap();
-                    let a = stack.pop().unwrap();
+                    let b = pop(&mut stack, token)?;
+                    let a = pop(&mut stack, token)?;
                     let tmp = mdd.or(a, b);
                     stack.push(tmp);
                 }
                 "!" => {
                     let mut mdd = self.mdd.borrow_mut();
-                    let a = stack.pop().unwrap();
+                    let a = pop(&mut stack, token)?;
                     let tmp = mdd.not(a);
                     stack.push(tmp);
                 }
                 "?" => {
                     let mut mdd = self.mdd.borrow_mut();
-                    let c = stack.pop().unwrap();
-                    let b = stack.pop().unwrap();
-                    let a = stack.pop().unwrap();
+                    let c = pop(&mut stack, token)?;
+                    let b = pop(&mut stack, token)?;
+                    let a = pop(&mut stack, token)?;
                     let tmp = mdd.ite(a, b, c);
                     stack.push(tmp);
                 }
@@ -430,7 +593,7 @@ where
                     if let Some(node) = stack.last() {
                         cache.insert(name.to_string(), node.clone());
                     } else {
-                        return Err("Stack is empty for save operation".to_string());
+                        return Err(RelibError::Invalid("Stack is empty for save operation".to_string()));
                     }
                 }
                 _ if token.starts_with("load(") && token.ends_with(")") => {
@@ -438,7 +601,7 @@ where
                     if let Some(node) = cache.get(name) {
                         stack.push(node.clone());
                     } else {
-                        return Err(format!("No cached value for {}", name));
+                        return Err(RelibError::Invalid(format!("No cached value for {}", name)));
                     }
                 }
                 _ => {
@@ -456,7 +619,7 @@ where
                                 let node = self.defvar(token, range.clone());
                                 stack.push(node.node.clone());
                             }
-                            None => panic!("Unknown variable: {}", token),
+                            None => return Err(RelibError::UnknownVariable(token.to_string())),
                         },
                     }
                 }
@@ -465,10 +628,46 @@ where
         if stack.len() == 1 {
             Ok(self.wrap(stack.pop().unwrap()))
         } else {
-            Err("Invalid expression".to_string())
+            Err(RelibError::Invalid("Invalid expression".to_string()))
         }
     }
 
+    /// Builds an MTMDD2 node from an infix expression: arithmetic, comparisons, `&&`/`||`/`!`,
+    /// n-ary `min(…)`/`max(…)`, `if c then a else b`, `case` tables, `let` bindings and
+    /// inline variable declarations `var x: 0..3;` (grammar in [`mdd_parse`]).
+    ///
+    /// Unlike [`rpn`](Self::rpn) no `vars` map is needed: a variable is declared by a `var`
+    /// line or earlier with [`defvar`](Self::defvar).
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`RelibError::Parse`] with the byte span of the offending text for undeclared
+    /// variables, booleans used as values (or vice versa), `case` tables that miss a state,
+    /// unknown functions and anything else outside the grammar.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mss::prelude::*;
+    ///
+    /// let mut mgr: MddMgr<i32> = MddMgr::new();
+    /// let src = "
+    ///     var x: 0..2;
+    ///     var y: 0..2;
+    ///     let cap = case x { 0 => 0, 1 => 50, 2 => 100 };
+    ///     min(cap, 50 * y) >= 50";
+    /// let node = mgr.parse(src).unwrap();
+    /// assert!(node.is_boolean());
+    ///
+    /// match mgr.parse("x + z") {
+    ///     Err(RelibError::Parse(err)) => assert_eq!(err.span, 4..5),
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    pub fn parse(&mut self, src: &str) -> Result<MddNode<V>, RelibError> {
+        mdd_parse::parse(self, src).map_err(RelibError::Parse)
+    }
+
     pub fn and(&self, nodes: &[MddNode<V>]) -> MddNode<V> {
         let result = {
             let mut mdd = self.mdd.borrow_mut();
@@ -517,6 +716,89 @@ where
         self.wrap(result)
     }
 
+    /// Boolean MDD for `Σ weights[i]·nodes[i] >= threshold`, where `nodes` are value MDDs
+    /// (typically multi-state variables whose state is a number of units, e.g. capacity),
+    /// built by a memoized expansion (see [`mdd_kofn::weighted_threshold`]) rather than by
+    /// materializing the weighted sum. Per-state weights that are not proportional to the
+    /// state can be given as value MDDs (e.g. a `case` table from [`parse`](Self::parse))
+    /// with unit weights.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `weights` and `nodes` differ in length, or if a node is boolean or
+    /// undetermined.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mss::prelude::*;
+    ///
+    /// let mut mgr: MddMgr<i32> = MddMgr::new();
+    /// let a = mgr.defvar("a", 3);
+    /// let b = mgr.defvar("b", 3);
+    /// let f = mgr.weighted_threshold(&[2, 1], 4, &[a, b]);
+    /// let g = mgr.parse("2 * a + b >= 4").unwrap();
+    /// assert_eq!(f.get_node(), g.get_node());
+    /// ```
+    pub fn weighted_threshold(&self, weights: &[V], threshold: V, nodes: &[MddNode<V>]) -> MddNode<V> {
+        let ids: Vec<Node> = nodes.iter().map(|x| x.node).collect();
+        let result = mdd_kofn::weighted_threshold(&mut self.mdd.borrow_mut(), weights, threshold, &ids);
+        self.wrap(result)
+    }
+
+    /// Boolean MDD for "at least `k` of the value nodes `nodes` are `>= level`" (see
+    /// [`mdd_kofn::kofn`]), e.g. at least `k` components in state `level` or above.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mss::prelude::*;
+    ///
+    /// let mut mgr: MddMgr<i32> = MddMgr::new();
+    /// let xs: Vec<_> = ["a", "b", "c"].iter().map(|x| mgr.defvar(x, 3)).collect();
+    /// let f = mgr.kofn(2, &xs, 2);
+    /// let g = mgr.parse("(a >= 2 && b >= 2) || (a >= 2 && c >= 2) || (b >= 2 && c >= 2)").unwrap();
+    /// assert_eq!(f.get_node(), g.get_node());
+    /// ```
+    pub fn kofn(&self, k: usize, nodes: &[MddNode<V>], level: V) -> MddNode<V> {
+        let ids: Vec<Node> = nodes.iter().map(|x| x.node).collect();
+        let result = mdd_kofn::kofn(&mut self.mdd.borrow_mut(), k, &ids, level);
+        self.wrap(result)
+    }
+
+    /// Value MDD of the generalized multi-state k-out-of-n system with `ks[l - 1]` components
+    /// needed for level `l` (see [`mdd_kofn::generalized_kofn`]).
+    pub fn generalized_kofn(&self, ks: &[usize], nodes: &[MddNode<V>]) -> MddNode<V> {
+        let ids: Vec<Node> = nodes.iter().map(|x| x.node).collect();
+        let result = mdd_kofn::generalized_kofn(&mut self.mdd.borrow_mut(), ks, &ids);
+        self.wrap(result)
+    }
+
+    /// Value MDD of `Σ nodes`, clamped to `cap` if given (see [`mdd_kofn::sum`]): the
+    /// capacity of load-sharing components, limited by what the system can deliver.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a node is boolean or undetermined.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mss::prelude::*;
+    ///
+    /// let mut mgr: MddMgr<i32> = MddMgr::new();
+    /// let a = mgr.defvar("a", 3);
+    /// let b = mgr.defvar("b", 4);
+    /// let f = mgr.sum(&[a, b], Some(4));
+    /// let g = mgr.parse("min(a + b, 4)").unwrap();
+    /// assert_eq!(f.get_node(), g.get_node());
+    /// ```
+    pub fn sum(&self, nodes: &[MddNode<V>], cap: Option<V>) -> MddNode<V> {
+        let ids: Vec<Node> = nodes.iter().map(|x| x.node).collect();
+        let result = mdd_kofn::sum(&mut self.mdd.borrow_mut(), &ids, cap);
+        self.wrap(result)
+    }
+
     pub fn clear_cache(&mut self) {
         let mut mdd = self.mdd.borrow_mut();
         mdd.clear_cache();
@@ -528,7 +810,40 @@ where
     V: MddValue,
 {
     pub fn get_mgr(&self) -> Rc<RefCell<MtMdd2Manager<V>>> {
-        self.parent.upgrade().unwrap()
+        self.try_get_mgr().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`get_mgr`](Self::get_mgr): `Err` if the manager has been dropped.
+    pub fn try_get_mgr(&self) -> Result<Rc<RefCell<MtMdd2Manager<V>>>, RelibError> {
+        self.mgr_of(&[])
+    }
+
+    /// The manager of `self` and `others`.
+    fn mgr_of(&self, others: &[&MddNode<V>]) -> Result<Rc<RefCell<MtMdd2Manager<V>>>, RelibError> {
+        let mdd = self.parent.upgrade().ok_or(RelibError::ManagerDropped)?;
+        for x in others {
+            if !x.parent.ptr_eq(&self.parent) {
+                return Err(if x.parent.strong_count() == 0 {
+                    RelibError::ManagerDropped
+                } else {
+                    RelibError::ManagerMismatch
+                });
+            }
+        }
+        Ok(mdd)
+    }
+
+    /// The manager of `self` and `others`, checking that they are all value nodes (`value`)
+    /// or all boolean nodes.
+    fn operands(&self, op: &str, others: &[&MddNode<V>], value: bool) -> Result<Rc<RefCell<MtMdd2Manager<V>>>, RelibError> {
+        let mdd = self.mgr_of(others)?;
+        let ok = |x: &MddNode<V>| matches!(x.node, Node::Value(_)) == value;
+        if ok(self) && others.iter().all(|x| ok(x)) {
+            Ok(mdd)
+        } else {
+            let kind = if value { "value" } else { "boolean" };
+            Err(RelibError::KindMismatch(format!("{} expects {} operands", op, kind)))
+        }
     }
 
     pub fn get_node(&self) -> Node {
@@ -552,13 +867,13 @@ where
     pub fn get_header(&self) -> Option<HeaderId> {
         match &self.node {
             Node::Value(x) => {
-                let mddmgr = self.parent.upgrade().unwrap();
+                let mddmgr = self.get_mgr();
                 let mdd = mddmgr.borrow();
                 let node = mdd.mtmdd().get_node(x)?;
                 node.headerid()
             }
             Node::Bool(x) => {
-                let mddmgr = self.parent.upgrade().unwrap();
+                let mddmgr = self.get_mgr();
                 let mdd = mddmgr.borrow();
                 let node = mdd.mdd().get_node(x)?;
                 node.headerid()
@@ -569,7 +884,7 @@ where
     pub fn get_level(&self) -> Option<Level> {
         match &self.node {
             Node::Value(x) => {
-                let mddmgr = self.parent.upgrade().unwrap();
+                let mddmgr = self.get_mgr();
                 let mdd = mddmgr.borrow();
                 let node = mdd.mtmdd().get_node(x)?;
                 let hid = node.headerid()?;
@@ -577,7 +892,7 @@ where
                 Some(header.level())
             }
             Node::Bool(x) => {
-                let mddmgr = self.parent.upgrade().unwrap();
+                let mddmgr = self.get_mgr();
                 let mdd = mddmgr.borrow();
                 let node = mdd.mdd().get_node(x)?;
                 let hid = node.headerid()?;
@@ -590,7 +905,7 @@ where
     pub fn get_label(&self) -> Option<String> {
         match &self.node {
             Node::Value(x) => {
-                let mddmgr = self.parent.upgrade().unwrap();
+                let mddmgr = self.get_mgr();
                 let mdd = mddmgr.borrow();
                 let node = mdd.mtmdd().get_node(x)?;
                 let hid = node.headerid()?;
@@ -598,7 +913,7 @@ where
                 Some(header.label().to_string())
             }
             Node::Bool(x) => {
-                let mddmgr = self.parent.upgrade().unwrap();
+                let mddmgr = self.get_mgr();
                 let mdd = mddmgr.borrow();
                 let node = mdd.mdd().get_node(x)?;
                 let hid = node.headerid()?;
@@ -609,9 +924,14 @@ where
     }
 
     pub fn get_children(&self) -> Option<Vec<MddNode<V>>> {
-        match &self.node {
+        self.try_get_children().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`get_children`](Self::get_children): `Err` if the manager has been dropped.
+    pub fn try_get_children(&self) -> Result<Option<Vec<MddNode<V>>>, RelibError> {
+        let mddmgr = self.mgr_of(&[])?;
+        Ok(match &self.node {
             Node::Value(x) => {
-                let mddmgr = self.parent.upgrade().unwrap();
                 let mdd = mddmgr.borrow();
                 let node = mdd.mtmdd().get_node(x).unwrap();
                 match node {
@@ -634,7 +954,6 @@ where
                 }
             }
             Node::Bool(x) => {
-                let mddmgr = self.parent.upgrade().unwrap();
                 let mdd = mddmgr.borrow();
                 let node = mdd.mdd().get_node(x).unwrap();
                 match node {
@@ -656,7 +975,7 @@ where
                     }
                 }
             }
-        }
+        })
     }
 
     pub fn is_boolean(&self) -> bool {
@@ -677,7 +996,7 @@ where
         match &self.node {
             Node::Value(x) => false,
             Node::Bool(x) => {
-                let mddmgr = self.parent.upgrade().unwrap();
+                let mddmgr = self.get_mgr();
                 let mdd = mddmgr.borrow();
                 let node = mdd.mdd().get_node(x).unwrap();
                 match node {
@@ -694,7 +1013,7 @@ where
         match &self.node {
             Node::Value(x) => false,
             Node::Bool(x) => {
-                let mddmgr = self.parent.upgrade().unwrap();
+                let mddmgr = self.get_mgr();
                 let mdd = mddmgr.borrow();
                 let node = mdd.mdd().get_node(x).unwrap();
                 match node {
@@ -711,7 +1030,7 @@ where
         match &self.node {
             Node::Value(x) => false,
             Node::Bool(x) => {
-                let mddmgr = self.parent.upgrade().unwrap();
+                let mddmgr = self.get_mgr();
                 let mdd = mddmgr.borrow();
                 let node = mdd.mdd().get_node(x).unwrap();
                 match node {
@@ -727,7 +1046,7 @@ where
     pub fn value(&self) -> Option<V> {
         match &self.node {
             Node::Value(x) => {
-                let mddmgr = self.parent.upgrade().unwrap();
+                let mddmgr = self.get_mgr();
                 let mdd = mddmgr.borrow();
                 let node = mdd.mtmdd().get_node(x).unwrap();
                 match node {
@@ -740,145 +1059,201 @@ where
     }
 
     pub fn dot(&self) -> String {
-        let mddmgr = self.parent.upgrade().unwrap();
-        let mdd = mddmgr.borrow();
-        mdd.dot_string(&self.node)
+        self.try_dot().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`dot`](Self::dot): `Err` if the manager has been dropped.
+    pub fn try_dot(&self) -> Result<String, RelibError> {
+        let mddmgr = self.mgr_of(&[])?;
+        let result = mddmgr.borrow().dot_string(&self.node);
+        Ok(result)
     }
 
     pub fn add(&self, other: &MddNode<V>) -> MddNode<V> {
-        let mddmgr = self.parent.upgrade().unwrap();
-        let mut mdd = mddmgr.borrow_mut();
-        let node = mdd.add(self.node, other.node);
-        drop(mdd);
-        self.rewrap(&mddmgr, node)
+        self.try_add(other).unwrap_or_else(|e| panic!("{}", e))
     }
 
     pub fn sub(&self, other: &MddNode<V>) -> MddNode<V> {
-        let mddmgr = self.parent.upgrade().unwrap();
-        let mut mdd = mddmgr.borrow_mut();
-        let node = mdd.sub(self.node, other.node);
-        drop(mdd);
-        self.rewrap(&mddmgr, node)
+        self.try_sub(other).unwrap_or_else(|e| panic!("{}", e))
     }
 
     pub fn mul(&self, other: &MddNode<V>) -> MddNode<V> {
-        let mddmgr = self.parent.upgrade().unwrap();
-        let mut mdd = mddmgr.borrow_mut();
-        let node = mdd.mul(self.node, other.node);
-        drop(mdd);
-        self.rewrap(&mddmgr, node)
+        self.try_mul(other).unwrap_or_else(|e| panic!("{}", e))
     }
 
     pub fn div(&self, other: &MddNode<V>) -> MddNode<V> {
-        let mddmgr = self.parent.upgrade().unwrap();
-        let mut mdd = mddmgr.borrow_mut();
-        let node = mdd.div(self.node, other.node);
-        drop(mdd);
-        self.rewrap(&mddmgr, node)
+        self.try_div(other).unwrap_or_else(|e| panic!("{}", e))
     }
 
     pub fn min(&self, other: &MddNode<V>) -> MddNode<V> {
-        let mddmgr = self.parent.upgrade().unwrap();
-        let mut mdd = mddmgr.borrow_mut();
-        let node = mdd.min(self.node, other.node);
-        drop(mdd);
-        self.rewrap(&mddmgr, node)
+        self.try_min(other).unwrap_or_else(|e| panic!("{}", e))
     }
 
     pub fn max(&self, other: &MddNode<V>) -> MddNode<V> {
-        let mddmgr = self.parent.upgrade().unwrap();
-        let mut mdd = mddmgr.borrow_mut();
-        let node = mdd.max(self.node, other.node);
-        drop(mdd);
-        self.rewrap(&mddmgr, node)
+        self.try_max(other).unwrap_or_else(|e| panic!("{}", e))
     }
 
     pub fn eq(&self, other: &MddNode<V>) -> MddNode<V> {
-        let mddmgr = self.parent.upgrade().unwrap();
-        let mut mdd = mddmgr.borrow_mut();
-        let node = mdd.eq(self.node, other.node);
-        drop(mdd);
-        self.rewrap(&mddmgr, node)
+        self.try_eq(other).unwrap_or_else(|e| panic!("{}", e))
     }
 
     pub fn ne(&self, other: &MddNode<V>) -> MddNode<V> {
-        let mddmgr = self.parent.upgrade().unwrap();
-        let mut mdd = mddmgr.borrow_mut();
-        let node = mdd.neq(self.node, other.node);
-        drop(mdd);
-        self.rewrap(&mddmgr, node)
+        self.try_ne(other).unwrap_or_else(|e| panic!("{}", e))
     }
 
     pub fn lt(&self, other: &MddNode<V>) -> MddNode<V> {
-        let mddmgr = self.parent.upgrade().unwrap();
-        let mut mdd = mddmgr.borrow_mut();
-        let node = mdd.lt(self.node, other.node);
-        drop(mdd);
-        self.rewrap(&mddmgr, node)
+        self.try_lt(other).unwrap_or_else(|e| panic!("{}", e))
     }
 
     pub fn le(&self, other: &MddNode<V>) -> MddNode<V> {
-        let mddmgr = self.parent.upgrade().unwrap();
-        let mut mdd = mddmgr.borrow_mut();
-        let node = mdd.lte(self.node, other.node);
-        drop(mdd);
-        self.rewrap(&mddmgr, node)
+        self.try_le(other).unwrap_or_else(|e| panic!("{}", e))
     }
 
     pub fn gt(&self, other: &MddNode<V>) -> MddNode<V> {
-        let mddmgr = self.parent.upgrade().unwrap();
-        let mut mdd = mddmgr.borrow_mut();
-        let node = mdd.gt(self.node, other.node);
-        drop(mdd);
-        self.rewrap(&mddmgr, node)
+        self.try_gt(other).unwrap_or_else(|e| panic!("{}", e))
     }
 
     pub fn ge(&self, other: &MddNode<V>) -> MddNode<V> {
-        let mddmgr = self.parent.upgrade().unwrap();
-        let mut mdd = mddmgr.borrow_mut();
-        let node = mdd.gte(self.node, other.node);
-        drop(mdd);
-        self.rewrap(&mddmgr, node)
+        self.try_ge(other).unwrap_or_else(|e| panic!("{}", e))
     }
 
     pub fn and(&self, other: &MddNode<V>) -> MddNode<V> {
-        let mddmgr = self.parent.upgrade().unwrap();
-        let mut mdd = mddmgr.borrow_mut();
-        let node = mdd.and(self.node, other.node);
-        drop(mdd);
-        self.rewrap(&mddmgr, node)
+        self.try_and(other).unwrap_or_else(|e| panic!("{}", e))
     }
 
     pub fn or(&self, other: &MddNode<V>) -> MddNode<V> {
-        let mddmgr = self.parent.upgrade().unwrap();
-        let mut mdd = mddmgr.borrow_mut();
-        let node = mdd.or(self.node, other.node);
-        drop(mdd);
-        self.rewrap(&mddmgr, node)
+        self.try_or(other).unwrap_or_else(|e| panic!("{}", e))
     }
 
     pub fn xor(&self, other: &MddNode<V>) -> MddNode<V> {
-        let mddmgr = self.parent.upgrade().unwrap();
-        let mut mdd = mddmgr.borrow_mut();
-        let node = mdd.xor(self.node, other.node);
-        drop(mdd);
-        self.rewrap(&mddmgr, node)
+        self.try_xor(other).unwrap_or_else(|e| panic!("{}", e))
     }
 
     pub fn not(&self) -> MddNode<V> {
-        let mddmgr = self.parent.upgrade().unwrap();
-        let mut mdd = mddmgr.borrow_mut();
-        let node = mdd.not(self.node);
-        drop(mdd);
-        self.rewrap(&mddmgr, node)
+        self.try_not().unwrap_or_else(|e| panic!("{}", e))
     }
 
     pub fn ite(&self, then: &MddNode<V>, els: &MddNode<V>) -> MddNode<V> {
-        let mddmgr = self.parent.upgrade().unwrap();
-        let mut mdd = mddmgr.borrow_mut();
-        let node = mdd.ite(self.node, then.node, els.node);
-        drop(mdd);
-        self.rewrap(&mddmgr, node)
+        self.try_ite(then, els).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    // --- fallible operators ---------------------------------------------------
+    //
+    // The operators above panic if the manager has been dropped, an operand belongs to
+    // another manager or is of the wrong kind (a boolean added to a value, …); the `try_*`
+    // forms report these as a `RelibError`.
+
+    /// Fallible [`add`](Self::add): `Err` if the manager has been dropped, `other` belongs to
+    /// another manager, or an operand is not a value node; likewise for the other `try_*`
+    /// operators with their operand kinds.
+    pub fn try_add(&self, other: &MddNode<V>) -> Result<MddNode<V>, RelibError> {
+        let mddmgr = self.operands("add", &[other], true)?;
+        let node = mddmgr.borrow_mut().add(self.node, other.node);
+        Ok(self.rewrap(&mddmgr, node))
+    }
+
+    pub fn try_sub(&self, other: &MddNode<V>) -> Result<MddNode<V>, RelibError> {
+        let mddmgr = self.operands("sub", &[other], true)?;
+        let node = mddmgr.borrow_mut().sub(self.node, other.node);
+        Ok(self.rewrap(&mddmgr, node))
+    }
+
+    pub fn try_mul(&self, other: &MddNode<V>) -> Result<MddNode<V>, RelibError> {
+        let mddmgr = self.operands("mul", &[other], true)?;
+        let node = mddmgr.borrow_mut().mul(self.node, other.node);
+        Ok(self.rewrap(&mddmgr, node))
+    }
+
+    pub fn try_div(&self, other: &MddNode<V>) -> Result<MddNode<V>, RelibError> {
+        let mddmgr = self.operands("div", &[other], true)?;
+        let node = mddmgr.borrow_mut().div(self.node, other.node);
+        Ok(self.rewrap(&mddmgr, node))
+    }
+
+    pub fn try_min(&self, other: &MddNode<V>) -> Result<MddNode<V>, RelibError> {
+        let mddmgr = self.operands("min", &[other], true)?;
+        let node = mddmgr.borrow_mut().min(self.node, other.node);
+        Ok(self.rewrap(&mddmgr, node))
+    }
+
+    pub fn try_max(&self, other: &MddNode<V>) -> Result<MddNode<V>, RelibError> {
+        let mddmgr = self.operands("max", &[other], true)?;
+        let node = mddmgr.borrow_mut().max(self.node, other.node);
+        Ok(self.rewrap(&mddmgr, node))
+    }
+
+    pub fn try_eq(&self, other: &MddNode<V>) -> Result<MddNode<V>, RelibError> {
+        let mddmgr = self.operands("eq", &[other], true)?;
+        let node = mddmgr.borrow_mut().eq(self.node, other.node);
+        Ok(self.rewrap(&mddmgr, node))
+    }
+
+    pub fn try_ne(&self, other: &MddNode<V>) -> Result<MddNode<V>, RelibError> {
+        let mddmgr = self.operands("ne", &[other], true)?;
+        let node = mddmgr.borrow_mut().neq(self.node, other.node);
+        Ok(self.rewrap(&mddmgr, node))
+    }
+
+    pub fn try_lt(&self, other: &MddNode<V>) -> Result<MddNode<V>, RelibError> {
+        let mddmgr = self.operands("lt", &[other], true)?;
+        let node = mddmgr.borrow_mut().lt(self.node, other.node);
+        Ok(self.rewrap(&mddmgr, node))
+    }
+
+    pub fn try_le(&self, other: &MddNode<V>) -> Result<MddNode<V>, RelibError> {
+        let mddmgr = self.operands("le", &[other], true)?;
+        let node = mddmgr.borrow_mut().lte(self.node, other.node);
+        Ok(self.rewrap(&mddmgr, node))
+    }
+
+    pub fn try_gt(&self, other: &MddNode<V>) -> Result<MddNode<V>, RelibError> {
+        let mddmgr = self.operands("gt", &[other], true)?;
+        let node = mddmgr.borrow_mut().gt(self.node, other.node);
+        Ok(self.rewrap(&mddmgr, node))
+    }
+
+    pub fn try_ge(&self, other: &MddNode<V>) -> Result<MddNode<V>, RelibError> {
+        let mddmgr = self.operands("ge", &[other], true)?;
+        let node = mddmgr.borrow_mut().gte(self.node, other.node);
+        Ok(self.rewrap(&mddmgr, node))
+    }
+
+    pub fn try_and(&self, other: &MddNode<V>) -> Result<MddNode<V>, RelibError> {
+        let mddmgr = self.operands("and", &[other], false)?;
+        let node = mddmgr.borrow_mut().and(self.node, other.node);
+        Ok(self.rewrap(&mddmgr, node))
+    }
+
+    pub fn try_or(&self, other: &MddNode<V>) -> Result<MddNode<V>, RelibError> {
+        let mddmgr = self.operands("or", &[other], false)?;
+        let node = mddmgr.borrow_mut().or(self.node, other.node);
+        Ok(self.rewrap(&mddmgr, node))
+    }
+
+    pub fn try_xor(&self, other: &MddNode<V>) -> Result<MddNode<V>, RelibError> {
+        let mddmgr = self.operands("xor", &[other], false)?;
+        let node = mddmgr.borrow_mut().xor(self.node, other.node);
+        Ok(self.rewrap(&mddmgr, node))
+    }
+
+    pub fn try_not(&self) -> Result<MddNode<V>, RelibError> {
+        let mddmgr = self.operands("not", &[], false)?;
+        let node = mddmgr.borrow_mut().not(self.node);
+        Ok(self.rewrap(&mddmgr, node))
+    }
+
+    /// Fallible [`ite`](Self::ite): the condition must be boolean and the branches of the
+    /// same kind.
+    pub fn try_ite(&self, then: &MddNode<V>, els: &MddNode<V>) -> Result<MddNode<V>, RelibError> {
+        let mddmgr = self.mgr_of(&[then, els])?;
+        if !matches!(self.node, Node::Bool(_)) {
+            return Err(RelibError::KindMismatch("ite expects a boolean condition".to_string()));
+        }
+        if matches!(then.node, Node::Value(_)) != matches!(els.node, Node::Value(_)) {
+            return Err(RelibError::KindMismatch("ite expects branches of the same kind".to_string()));
+        }
+        let node = mddmgr.borrow_mut().ite(self.node, then.node, els.node);
+        Ok(self.rewrap(&mddmgr, node))
     }
 
     pub fn prob<T>(&mut self, pv: &HashMap<String, Vec<T>>, ss: &[V]) -> T
@@ -891,10 +1266,244 @@ where
             + PartialEq
             + From<f64>,
     {
-        let mgr = self.parent.upgrade().unwrap();
+        self.try_prob(pv, ss).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`prob`](Self::prob): `Err` if the manager has been dropped or a variable of
+    /// the diagram lacks probabilities in `pv`, where `prob` panics.
+    pub fn try_prob<T>(&self, pv: &HashMap<String, Vec<T>>, ss: &[V]) -> Result<T, RelibError>
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialEq + From<f64>,
+    {
+        let mgr = self.mgr_of(&[])?;
         let mut mdd = mgr.borrow_mut();
+        mdd_prob::check_probabilities(&mdd, &self.node, pv)?;
         let hashset: HashSet<V> = ss.iter().cloned().collect();
-        mdd_prob::prob(&mut mdd, &self.node, pv, &hashset)
+        Ok(mdd_prob::prob(&mut mdd, &self.node, pv, &hashset))
+    }
+
+    /// `(P(φ ∈ ss), P(φ ∉ ss))` with the complement summed from its own terminals instead
+    /// of computed as `1 − P` (see [`mdd_prob::prob_with_complement`]).
+    pub fn prob_with_complement<T>(&self, pv: &HashMap<String, Vec<T>>, ss: &[V]) -> (T, T)
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialEq + From<f64>,
+    {
+        self.try_prob_with_complement(pv, ss).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`prob_with_complement`](Self::prob_with_complement); see [`try_prob`](Self::try_prob).
+    pub fn try_prob_with_complement<T>(&self, pv: &HashMap<String, Vec<T>>, ss: &[V]) -> Result<(T, T), RelibError>
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialEq + From<f64>,
+    {
+        let mgr = self.mgr_of(&[])?;
+        let mdd = mgr.borrow();
+        mdd_prob::check_probabilities(&mdd, &self.node, pv)?;
+        let hashset: HashSet<V> = ss.iter().cloned().collect();
+        Ok(mdd_prob::prob_with_complement(&mdd, &self.node, pv, &hashset))
+    }
+
+    /// The whole output distribution `P(φ = v)` over the terminal values `v` in one pass (see
+    /// [`mdd_prob::distribution`]), instead of one [`prob`](Self::prob) call per value. A
+    /// boolean node gives the masses of `0` and `1`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mss::prelude::*;
+    /// use std::collections::HashMap;
+    ///
+    /// let mut mgr: MddMgr<i32> = MddMgr::new();
+    /// let f = mgr.parse("var x: 0..2; var y: 0..2; min(x, y)").unwrap();
+    /// let pv = HashMap::from([
+    ///     ("x".to_string(), vec![0.2_f64, 0.3, 0.5]),
+    ///     ("y".to_string(), vec![0.1, 0.4, 0.5]),
+    /// ]);
+    ///
+    /// let dist = f.distribution(&pv);
+    /// assert!((dist[&2] - 0.25).abs() < 1e-12);
+    /// assert!((f.exceedance(&pv)[&1] - 0.9 * 0.8).abs() < 1e-12);
+    /// assert!((f.expectation(&pv) - (0.47 + 2.0 * 0.25)).abs() < 1e-12);
+    /// ```
+    pub fn distribution<T>(&self, pv: &HashMap<String, Vec<T>>) -> BTreeMap<V, T>
+    where
+        T: Add<Output = T> + Mul<Output = T> + Copy + From<f64>,
+    {
+        self.try_distribution(pv).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`distribution`](Self::distribution); see [`try_prob`](Self::try_prob).
+    pub fn try_distribution<T>(&self, pv: &HashMap<String, Vec<T>>) -> Result<BTreeMap<V, T>, RelibError>
+    where
+        T: Add<Output = T> + Mul<Output = T> + Copy + From<f64>,
+    {
+        let mgr = self.mgr_of(&[])?;
+        let mdd = mgr.borrow();
+        mdd_prob::check_probabilities(&mdd, &self.node, pv)?;
+        Ok(mdd_prob::distribution(&mdd, &self.node, pv))
+    }
+
+    /// `E[φ]`, from [`distribution`](Self::distribution).
+    pub fn expectation<T>(&self, pv: &HashMap<String, Vec<T>>) -> T
+    where
+        T: Add<Output = T> + Mul<Output = T> + Copy + From<f64>,
+    {
+        mdd_prob::expectation(&self.distribution(pv))
+    }
+
+    /// `Var[φ]`, from [`distribution`](Self::distribution).
+    pub fn variance<T>(&self, pv: &HashMap<String, Vec<T>>) -> T
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Copy + From<f64>,
+    {
+        mdd_prob::variance(&self.distribution(pv))
+    }
+
+    /// `P(φ >= v)` for every terminal value `v`, from [`distribution`](Self::distribution).
+    pub fn exceedance<T>(&self, pv: &HashMap<String, Vec<T>>) -> BTreeMap<V, T>
+    where
+        T: Add<Output = T> + Mul<Output = T> + Copy + From<f64>,
+    {
+        mdd_prob::exceedance(&self.distribution(pv))
+    }
+
+    /// Lower and upper bounds of [`prob`](Self::prob) from interval-valued state
+    /// probabilities (see [`mdd_prob::prob_interval`]). The bounds are always sound;
+    /// [`MssMgr::prob_interval`](crate::mss::MssMgr::prob_interval) checks coherence first
+    /// and returns exact bounds when it can.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the manager has been dropped, a variable of the diagram has no
+    /// intervals, or the intervals of a variable hold no distribution (`Σ lo > 1` or
+    /// `Σ hi < 1`).
+    pub fn prob_interval(&self, pv: &HashMap<String, Vec<Interval>>, ss: &[V]) -> Result<Interval, RelibError> {
+        let mgr = self.mgr_of(&[])?;
+        let mdd = mgr.borrow();
+        let hashset: HashSet<V> = ss.iter().cloned().collect();
+        mdd_prob::prob_interval(&mdd, &self.node, pv, &hashset)
+    }
+
+    /// [`prob`](Self::prob) given evidence on variable states (`evidence[x]` = the states
+    /// `x` is known to be in): returns `(P(f ∈ ss, evidence), P(f ∈ ss | evidence))` without
+    /// rebuilding the diagram (see [`mdd_prob::prob_given`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the manager has been dropped, an observed variable has no entry in
+    /// `pv` ([`RelibError::MissingProbability`]) or a listed state is out of range.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mss::prelude::*;
+    /// use std::collections::HashMap;
+    ///
+    /// let mut mgr: MddMgr<i32> = MddMgr::new();
+    /// let mut f = mgr.parse("var x: 0..3; var y: 0..3; min(x, y)").unwrap();
+    /// let pv = HashMap::from([
+    ///     ("x".to_string(), vec![0.1, 0.2, 0.3, 0.4]),
+    ///     ("y".to_string(), vec![0.25_f64; 4]),
+    /// ]);
+    /// // x is known to be in state >= 2.
+    /// let evidence = HashMap::from([("x".to_string(), vec![2, 3])]);
+    ///
+    /// let (joint, conditional) = f.prob_given(&pv, &[0, 1], &evidence).unwrap();
+    /// assert!((conditional - 0.5).abs() < 1e-12);
+    /// assert!((joint - 0.7 * 0.5).abs() < 1e-12);
+    /// ```
+    pub fn prob_given<T>(
+        &mut self,
+        pv: &HashMap<String, Vec<T>>,
+        ss: &[V],
+        evidence: &HashMap<String, Vec<usize>>,
+    ) -> Result<(T, T), RelibError>
+    where
+        T: Add<Output = T>
+            + Sub<Output = T>
+            + Mul<Output = T>
+            + Div<Output = T>
+            + Clone
+            + Copy
+            + PartialEq
+            + From<f64>,
+    {
+        let mgr = self.mgr_of(&[])?;
+        let mut mdd = mgr.borrow_mut();
+        let hashset: HashSet<V> = ss.iter().cloned().collect();
+        mdd_prob::prob_given(&mut mdd, &self.node, pv, &hashset, evidence)
+    }
+
+    /// [`prob`](Self::prob) for many parameter sets at once: the diagram is linearized into
+    /// an [`MddProbTape`](mdd_prob::MddProbTape) a single time and every parameter set is
+    /// evaluated against it, instead of re-walking the graph per set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a variable of the diagram has no entry in a parameter set, or an entry
+    /// shorter than the variable's state count.
+    pub fn prob_batch<T>(&self, params: &[HashMap<String, Vec<T>>], ss: &[V]) -> Vec<T>
+    where
+        T: Add<Output = T>
+            + Sub<Output = T>
+            + Mul<Output = T>
+            + Clone
+            + Copy
+            + PartialEq
+            + From<f64>,
+    {
+        self.try_prob_batch(params, ss).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`prob_batch`](Self::prob_batch): `Err` if the manager has been dropped or a
+    /// parameter set lacks probabilities for a variable of the diagram.
+    pub fn try_prob_batch<T>(&self, params: &[HashMap<String, Vec<T>>], ss: &[V]) -> Result<Vec<T>, RelibError>
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialEq + From<f64>,
+    {
+        let mgr = self.mgr_of(&[])?;
+        let mdd = mgr.borrow();
+        for pv in params {
+            mdd_prob::check_probabilities(&mdd, &self.node, pv)?;
+        }
+        let hashset: HashSet<V> = ss.iter().cloned().collect();
+        Ok(mdd_prob::prob_batch(&mdd, &self.node, params, &hashset))
+    }
+
+    /// Columnar [`prob_batch`](Self::prob_batch): `params[k][level][j]` is the probability
+    /// that the variable at `level` (the `defvar` order, see [`MddMgr::get_varorder`]) is in
+    /// state `j` in the `k`-th parameter set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a row is shorter than the highest level the diagram reads, or a row entry
+    /// is shorter than the state count of its variable.
+    pub fn prob_batch_levels<T>(&self, params: &[Vec<Vec<T>>], ss: &[V]) -> Vec<T>
+    where
+        T: Add<Output = T>
+            + Sub<Output = T>
+            + Mul<Output = T>
+            + Clone
+            + Copy
+            + PartialEq
+            + From<f64>,
+    {
+        self.try_prob_batch_levels(params, ss).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`prob_batch_levels`](Self::prob_batch_levels): `Err` if the manager has been
+    /// dropped or a row lacks the probabilities of a level the diagram reads.
+    pub fn try_prob_batch_levels<T>(&self, params: &[Vec<Vec<T>>], ss: &[V]) -> Result<Vec<T>, RelibError>
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialEq + From<f64>,
+    {
+        let mgr = self.mgr_of(&[])?;
+        let mdd = mgr.borrow();
+        for p in params {
+            mdd_prob::check_levels(&mdd, &self.node, p)?;
+        }
+        let hashset: HashSet<V> = ss.iter().cloned().collect();
+        Ok(mdd_prob::prob_batch_levels(&mdd, &self.node, params, &hashset))
     }
 
     /// Multi-state Birnbaum importance of every variable for the success set `ss`, as
@@ -918,19 +1527,265 @@ where
             + PartialEq
             + From<f64>,
     {
-        let mgr = self.parent.upgrade().unwrap();
+        self.try_bmeas(pv, ss).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Multi-state importance of every variable of the diagram for the success set `ss`:
+    /// conditional reliabilities, expected-performance importance `∂E[φ]/∂p_{i,j}` and the
+    /// threshold Birnbaum, criticality, diagnostic, RAW and RRW measures, from one
+    /// shared traversal, and the Fussell–Vesely importance from the down-closures of the
+    /// critical vectors (see [`mdd_importance`](crate::mdd_importance)).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mss::prelude::*;
+    /// use std::collections::HashMap;
+    ///
+    /// // A pump (0..2) and a valve (0..1) in series; success is a performance of at least 1.
+    /// let mut mgr: MddMgr<i32> = MddMgr::new();
+    /// let f = mgr.parse("var pump: 0..2; var valve: 0..1; min(pump, 2 * valve)").unwrap();
+    /// let pv = HashMap::from([
+    ///     ("pump".to_string(), vec![0.1_f64, 0.3, 0.6]),
+    ///     ("valve".to_string(), vec![0.2, 0.8]),
+    /// ]);
+    ///
+    /// let imp = f.importance(&pv, &[1, 2]);
+    /// // With the pump failed (state 0) the system fails; from state 1 up it works if the valve does.
+    /// assert_eq!(imp["pump"].conditional[0], 0.0);
+    /// assert!((imp["pump"].conditional[1] - 0.8).abs() < 1e-12);
+    /// assert!((imp["pump"].performance[2] - 1.6).abs() < 1e-12);
+    /// assert!((imp["valve"].birnbaum[0] - 0.9).abs() < 1e-12);
+    /// ```
+    pub fn importance<T>(&self, pv: &HashMap<String, Vec<T>>, ss: &[V]) -> HashMap<String, MssImportance<T>>
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Div<Output = T> + Copy + From<f64>,
+    {
+        self.try_importance(pv, ss).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`importance`](Self::importance); see [`try_prob`](Self::try_prob).
+    pub fn try_importance<T>(&self, pv: &HashMap<String, Vec<T>>, ss: &[V]) -> Result<HashMap<String, MssImportance<T>>, RelibError>
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Div<Output = T> + Copy + From<f64>,
+    {
+        let mgr = self.mgr_of(&[])?;
         let mut mdd = mgr.borrow_mut();
+        mdd_prob::check_probabilities(&mdd, &self.node, pv)?;
         let hashset: HashSet<V> = ss.iter().cloned().collect();
-        mdd_prob::bmeas(&mut mdd, &self.node, pv, &hashset)
+        Ok(mdd_importance::importance(&mut mdd, &self.node, pv, &hashset))
+    }
+
+    /// The direct partial logic derivative `∂φ(j→h)/∂x(s→r)`: a boolean node that is `1`
+    /// on the state vectors of the other variables where changing `var` from state `s` to
+    /// `r` changes `φ` from `j` to `h` (see [`mdd_dpld`]). A boolean `φ` has the states `0`
+    /// and `1`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if `var` is not a defined variable or `s` or `r` is not one of its states.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mss::prelude::*;
+    /// use std::collections::HashMap;
+    ///
+    /// let mut mgr: MddMgr<i32> = MddMgr::new();
+    /// let f = mgr.parse("var x: 0..2; var y: 0..2; min(x, y)").unwrap();
+    ///
+    /// // y failing from 2 to 1 drops the system from 2 to 1 only when x = 2.
+    /// let d = f.dpld("y", 2, 1, 2, 1).unwrap();
+    /// assert!((d.structural_importance() - 1.0 / 3.0).abs() < 1e-12);
+    /// let critical: Vec<_> = d.critical_vectors("y").collect();
+    /// assert_eq!(critical, vec![HashMap::from([("x".to_string(), 2)])]);
+    /// ```
+    pub fn dpld(&self, var: &str, s: usize, r: usize, j: V, h: V) -> Result<MddNode<V>, RelibError> {
+        self.dpld_integrated(var, s, r, &[j], &[h])
+    }
+
+    /// The integrated DPLD: `1` where changing `var` from `s` to `r` takes `φ` from a state
+    /// in `from` to a state in `to`, e.g. `from = [j]` and `to = [0, .., j-1]` for any
+    /// degradation of the system from `j`.
+    ///
+    /// # Errors
+    ///
+    /// As [`dpld`](Self::dpld).
+    pub fn dpld_integrated(&self, var: &str, s: usize, r: usize, from: &[V], to: &[V]) -> Result<MddNode<V>, RelibError> {
+        let mddmgr = self.get_mgr();
+        let mut mdd = mddmgr.borrow_mut();
+        let node = mdd_dpld::dpld(&mut mdd, &self.node, var, s, r, from, to)?;
+        drop(mdd);
+        Ok(self.rewrap(&mddmgr, node))
+    }
+
+    /// The structural importance read off a DPLD node: the fraction of the state vectors of
+    /// the other variables on which it is `1` (see [`mdd_dpld::structural_importance`]).
+    pub fn structural_importance(&self) -> f64 {
+        self.try_structural_importance().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`structural_importance`](Self::structural_importance): `Err` if the manager
+    /// has been dropped.
+    pub fn try_structural_importance(&self) -> Result<f64, RelibError> {
+        let mgr = self.mgr_of(&[])?;
+        let result = mdd_dpld::structural_importance(&mgr.borrow(), &self.node);
+        Ok(result)
+    }
+
+    /// The critical state vectors of a DPLD node for the differentiated variable `var`: the
+    /// assignments of the other variables on which it is `1`, as in
+    /// [`mdd_extract`](Self::mdd_extract). Variables above the top of the diagram are left
+    /// out (they may take any state), and so is `var`, on which the DPLD does not depend.
+    pub fn critical_vectors(&self, var: &str) -> MddPath<V> {
+        MddPath::new(self, &HashSet::from([V::from(1)])).skipping(var)
+    }
+
+    /// Fallible [`bmeas`](Self::bmeas); see [`try_prob`](Self::try_prob).
+    pub fn try_bmeas<T>(&self, pv: &HashMap<String, Vec<T>>, ss: &[V]) -> Result<HashMap<String, Vec<T>>, RelibError>
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialEq + From<f64>,
+    {
+        let mgr = self.mgr_of(&[])?;
+        let mut mdd = mgr.borrow_mut();
+        mdd_prob::check_probabilities(&mdd, &self.node, pv)?;
+        let hashset: HashSet<V> = ss.iter().cloned().collect();
+        Ok(mdd_prob::bmeas(&mut mdd, &self.node, pv, &hashset))
+    }
+
+    /// Posterior state distribution `P(x = j | f ∈ ss)` of every variable `x` of the
+    /// diagram, in one pass (see [`mdd_prob::posterior`]).
+    pub fn posterior<T>(&self, pv: &HashMap<String, Vec<T>>, ss: &[V]) -> HashMap<String, Vec<T>>
+    where
+        T: Add<Output = T>
+            + Sub<Output = T>
+            + Mul<Output = T>
+            + Div<Output = T>
+            + Clone
+            + Copy
+            + PartialEq
+            + From<f64>,
+    {
+        self.try_posterior(pv, ss).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`posterior`](Self::posterior); see [`try_prob`](Self::try_prob).
+    pub fn try_posterior<T>(&self, pv: &HashMap<String, Vec<T>>, ss: &[V]) -> Result<HashMap<String, Vec<T>>, RelibError>
+    where
+        T: Add<Output = T>
+            + Sub<Output = T>
+            + Mul<Output = T>
+            + Div<Output = T>
+            + Clone
+            + Copy
+            + PartialEq
+            + From<f64>,
+    {
+        let mgr = self.mgr_of(&[])?;
+        let mdd = mgr.borrow();
+        mdd_prob::check_probabilities(&mdd, &self.node, pv)?;
+        let hashset: HashSet<V> = ss.iter().cloned().collect();
+        Ok(mdd_prob::posterior(&mdd, &self.node, pv, &hashset))
+    }
+
+    /// Most probable explanation of `f ∈ ss`: the most probable state of every variable of
+    /// the diagram under which `f` takes a value in `ss`, with its probability, or `None` if
+    /// there is none (see [`mdd_prob::mpe`]).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mss::prelude::*;
+    /// use std::collections::HashMap;
+    ///
+    /// let mut mgr: MddMgr<i32> = MddMgr::new();
+    /// let f = mgr.parse("var x: 0..2; var y: 0..2; min(x, y) == 0").unwrap();
+    /// let pv = HashMap::from([
+    ///     ("x".to_string(), vec![0.1, 0.3, 0.6]),
+    ///     ("y".to_string(), vec![0.2, 0.7, 0.1]),
+    /// ]);
+    ///
+    /// let (p, states) = f.mpe(&pv, &[1]).unwrap();
+    /// assert!((p - 0.6 * 0.2_f64).abs() < 1e-12);
+    /// assert_eq!((states["x"], states["y"]), (2, 0));
+    /// ```
+    pub fn mpe<T>(&self, pv: &HashMap<String, Vec<T>>, ss: &[V]) -> Option<(T, HashMap<String, usize>)>
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialOrd + From<f64>,
+    {
+        self.try_mpe(pv, ss).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`mpe`](Self::mpe); see [`try_prob`](Self::try_prob).
+    pub fn try_mpe<T>(&self, pv: &HashMap<String, Vec<T>>, ss: &[V]) -> Result<Mpe<T>, RelibError>
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialOrd + From<f64>,
+    {
+        let mgr = self.mgr_of(&[])?;
+        let mdd = mgr.borrow();
+        mdd_prob::check_probabilities(&mdd, &self.node, pv)?;
+        let hashset: HashSet<V> = ss.iter().cloned().collect();
+        Ok(mdd_prob::mpe(&mdd, &self.node, pv, &hashset))
+    }
+
+    /// Whether the function is nondecreasing in every variable (see [`mdd_coherence`]); if
+    /// not, a variable and two state vectors differing only in it by one state on which the
+    /// function decreases. This is why [`MssMgr::minpath`](crate::mss::MssMgr::minpath)
+    /// returns `None`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mss::prelude::*;
+    ///
+    /// let mut mgr: MddMgr<i32> = MddMgr::new();
+    /// let f = mgr.parse("var x: 0..2; var y: 0..2; if x == 1 then y else 0").unwrap();
+    /// match f.check_coherence() {
+    ///     Coherence::Decreasing { var, low, high } => {
+    ///         assert_eq!(var, "x");
+    ///         assert_eq!((low["x"], high["x"]), (1, 2));
+    ///         assert!(low["y"] > 0);
+    ///     }
+    ///     Coherence::Coherent => unreachable!(),
+    /// }
+    /// ```
+    pub fn check_coherence(&self) -> Coherence<usize> {
+        self.try_check_coherence().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`check_coherence`](Self::check_coherence): `Err` if the manager has been
+    /// dropped.
+    pub fn try_check_coherence(&self) -> Result<Coherence<usize>, RelibError> {
+        let mgr = self.mgr_of(&[])?;
+        let result = mdd_coherence::check_coherence(&mut mgr.borrow_mut(), &self.node);
+        Ok(result)
+    }
+
+    /// The defined variables the function does not depend on (irrelevant components), in
+    /// variable order.
+    pub fn irrelevant(&self) -> Vec<String> {
+        self.try_irrelevant().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`irrelevant`](Self::irrelevant): `Err` if the manager has been dropped.
+    pub fn try_irrelevant(&self) -> Result<Vec<String>, RelibError> {
+        let mgr = self.mgr_of(&[])?;
+        let result = mdd_coherence::irrelevant(&mgr.borrow(), &self.node);
+        Ok(result)
     }
 
     // `minpath` lives on [`MssMgr`](crate::mss::MssMgr) (it also needs a `ZmddMgr`);
     // it returns a genuine `ZmddNode` set family.
 
     pub fn mdd_count(&self, ss: &HashSet<V>) -> u64 {
-        let mgr = self.parent.upgrade().unwrap();
-        let mdd = mgr.borrow();
-        mdd_count::mdd_count(&mdd, &self.node, ss)
+        self.try_mdd_count(ss).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`mdd_count`](Self::mdd_count): `Err` if the manager has been dropped.
+    pub fn try_mdd_count(&self, ss: &HashSet<V>) -> Result<u64, RelibError> {
+        let mgr = self.mgr_of(&[])?;
+        let result = mdd_count::mdd_count(&mgr.borrow(), &self.node, ss);
+        Ok(result)
     }
 
     pub fn mdd_extract(&self, ss: &HashSet<V>) -> MddPath<V> {
@@ -938,9 +1793,14 @@ where
     }
 
     pub fn size(&self) -> (u64, u64, u64) {
-        let mgr = self.parent.upgrade().unwrap();
-        let mdd = mgr.borrow();
-        mdd_count::mddnode_count(&mdd, &self.node)
+        self.try_size().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`size`](Self::size): `Err` if the manager has been dropped.
+    pub fn try_size(&self) -> Result<(u64, u64, u64), RelibError> {
+        let mgr = self.mgr_of(&[])?;
+        let result = mdd_count::mddnode_count(&mgr.borrow(), &self.node);
+        Ok(result)
     }
 }
 
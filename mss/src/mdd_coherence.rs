@@ -0,0 +1,212 @@
+//! Coherence (monotonicity) and relevance checks with counterexamples.
+//!
+//! [`check_coherence`] walks the diagram and applies the per-node test of
+//! [`minsol`](crate::mdd_minsol::minsol) — the cofactors of every node ascend pointwise — to
+//! each reachable node. At the first node that fails it, a path from the root fixes the
+//! variables above, and a descent through the two offending cofactors fixes the variables
+//! below, where the lower cofactor is greater. Every step follows a branch on which the
+//! cofactors still differ in the wrong direction, so no backtracking is needed.
+//!
+//! [`irrelevant`] lists the defined variables the function does not depend on: on the
+//! reduced diagram, those that label no node.
+
+use std::collections::HashMap;
+
+use mddcore::prelude::*;
+
+use crate::mdd_minsol::{bdescent, vdescent};
+use crate::mdd_prob::{flatten_values, FlatNode};
+
+/// A variable, the lower of two adjacent states of it, and a path assignment of the other
+/// variables on which the function decreases between the two.
+type Found = (String, usize, Vec<(String, usize)>);
+
+/// [`Coherence::Coherent`] if `node` is nondecreasing in every variable, otherwise a
+/// counterexample. A boolean node counts `false < true`.
+pub fn check_coherence<V>(mdd: &mut MtMdd2Manager<V>, node: &Node) -> Coherence<usize>
+where
+    V: MddValue,
+{
+    let mut path = Vec::new();
+    let mut visited = BddHashSet::default();
+    let found = match node {
+        Node::Value(fnode) => vsearch(mdd.mtmdd_mut(), *fnode, &mut path, &mut visited),
+        Node::Bool(fnode) => bsearch(mdd.mdd_mut(), *fnode, &mut path, &mut visited),
+    };
+    let Some((var, s, assignment)) = found else {
+        return Coherence::Coherent;
+    };
+    let mut low: HashMap<String, usize> = diagram_labels(mdd, node).into_iter().map(|x| (x, 0)).collect();
+    low.extend(assignment);
+    let mut high = low.clone();
+    low.insert(var.clone(), s);
+    high.insert(var.clone(), s + 1);
+    Coherence::Decreasing { var, low, high }
+}
+
+/// The defined variables `node` does not depend on, in variable order (lowest level first).
+pub fn irrelevant<V>(mdd: &MtMdd2Manager<V>, node: &Node) -> Vec<String>
+where
+    V: MddValue,
+{
+    let used = diagram_labels(mdd, node);
+    let (nheaders, _, _, _) = mdd.size();
+    let mut headers: Vec<_> = (0..nheaders).filter_map(|h| mdd.mtmdd().get_header(&h)).collect();
+    headers.sort_by_key(|h| h.level());
+    headers
+        .into_iter()
+        .map(|h| h.label().to_string())
+        .filter(|x| !used.contains(x))
+        .collect()
+}
+
+/// The variables `node` depends on, in the order the diagram first reaches them.
+pub(crate) fn diagram_labels<V>(mdd: &MtMdd2Manager<V>, node: &Node) -> Vec<String>
+where
+    V: MddValue,
+{
+    let mut labels = Vec::new();
+    for f in flatten_values(mdd, node) {
+        if let FlatNode::Node { label, .. } = f {
+            if !labels.contains(&label) {
+                labels.push(label);
+            }
+        }
+    }
+    labels
+}
+
+fn vsearch<V>(
+    dd: &mut mtmdd::MtMddManager<V>,
+    f: NodeId,
+    path: &mut Vec<(String, usize)>,
+    visited: &mut BddHashSet<NodeId>,
+) -> Option<Found>
+where
+    V: MddValue,
+{
+    if !visited.insert(f) {
+        return None;
+    }
+    let mtmdd::Node::NonTerminal(fnode) = dd.get_node(&f).unwrap() else {
+        return None;
+    };
+    let children: Vec<NodeId> = fnode.iter().collect();
+    let label = dd.label(&f).unwrap().to_string();
+    if let Some(i) = vdescent(dd, &children) {
+        let mut below = Vec::new();
+        vgreater(dd, children[i - 1], children[i], &mut below);
+        let mut assignment = path.clone();
+        assignment.extend(below);
+        return Some((label, i - 1, assignment));
+    }
+    for (k, &c) in children.iter().enumerate() {
+        path.push((label.clone(), k));
+        let found = vsearch(dd, c, path, visited);
+        path.pop();
+        if found.is_some() {
+            return found;
+        }
+    }
+    None
+}
+
+/// Descends `f` and `g` together to an assignment on which `f > g`, pushing its states to
+/// `out`; `false` (with `out` untouched) if `f <= g` everywhere.
+fn vgreater<V>(dd: &mut mtmdd::MtMddManager<V>, f: NodeId, g: NodeId, out: &mut Vec<(String, usize)>) -> bool
+where
+    V: MddValue,
+{
+    if dd.min(f, g) == f {
+        return false;
+    }
+    let top = match (dd.level(&f), dd.level(&g)) {
+        (None, None) => return true,
+        (lf, lg) if lf >= lg => f,
+        _ => g,
+    };
+    let level = dd.level(&top);
+    let label = dd.label(&top).unwrap().to_string();
+    let cofactors = |dd: &mtmdd::MtMddManager<V>, x: NodeId| -> Option<Vec<NodeId>> {
+        match dd.get_node(&x).unwrap() {
+            mtmdd::Node::NonTerminal(xnode) if dd.level(&x) == level => Some(xnode.iter().collect()),
+            _ => None,
+        }
+    };
+    let fs = cofactors(dd, f);
+    let gs = cofactors(dd, g);
+    let n = fs.as_ref().or(gs.as_ref()).map_or(0, |x| x.len());
+    for k in 0..n {
+        let fk = fs.as_ref().map_or(f, |x| x[k]);
+        let gk = gs.as_ref().map_or(g, |x| x[k]);
+        if vgreater(dd, fk, gk, out) {
+            out.push((label, k));
+            return true;
+        }
+    }
+    false
+}
+
+fn bsearch(
+    dd: &mut mdd::MddManager,
+    f: NodeId,
+    path: &mut Vec<(String, usize)>,
+    visited: &mut BddHashSet<NodeId>,
+) -> Option<Found> {
+    if !visited.insert(f) {
+        return None;
+    }
+    let mdd::Node::NonTerminal(fnode) = dd.get_node(&f).unwrap() else {
+        return None;
+    };
+    let children: Vec<NodeId> = fnode.iter().collect();
+    let label = dd.label(&f).unwrap().to_string();
+    if let Some(i) = bdescent(dd, &children) {
+        let mut below = Vec::new();
+        bgreater(dd, children[i - 1], children[i], &mut below);
+        let mut assignment = path.clone();
+        assignment.extend(below);
+        return Some((label, i - 1, assignment));
+    }
+    for (k, &c) in children.iter().enumerate() {
+        path.push((label.clone(), k));
+        let found = bsearch(dd, c, path, visited);
+        path.pop();
+        if found.is_some() {
+            return found;
+        }
+    }
+    None
+}
+
+/// [`vgreater`] for boolean nodes: an assignment on which `f` is true and `g` false.
+fn bgreater(dd: &mut mdd::MddManager, f: NodeId, g: NodeId, out: &mut Vec<(String, usize)>) -> bool {
+    if dd.and(f, g) == f {
+        return false;
+    }
+    let top = match (dd.level(&f), dd.level(&g)) {
+        (None, None) => return true,
+        (lf, lg) if lf >= lg => f,
+        _ => g,
+    };
+    let level = dd.level(&top);
+    let label = dd.label(&top).unwrap().to_string();
+    let cofactors = |dd: &mdd::MddManager, x: NodeId| -> Option<Vec<NodeId>> {
+        match dd.get_node(&x).unwrap() {
+            mdd::Node::NonTerminal(xnode) if dd.level(&x) == level => Some(xnode.iter().collect()),
+            _ => None,
+        }
+    };
+    let fs = cofactors(dd, f);
+    let gs = cofactors(dd, g);
+    let n = fs.as_ref().or(gs.as_ref()).map_or(0, |x| x.len());
+    for k in 0..n {
+        let fk = fs.as_ref().map_or(f, |x| x[k]);
+        let gk = gs.as_ref().map_or(g, |x| x[k]);
+        if bgreater(dd, fk, gk, out) {
+            out.push((label, k));
+            return true;
+        }
+    }
+    false
+}
@@ -0,0 +1,87 @@
+//! Direct partial logic derivatives (DPLD) for multi-state systems.
+//!
+//! `∂φ(j→h)/∂x_i(s→r)` is `1` on the state vectors of the other components where changing
+//! `x_i` from `s` to `r` changes the system from state `j` to `h`; the construction is
+//! [`MtMdd2Manager::dpld`]. The measures of logical differential calculus are read off the
+//! derivative, a boolean diagram:
+//!
+//! - the **critical state vectors** are its paths to `1`
+//!   ([`MddNode::critical_vectors`](crate::mdd::MddNode::critical_vectors), which leaves the
+//!   differentiated variable out);
+//! - the **structural importance** is the fraction of the state vectors of the other
+//!   components on which it is `1` ([`structural_importance`]);
+//! - its probability `P(∂φ = 1)` under the component state probabilities
+//!   ([`MddNode::prob`](crate::mdd::MddNode::prob) with `ss = [1]`) is the DPLD-based
+//!   reliability importance.
+//!
+//! The integrated DPLDs take sets of system states instead of a single `j` and `h`, e.g.
+//! `∂φ(j→<j)/∂x_i(s→s−1)`, any degradation of the system from `j` caused by a degradation
+//! of `x_i`.
+
+use mddcore::prelude::*;
+
+use crate::mdd_prob::{flatten_values, FlatNode};
+
+/// The level and number of states of the variable `var`, if it is defined.
+fn find_var<V>(mdd: &MtMdd2Manager<V>, var: &str) -> Option<(Level, usize)>
+where
+    V: MddValue,
+{
+    let (nheaders, _, _, _) = mdd.size();
+    (0..nheaders)
+        .filter_map(|h| mdd.mtmdd().get_header(&h))
+        .find(|h| h.label() == var)
+        .map(|h| (h.level(), h.edge_num()))
+}
+
+/// The integrated DPLD of `node` for the change of `var` from state `s` to `r`: `1` where
+/// the system is in a state of `from` before the change and in a state of `to` after it.
+///
+/// # Errors
+///
+/// Returns `Err` if `var` is not a defined variable or `s` or `r` is not one of its states.
+pub fn dpld<V>(
+    mdd: &mut MtMdd2Manager<V>,
+    node: &Node,
+    var: &str,
+    s: usize,
+    r: usize,
+    from: &[V],
+    to: &[V],
+) -> Result<Node, RelibError>
+where
+    V: MddValue,
+{
+    let (level, range) = find_var(mdd, var).ok_or_else(|| RelibError::UnknownVariable(var.to_string()))?;
+    if s >= range || r >= range {
+        return Err(RelibError::Invalid(format!("{} has the states 0..{}, got {} -> {}", var, range - 1, s, r)));
+    }
+    Ok(mdd.dpld(*node, level, s, r, from, to))
+}
+
+/// The fraction of state vectors on which the boolean `node` is `1`, counting each variable
+/// of the diagram with all of its states equally likely. For a DPLD of `x_i` this is the
+/// structural importance: the share of the state vectors of the other components on which
+/// the change of `x_i` has the given effect.
+pub fn structural_importance<V>(mdd: &MtMdd2Manager<V>, node: &Node) -> f64
+where
+    V: MddValue,
+{
+    let flat = flatten_values(mdd, node);
+    let mut below = vec![0.0; flat.len()];
+    for (i, f) in flat.iter().enumerate().rev() {
+        below[i] = match f {
+            FlatNode::Terminal(v) => {
+                if *v == Some(V::from(1)) {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            FlatNode::Node { edges, .. } => {
+                edges.iter().map(|&e| below[e]).sum::<f64>() / edges.len() as f64
+            }
+        };
+    }
+    below[0]
+}
@@ -0,0 +1,309 @@
+//! Importance measures for multi-state components.
+//!
+//! [`mdd_prob::bmeas`](crate::mdd_prob::bmeas) gives adjacent-state Birnbaum differences.
+//! This module computes, in one shared traversal, the conditional quantities every other
+//! multi-state measure is built from:
+//!
+//! - `R_{i,j} = P(φ ∈ ss | x_i = j)`, the system reliability with component `i` fixed to
+//!   state `j`, and
+//! - `E_{i,j} = E[φ | x_i = j] = ∂E[φ]/∂p_{i,j}`, the expected-performance importance (the
+//!   partial derivative of `E[φ]` written over full state vectors).
+//!
+//! The measures for a performance level are then read off per **component threshold**
+//! `k = 1 .. M_i − 1`, splitting the states of `x_i` into "below `k`" and "at least `k`".
+//! With `U = 1 − P(φ ∈ ss)`, `U_{<k} = P(φ ∉ ss | x_i < k)` and `U_{≥k} = P(φ ∉ ss | x_i ≥ k)`:
+//!
+//! | measure | formula |
+//! |---|---|
+//! | Birnbaum | `U_{<k} − U_{≥k}` |
+//! | criticality | `P(x_i < k)·(U_{<k} − U_{≥k}) / U` |
+//! | diagnostic | `P(x_i < k)·U_{<k} / U` = `P(x_i < k | φ ∉ ss)` |
+//! | Fussell–Vesely | `P(a failed minimal cut vector y with y_i < k has occurred) / U` |
+//! | RAW (achievement worth) | `U_{<k} / U` |
+//! | RRW (reduction worth) | `U / U_{≥k}` |
+//!
+//! For a binary component (`k = 1` only) these are the usual BSS measures with `ss` as the
+//! working states. The diagnostic importance is the probability that the component is down
+//! given that the system is. Fussell–Vesely only counts a component whose failed minimal cut
+//! caused the failure: a component can be down in a failed state without lying in any failed
+//! minimal cut, so it is at most the diagnostic importance.
+//!
+//! The minimal cut vectors are the maximal state vectors `y` with `φ(y) ∉ ss`, and one has
+//! occurred when `x ≤ y`. For a coherent `φ` and an upper set `ss` such a `y` with `y_i < k`
+//! exists above `x` exactly when some failed `y ≥ x` with `y_i < k` is repaired by raising
+//! `y_i` by one, which is what [`importance`] evaluates; for other structures the measure is
+//! that probability. Ratios are undefined (`NaN` for `f64`) when their denominator is 0.
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::ops::{Add, Div, Mul, Range, Sub};
+
+use mddcore::prelude::*;
+
+use crate::mdd_prob::{flatten_values, FlatNode};
+
+/// Importance of one multi-state component; see the [module docs](self).
+///
+/// `conditional` and `performance` are indexed by state `j`, the threshold measures by
+/// `k − 1` for the thresholds `k = 1 .. M − 1`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MssImportance<T> {
+    /// `P(φ ∈ ss | x = j)`.
+    pub conditional: Vec<T>,
+    /// `E[φ | x = j] = ∂E[φ]/∂p_j`.
+    pub performance: Vec<T>,
+    pub birnbaum: Vec<T>,
+    pub criticality: Vec<T>,
+    /// `P(x < k | φ ∉ ss)`.
+    pub diagnostic: Vec<T>,
+    /// `P(a failed minimal cut vector y with y_i < k has occurred | φ ∉ ss)`.
+    pub fussell_vesely: Vec<T>,
+    pub raw: Vec<T>,
+    pub rrw: Vec<T>,
+}
+
+/// Per-variable sums over its nodes `u` of `w(u)·g(child_j(u))` and of `w(u)·g(u)`.
+struct Acc<T> {
+    states: Vec<(T, T)>,
+    base: (T, T),
+}
+
+/// The [`MssImportance`] of every variable of the diagram.
+///
+/// One backward pass gives `P(φ ∈ ss)` and `E[φ]` below each node, one forward pass the
+/// probability `w(u)` of reaching each node `u`. For a variable `x`,
+///
+/// ```text
+/// R_{x,j} = Σ_u w(u)·prob(u_j) + (P(φ ∈ ss) − Σ_u w(u)·prob(u))
+/// ```
+///
+/// over the nodes `u` labelled `x`; the second term is the mass of the paths that skip `x`,
+/// on which `φ` does not depend on its state. `E_{x,j}` is the same with `E[φ]` in place of
+/// `P(φ ∈ ss)`. A boolean node has the values `0` and `1`.
+///
+/// Fussell–Vesely builds boolean diagrams in the manager: with `F_s` the failure indicator
+/// `φ ∉ ss` at `x = s` and `↓` the down-closure over the other variables,
+///
+/// ```text
+/// FV_{x,k}·U = Σ_{j<k} p_j·P(∪_{s=j}^{k−1} ↓(F_s ∧ ¬F_{s+1}))
+/// ```
+///
+/// # Panics
+///
+/// Panics if a variable of the diagram has no entry in `pv` or fewer probabilities than
+/// states.
+pub fn importance<V, T>(
+    mdd: &mut MtMdd2Manager<V>,
+    node: &Node,
+    pv: &HashMap<String, Vec<T>>,
+    ss: &HashSet<V>,
+) -> HashMap<String, MssImportance<T>>
+where
+    T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Div<Output = T> + Copy + From<f64>,
+    V: MddValue,
+{
+    let zero = T::from(0.0);
+    let flat = flatten_values(mdd, node);
+    let probs = |label: &String| pv.get(label).unwrap_or_else(|| panic!("no probabilities for {}", label));
+
+    // (P(φ ∈ ss), E[φ]) below each node.
+    let mut below = vec![(zero, zero); flat.len()];
+    for (i, f) in flat.iter().enumerate().rev() {
+        below[i] = match f {
+            FlatNode::Terminal(Some(v)) => (T::from(if ss.contains(v) { 1.0 } else { 0.0 }), T::from(v.to_f64())),
+            FlatNode::Terminal(None) => (zero, zero),
+            FlatNode::Node { label, edges, .. } => {
+                let fp = probs(label);
+                edges.iter().enumerate().fold((zero, zero), |(a, b), (j, &e)| {
+                    (a + fp[j] * below[e].0, b + fp[j] * below[e].1)
+                })
+            }
+        };
+    }
+
+    let mut w = vec![zero; flat.len()];
+    w[0] = T::from(1.0);
+    let mut acc: HashMap<&String, Acc<T>> = HashMap::new();
+    for (i, f) in flat.iter().enumerate() {
+        if let FlatNode::Node { label, edges, .. } = f {
+            let fp = probs(label);
+            let a = acc.entry(label).or_insert_with(|| Acc {
+                states: vec![(zero, zero); edges.len()],
+                base: (zero, zero),
+            });
+            a.base = (a.base.0 + w[i] * below[i].0, a.base.1 + w[i] * below[i].1);
+            for (j, &e) in edges.iter().enumerate() {
+                w[e] = w[e] + w[i] * fp[j];
+                a.states[j] = (a.states[j].0 + w[i] * below[e].0, a.states[j].1 + w[i] * below[e].1);
+            }
+        }
+    }
+
+    let (r, e) = below[0];
+    let u = T::from(1.0) - r;
+
+    // Levels and state counts of the variables, for the Fussell–Vesely diagrams.
+    let vars: HashMap<&String, (Level, usize)> = flat
+        .iter()
+        .filter_map(|f| match f {
+            FlatNode::Node { level, label, edges } => Some((label, (*level, edges.len()))),
+            FlatNode::Terminal(_) => None,
+        })
+        .collect();
+    let fail = failure(mdd, node, ss);
+    let mut closures = BddHashMap::default();
+    let mut fussell_vesely: HashMap<&String, Vec<T>> = HashMap::new();
+    for (&label, &(level, m)) in vars.iter() {
+        let fp = probs(label);
+        let fixed: Vec<NodeId> = (0..m)
+            .map(|s| cofactor(mdd.mdd_mut(), fail, level, s, &mut BddHashMap::default()))
+            .collect();
+        // ↓ of the vectors of the other variables at which raising the variable from s repairs φ.
+        let critical: Vec<NodeId> = (0..m - 1)
+            .map(|s| {
+                let dd = mdd.mdd_mut();
+                let repaired = dd.not(fixed[s + 1]);
+                let c = dd.and(fixed[s], repaired);
+                down(dd, c, &mut closures)
+            })
+            .collect();
+        let fv = (1..m)
+            .map(|k| {
+                let mut occurred = mdd.mdd().zero();
+                let mut total = zero;
+                for j in (0..k).rev() {
+                    occurred = mdd.mdd_mut().or(occurred, critical[j]);
+                    total = total + fp[j] * bool_prob(mdd, occurred, pv);
+                }
+                total / u
+            })
+            .collect();
+        fussell_vesely.insert(label, fv);
+    }
+
+    acc.into_iter()
+        .map(|(label, a)| {
+            let fp = probs(label);
+            let conditional: Vec<T> = a.states.iter().map(|s| s.0 + r - a.base.0).collect();
+            let performance: Vec<T> = a.states.iter().map(|s| s.1 + e - a.base.1).collect();
+            // P(x ∈ range) and P(x ∈ range, φ ∉ ss).
+            let split = |range: Range<usize>| {
+                range.fold((zero, zero), |(p, q), j| (p + fp[j], q + fp[j] * (T::from(1.0) - conditional[j])))
+            };
+            let mut m = MssImportance {
+                conditional: Vec::new(),
+                performance,
+                birnbaum: Vec::new(),
+                criticality: Vec::new(),
+                diagnostic: Vec::new(),
+                fussell_vesely: fussell_vesely.remove(label).unwrap_or_default(),
+                raw: Vec::new(),
+                rrw: Vec::new(),
+            };
+            for k in 1..conditional.len() {
+                let (p_lo, f_lo) = split(0..k);
+                let (p_hi, f_hi) = split(k..conditional.len());
+                let (u_lo, u_hi) = (f_lo / p_lo, f_hi / p_hi);
+                m.birnbaum.push(u_lo - u_hi);
+                m.criticality.push(p_lo * (u_lo - u_hi) / u);
+                m.diagnostic.push(f_lo / u);
+                m.raw.push(u_lo / u);
+                m.rrw.push(u / u_hi);
+            }
+            m.conditional = conditional;
+            (label.clone(), m)
+        })
+        .collect()
+}
+
+/// The boolean node `φ ∉ ss`.
+fn failure<V>(mdd: &mut MtMdd2Manager<V>, node: &Node, ss: &HashSet<V>) -> NodeId
+where
+    V: MddValue,
+{
+    let ok = match node {
+        Node::Value(_) => ss.iter().fold(mdd.zero(), |acc, &v| {
+            let value = mdd.value(v);
+            let hit = mdd.eq(*node, value);
+            mdd.or(acc, hit)
+        }),
+        Node::Bool(_) => {
+            let up = if ss.contains(&V::from(1)) { *node } else { mdd.zero() };
+            let down = if ss.contains(&V::from(0)) { mdd.not(*node) } else { mdd.zero() };
+            mdd.or(up, down)
+        }
+    };
+    match mdd.not(ok) {
+        Node::Bool(f) => f,
+        Node::Value(f) => f,
+    }
+}
+
+/// `f` with the variable at `level` fixed to the state `s`.
+fn cofactor(dd: &mut mdd::MddManager, f: NodeId, level: Level, s: usize, cache: &mut BddHashMap<NodeId, NodeId>) -> NodeId {
+    if let Some(&x) = cache.get(&f) {
+        return x;
+    }
+    let result = match dd.get_node(&f).unwrap() {
+        mdd::Node::NonTerminal(fnode) => {
+            let headerid = fnode.headerid();
+            let children: Vec<NodeId> = fnode.iter().collect();
+            match dd.level(&f).unwrap().cmp(&level) {
+                Ordering::Greater => {
+                    let tmp: Vec<_> = children.into_iter().map(|c| cofactor(dd, c, level, s, cache)).collect();
+                    dd.create_node(headerid, &tmp)
+                }
+                Ordering::Equal => children[s],
+                Ordering::Less => f,
+            }
+        }
+        _ => f,
+    };
+    cache.insert(f, result);
+    result
+}
+
+/// The down-closure of `g`: `1` at `x` if `g(y) = 1` for some `y ≥ x`.
+fn down(dd: &mut mdd::MddManager, g: NodeId, cache: &mut BddHashMap<NodeId, NodeId>) -> NodeId {
+    if let Some(&x) = cache.get(&g) {
+        return x;
+    }
+    let result = match dd.get_node(&g).unwrap() {
+        mdd::Node::NonTerminal(fnode) => {
+            let headerid = fnode.headerid();
+            let children: Vec<NodeId> = fnode.iter().collect();
+            let mut above = dd.zero();
+            let mut tmp = Vec::with_capacity(children.len());
+            for &c in children.iter().rev() {
+                let closed = down(dd, c, cache);
+                above = dd.or(above, closed);
+                tmp.push(above);
+            }
+            tmp.reverse();
+            dd.create_node(headerid, &tmp)
+        }
+        _ => g,
+    };
+    cache.insert(g, result);
+    result
+}
+
+/// `P(g = 1)` of a boolean node.
+fn bool_prob<V, T>(mdd: &MtMdd2Manager<V>, g: NodeId, pv: &HashMap<String, Vec<T>>) -> T
+where
+    T: Add<Output = T> + Mul<Output = T> + Copy + From<f64>,
+    V: MddValue,
+{
+    let flat = flatten_values(mdd, &Node::Bool(g));
+    let mut below = vec![T::from(0.0); flat.len()];
+    for (i, f) in flat.iter().enumerate().rev() {
+        below[i] = match f {
+            FlatNode::Terminal(v) => T::from(if *v == Some(V::from(1)) { 1.0 } else { 0.0 }),
+            FlatNode::Node { label, edges, .. } => {
+                let fp = &pv[label];
+                edges.iter().enumerate().fold(T::from(0.0), |a, (j, &e)| a + fp[j] * below[e])
+            }
+        };
+    }
+    below[0]
+}
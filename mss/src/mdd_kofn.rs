@@ -0,0 +1,259 @@
+//! k-out-of-n and additive builders for multi-state components.
+//!
+//! The multi-state counterparts of `relib-bss`'s `bdd_kofn`: each builder is a Shannon
+//! expansion along the given nodes, memoized on the position and the part of the count (or
+//! sum) already decided, so the number of `ite` calls is polynomial even where the
+//! un-memoized recursion, or a hand-built chain of `ite`s, is exponential.
+
+use std::collections::HashMap;
+
+use mddcore::prelude::*;
+
+use crate::mdd_prob::{flatten_values, FlatNode};
+
+/// Boolean MDD for "at least `k` of the value nodes are `>= level`".
+///
+/// The conditions `node[i] >= level` are built once; the expansion on them is memoized on
+/// `(k, start)` as in `bdd_kofn::kofn`, so O(n·k) `ite` calls. `k = 0` is `1` and
+/// `k > n` is `0` without building anything.
+pub fn kofn<V>(dd: &mut MtMdd2Manager<V>, k: usize, node: &[Node], level: V) -> Node
+where
+    V: MddValue,
+{
+    if k == 0 {
+        return dd.one();
+    }
+    if k > node.len() {
+        return dd.zero();
+    }
+    let l = dd.value(level);
+    let cond: Vec<Node> = node.iter().map(|&x| dd.gte(x, l)).collect();
+    let n = cond.len();
+    // Flat (k+1) x (n+1) memo table, valid only within this call.
+    let mut memo: Vec<Option<Node>> = vec![None; (k + 1) * (n + 1)];
+    kofn_rec(dd, &cond, k, 0, &mut memo)
+}
+
+fn kofn_rec<V>(dd: &mut MtMdd2Manager<V>, cond: &[Node], k: usize, start: usize, memo: &mut [Option<Node>]) -> Node
+where
+    V: MddValue,
+{
+    if k == 0 {
+        return dd.one();
+    }
+    let n = cond.len();
+    if k > n - start {
+        return dd.zero();
+    }
+    let idx = k * (n + 1) + start;
+    if let Some(v) = memo[idx] {
+        return v;
+    }
+    let then = kofn_rec(dd, cond, k - 1, start + 1, memo);
+    let else_ = kofn_rec(dd, cond, k, start + 1, memo);
+    let res = dd.ite(cond[start], then, else_);
+    memo[idx] = Some(res);
+    res
+}
+
+/// Value MDD of the generalized multi-state k-out-of-n system: the largest level `l >= 1`
+/// such that at least `ks[l - 1]` of the nodes are `>= l`, or `0` if there is none.
+///
+/// This is the generalized multi-state k-out-of-n:G system of Huang, Zuo and Wu: the system
+/// is in state `j` or above iff, for some `l >= j`, at least `ks[l - 1]` components are in
+/// state `l` or above. With the same `k` at every level it is the ordinary multi-state
+/// k-out-of-n system, the `k`-th largest component state. Each level is one [`kofn`]; a
+/// level with `k = 0` replaces the levels below it and one with `k > n` is skipped.
+pub fn generalized_kofn<V>(dd: &mut MtMdd2Manager<V>, ks: &[usize], node: &[Node]) -> Node
+where
+    V: MddValue,
+{
+    let mut res = dd.value(V::from(0));
+    for (i, &k) in ks.iter().enumerate() {
+        if k > node.len() {
+            continue;
+        }
+        let l = dd.value(V::from(i as i32 + 1));
+        res = if k == 0 {
+            l
+        } else {
+            let reached = kofn(dd, k, node, V::from(i as i32 + 1));
+            dd.ite(reached, l, res)
+        };
+    }
+    res
+}
+
+/// Boolean MDD for `Σ weights[i]·node[i] >= threshold`, the multi-state counterpart of
+/// `bdd_kofn::weighted_threshold`.
+///
+/// Shannon expansion on the values each node can take, memoized on `(start, remaining
+/// threshold)`. A subproblem is decided without expanding further as soon as the weighted
+/// values left cannot reach the threshold (→ 0) or cannot fall below it (→ 1), so with
+/// non-negative weights and values there are at most `n · threshold` distinct subproblems.
+///
+/// # Panics
+///
+/// Panics if `weights` and `node` differ in length, or if a node is boolean or undetermined.
+pub fn weighted_threshold<V>(dd: &mut MtMdd2Manager<V>, weights: &[V], threshold: V, node: &[Node]) -> Node
+where
+    V: MddValue,
+{
+    assert_eq!(weights.len(), node.len(), "one weight per node");
+    let values: Vec<Vec<V>> = node
+        .iter()
+        .map(|x| {
+            assert!(matches!(x, Node::Value(_)), "weighted threshold of a boolean node");
+            let vs = values(dd, x);
+            assert!(!vs.is_empty(), "weighted threshold of an undetermined node");
+            vs
+        })
+        .collect();
+    // Bounds (min, max) of the weighted sum over node[i..].
+    let mut bounds = vec![(V::from(0), V::from(0)); node.len() + 1];
+    for i in (0..node.len()).rev() {
+        let (lo, hi) = bounds[i + 1];
+        let w = weights[i];
+        let (a, b) = (w * values[i][0], w * values[i][values[i].len() - 1]);
+        bounds[i] = (lo + a.min(b), hi + a.max(b));
+    }
+    let mut t = Threshold {
+        node,
+        weights,
+        values,
+        bounds,
+        memo: HashMap::new(),
+    };
+    t.rec(dd, 0, threshold)
+}
+
+struct Threshold<'a, V> {
+    node: &'a [Node],
+    weights: &'a [V],
+    values: Vec<Vec<V>>,
+    bounds: Vec<(V, V)>,
+    memo: HashMap<(usize, V), Node>,
+}
+
+impl<'a, V> Threshold<'a, V>
+where
+    V: MddValue,
+{
+    fn rec(&mut self, dd: &mut MtMdd2Manager<V>, start: usize, t: V) -> Node {
+        let (lo, hi) = self.bounds[start];
+        if lo >= t {
+            return dd.one();
+        }
+        if hi < t {
+            return dd.zero();
+        }
+        if let Some(&v) = self.memo.get(&(start, t)) {
+            return v;
+        }
+        let w = self.weights[start];
+        let vs = self.values[start].clone();
+        let (&top, rest) = vs.split_last().unwrap();
+        let mut res = self.rec(dd, start + 1, t - w * top);
+        for &v in rest.iter().rev() {
+            let below = self.rec(dd, start + 1, t - w * v);
+            let c = dd.value(v);
+            let cond = dd.eq(self.node[start], c);
+            res = dd.ite(cond, below, res);
+        }
+        self.memo.insert((start, t), res);
+        res
+    }
+}
+
+/// Value MDD of `Σ node[i]`, clamped to `cap` from above if given.
+///
+/// Shannon expansion on the values each node can take, memoized on `(start, partial sum)`.
+/// A subproblem is decided without expanding further once the smallest values left cannot
+/// keep the sum below `cap`, so with non-negative values there are at most `n · cap`
+/// distinct subproblems however many state vectors reach them. Negative values are allowed.
+///
+/// # Panics
+///
+/// Panics if a node is boolean or undetermined.
+pub fn sum<V>(dd: &mut MtMdd2Manager<V>, node: &[Node], cap: Option<V>) -> Node
+where
+    V: MddValue,
+{
+    let values: Vec<Vec<V>> = node
+        .iter()
+        .map(|x| {
+            assert!(matches!(x, Node::Value(_)), "sum of a boolean node");
+            let vs = values(dd, x);
+            assert!(!vs.is_empty(), "sum of an undetermined node");
+            vs
+        })
+        .collect();
+    // Smallest sum of node[i..].
+    let mut lows = vec![V::from(0); node.len() + 1];
+    for i in (0..node.len()).rev() {
+        lows[i] = lows[i + 1] + values[i][0];
+    }
+    let mut s = Sum {
+        node,
+        values,
+        lows,
+        cap,
+        memo: HashMap::new(),
+    };
+    s.rec(dd, 0, V::from(0))
+}
+
+struct Sum<'a, V> {
+    node: &'a [Node],
+    values: Vec<Vec<V>>,
+    lows: Vec<V>,
+    cap: Option<V>,
+    memo: HashMap<(usize, V), Node>,
+}
+
+impl<'a, V> Sum<'a, V>
+where
+    V: MddValue,
+{
+    fn rec(&mut self, dd: &mut MtMdd2Manager<V>, start: usize, partial: V) -> Node {
+        if let Some(c) = self.cap {
+            if partial + self.lows[start] >= c {
+                return dd.value(c);
+            }
+        }
+        if start == self.node.len() {
+            return dd.value(partial);
+        }
+        if let Some(&v) = self.memo.get(&(start, partial)) {
+            return v;
+        }
+        let vs = self.values[start].clone();
+        let (&top, rest) = vs.split_last().unwrap();
+        let mut res = self.rec(dd, start + 1, partial + top);
+        for &v in rest.iter().rev() {
+            let below = self.rec(dd, start + 1, partial + v);
+            let c = dd.value(v);
+            let cond = dd.eq(self.node[start], c);
+            res = dd.ite(cond, below, res);
+        }
+        self.memo.insert((start, partial), res);
+        res
+    }
+}
+
+/// The values `f` takes, ascending.
+fn values<V>(dd: &MtMdd2Manager<V>, f: &Node) -> Vec<V>
+where
+    V: MddValue,
+{
+    let mut vs: Vec<V> = flatten_values(dd, f)
+        .into_iter()
+        .filter_map(|x| match x {
+            FlatNode::Terminal(v) => v,
+            FlatNode::Node { .. } => None,
+        })
+        .collect();
+    vs.sort();
+    vs.dedup();
+    vs
+}
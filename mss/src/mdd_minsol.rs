@@ -28,6 +28,21 @@ where
     }
 }
 
+/// The first `i` at which the cofactors `children` of a value node stop ascending
+/// pointwise, i.e. `min(c_{i-1}, c_i) != c_{i-1}` (canonical -> O(1) id compare), or `None`
+/// if the node is monotone in its variable.
+pub(crate) fn vdescent<V>(dd: &mut mtmdd::MtMddManager<V>, children: &[NodeId]) -> Option<usize>
+where
+    V: MddValue,
+{
+    (1..children.len()).find(|&i| dd.min(children[i - 1], children[i]) != children[i - 1])
+}
+
+/// [`vdescent`] for a boolean node: the first `i` with `and(c_{i-1}, c_i) != c_{i-1}`.
+pub(crate) fn bdescent(dd: &mut mdd::MddManager, children: &[NodeId]) -> Option<usize> {
+    (1..children.len()).find(|&i| dd.and(children[i - 1], children[i]) != children[i - 1])
+}
+
 fn vminsol<V>(
     dd: &mut mtmdd::MtMddManager<V>,
     node: NodeId,
@@ -47,10 +62,8 @@ where
         mtmdd::Node::NonTerminal(fnode) => {
             let headerid = fnode.headerid();
             let children: Vec<NodeId> = fnode.iter().collect();
-            // Coherence in this variable: cofactors ascend pointwise, i.e.
-            // min(c_{i-1}, c_i) == c_{i-1} (canonical -> O(1) id compare).
-            let mono = (1..children.len())
-                .all(|i| dd.min(children[i - 1], children[i]) == children[i - 1]);
+            // Coherence in this variable: cofactors ascend pointwise.
+            let mono = vdescent(dd, &children).is_none();
             if !mono {
                 None
             } else {
@@ -175,10 +188,8 @@ fn bminsol(
         mdd::Node::NonTerminal(fnode) => {
             let headerid = fnode.headerid();
             let children: Vec<NodeId> = fnode.iter().collect();
-            // Coherence in this variable: cofactors ascend, i.e. c_{i-1} => c_i,
-            // and(c_{i-1}, c_i) == c_{i-1} (canonical -> O(1) id compare).
-            let mono = (1..children.len())
-                .all(|i| dd.and(children[i - 1], children[i]) == children[i - 1]);
+            // Coherence in this variable: cofactors ascend, i.e. c_{i-1} => c_i.
+            let mono = bdescent(dd, &children).is_none();
             if !mono {
                 None
             } else {
@@ -316,8 +327,7 @@ where
             let headerid = fnode.headerid();
             let children: Vec<NodeId> = fnode.iter().collect();
             // Same coherence condition as minsol: cofactors ascend pointwise.
-            let mono = (1..children.len())
-                .all(|i| dd.min(children[i - 1], children[i]) == children[i - 1]);
+            let mono = vdescent(dd, &children).is_none();
             if !mono {
                 None
             } else {
@@ -446,8 +456,7 @@ fn bmaxsol(
         mdd::Node::NonTerminal(fnode) => {
             let headerid = fnode.headerid();
             let children: Vec<NodeId> = fnode.iter().collect();
-            let mono = (1..children.len())
-                .all(|i| dd.and(children[i - 1], children[i]) == children[i - 1]);
+            let mono = bdescent(dd, &children).is_none();
             if !mono {
                 None
             } else {
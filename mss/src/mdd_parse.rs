@@ -0,0 +1,415 @@
+//! Infix expressions for multi-state structure functions, the readable alternative to
+//! [`MddMgr::rpn`].
+//!
+//! ```text
+//! program := decl* expr
+//! decl    := "var" name ":" int ".." int ";"        // states 0..=max, lower bound 0
+//!          | "let" name "=" expr ";"
+//! expr    := "if" expr "then" expr "else" expr | or
+//! or      := and ("||" and)*
+//! and     := not ("&&" not)*
+//! not     := "!" not | cmp
+//! cmp     := sum (("==" | "!=" | "<" | "<=" | ">" | ">=") sum)?
+//! sum     := term (("+" | "-") term)*
+//! term    := neg (("*" | "/") neg)*
+//! neg     := "-" neg | atom
+//! atom    := int | "true" | "false" | name | "(" expr ")"
+//!          | ("min" | "max") "(" expr ("," expr)* ")"
+//!          | "case" sum "{" arm ("," arm)* ","? "}"
+//! arm     := (int ("|" int)* | "_") "=>" expr
+//! name    := identifier | "quoted identifier"
+//! ```
+//!
+//! Arithmetic, comparisons and `min`/`max` take values; `&&`, `||`, `!` and the condition of
+//! `if` take booleans; comparisons yield booleans. A `case` table maps the states of its
+//! subject to results, e.g. `case pump { 0 => 0, 1 | 2 => 50, 3 => 100 }`; without a `_` arm
+//! the subject must be a variable and the arms must cover all its states.
+//!
+//! A name is a `let` binding if one is in scope, otherwise a variable: declared by a `var`
+//! line or earlier with [`MddMgr::defvar`]. An undeclared name, a type mismatch (`x + (y > 1)`),
+//! an unknown function or anything else outside the grammar is a [`ParseError`] carrying the
+//! span of the offending text. Integer literals must fit in `i32` (the range every
+//! [`MddValue`] converts from).
+
+use std::collections::HashMap;
+
+use common::parse::{ParseError, Span, Token, TokenStream};
+use mddcore::prelude::*;
+
+use crate::mdd::{MddMgr, MddNode};
+
+const PUNCTS: [&str; 24] = [
+    "+", "-", "*", "/", "==", "!=", "<", "<=", ">", ">=", "&&", "||", "!", "(", ")", ",", "=",
+    ";", ":", "..", "{", "}", "|", "=>",
+];
+const KEYWORDS: [&str; 10] = [
+    "var", "let", "if", "then", "else", "true", "false", "min", "max", "case",
+];
+
+type Spanned<V> = (MddNode<V>, Span);
+type BinOp<V> = fn(&MddNode<V>, &MddNode<V>) -> MddNode<V>;
+/// States of a `case` arm (with their spans) and the arm's result.
+type Arm<V> = (Vec<(i32, Span)>, Spanned<V>);
+
+struct Parser<'a, V> {
+    mgr: &'a mut MddMgr<V>,
+    t: TokenStream,
+    vars: HashMap<String, usize>,
+    bindings: HashMap<String, MddNode<V>>,
+}
+
+fn join(a: &Span, b: &Span) -> Span {
+    a.start..b.end
+}
+
+impl<'a, V> Parser<'a, V>
+where
+    V: MddValue,
+{
+    fn want_value(&self, x: &Spanned<V>, what: &str) -> Result<(), ParseError> {
+        if x.0.is_boolean() {
+            Err(ParseError::new(format!("{} needs a value, found a boolean", what), x.1.clone()))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn want_bool(&self, x: &Spanned<V>, what: &str) -> Result<(), ParseError> {
+        if x.0.is_boolean() {
+            Ok(())
+        } else {
+            Err(ParseError::new(format!("{} needs a boolean, found a value", what), x.1.clone()))
+        }
+    }
+
+    fn same_kind(&self, a: &Spanned<V>, b: &Spanned<V>, what: &str) -> Result<(), ParseError> {
+        if a.0.is_boolean() == b.0.is_boolean() {
+            Ok(())
+        } else {
+            Err(ParseError::new(
+                format!("{} mixes booleans and values", what),
+                join(&a.1, &b.1),
+            ))
+        }
+    }
+
+    fn literal(&mut self) -> Result<(i32, Span), ParseError> {
+        self.t.number::<i32>("an integer (within i32)")
+    }
+
+    fn program(&mut self) -> Result<MddNode<V>, ParseError> {
+        loop {
+            if self.t.eat_ident("var") {
+                let (name, span) = self.t.name(&KEYWORDS)?;
+                self.t.expect(":")?;
+                let (lo, lo_span) = self.literal()?;
+                if lo != 0 {
+                    return Err(ParseError::new("states start at 0", lo_span));
+                }
+                self.t.expect("..")?;
+                let (hi, hi_span) = self.literal()?;
+                if hi < 0 {
+                    return Err(ParseError::new("a variable needs at least one state", hi_span));
+                }
+                let range = hi as usize + 1;
+                match self.vars.get(&name) {
+                    Some(&r) if r != range => {
+                        return Err(ParseError::new(
+                            format!("{} is already declared with states 0..{}", name, r - 1),
+                            span.start..hi_span.end,
+                        ))
+                    }
+                    Some(_) => {}
+                    None => {
+                        self.mgr.defvar(&name, range);
+                        self.vars.insert(name, range);
+                    }
+                }
+                self.t.expect(";")?;
+            } else if self.t.eat_ident("let") {
+                let (name, _) = self.t.name(&KEYWORDS)?;
+                self.t.expect("=")?;
+                let (value, _) = self.expr()?;
+                self.t.expect(";")?;
+                self.bindings.insert(name, value);
+            } else {
+                break;
+            }
+        }
+        let (result, _) = self.expr()?;
+        if !self.t.is_end() {
+            return self.t.unexpected("an operator or end of input");
+        }
+        Ok(result)
+    }
+
+    fn expr(&mut self) -> Result<Spanned<V>, ParseError> {
+        let start = self.t.span();
+        if self.t.eat_ident("if") {
+            let c = self.expr()?;
+            self.want_bool(&c, "the condition of `if`")?;
+            self.t.expect_ident("then")?;
+            let a = self.expr()?;
+            self.t.expect_ident("else")?;
+            let b = self.expr()?;
+            self.same_kind(&a, &b, "`if`")?;
+            Ok((c.0.ite(&a.0, &b.0), join(&start, &b.1)))
+        } else {
+            self.or()
+        }
+    }
+
+    fn bool_chain(
+        &mut self,
+        sym: &str,
+        operand: fn(&mut Self) -> Result<Spanned<V>, ParseError>,
+        op: BinOp<V>,
+    ) -> Result<Spanned<V>, ParseError> {
+        let mut x = operand(self)?;
+        while self.t.is_punct(sym) {
+            self.want_bool(&x, &format!("`{}`", sym))?;
+            self.t.bump();
+            let y = operand(self)?;
+            self.want_bool(&y, &format!("`{}`", sym))?;
+            x = (op(&x.0, &y.0), join(&x.1, &y.1));
+        }
+        Ok(x)
+    }
+
+    fn or(&mut self) -> Result<Spanned<V>, ParseError> {
+        self.bool_chain("||", Self::and, MddNode::or)
+    }
+
+    fn and(&mut self) -> Result<Spanned<V>, ParseError> {
+        self.bool_chain("&&", Self::not, MddNode::and)
+    }
+
+    fn not(&mut self) -> Result<Spanned<V>, ParseError> {
+        let start = self.t.span();
+        if self.t.eat("!") {
+            let x = self.not()?;
+            self.want_bool(&x, "`!`")?;
+            Ok((x.0.not(), join(&start, &x.1)))
+        } else {
+            self.cmp()
+        }
+    }
+
+    fn cmp(&mut self) -> Result<Spanned<V>, ParseError> {
+        let a = self.sum()?;
+        let op: BinOp<V> = match self.t.peek() {
+            Some(Token::Punct("==")) => MddNode::eq,
+            Some(Token::Punct("!=")) => MddNode::ne,
+            Some(Token::Punct("<")) => MddNode::lt,
+            Some(Token::Punct("<=")) => MddNode::le,
+            Some(Token::Punct(">")) => MddNode::gt,
+            Some(Token::Punct(">=")) => MddNode::ge,
+            _ => return Ok(a),
+        };
+        let (sym, _) = self.t.bump().unwrap();
+        let b = self.sum()?;
+        self.want_value(&a, &format!("{}", sym))?;
+        self.want_value(&b, &format!("{}", sym))?;
+        Ok((op(&a.0, &b.0), join(&a.1, &b.1)))
+    }
+
+    fn arith_chain(
+        &mut self,
+        ops: &[(&str, BinOp<V>)],
+        operand: fn(&mut Self) -> Result<Spanned<V>, ParseError>,
+    ) -> Result<Spanned<V>, ParseError> {
+        let mut x = operand(self)?;
+        while let Some(&(sym, op)) = ops.iter().find(|(sym, _)| self.t.is_punct(sym)) {
+            self.t.bump();
+            let y = operand(self)?;
+            self.want_value(&x, &format!("`{}`", sym))?;
+            self.want_value(&y, &format!("`{}`", sym))?;
+            x = (op(&x.0, &y.0), join(&x.1, &y.1));
+        }
+        Ok(x)
+    }
+
+    fn sum(&mut self) -> Result<Spanned<V>, ParseError> {
+        self.arith_chain(&[("+", MddNode::add), ("-", MddNode::sub)], Self::term)
+    }
+
+    fn term(&mut self) -> Result<Spanned<V>, ParseError> {
+        self.arith_chain(&[("*", MddNode::mul), ("/", MddNode::div)], Self::neg)
+    }
+
+    fn neg(&mut self) -> Result<Spanned<V>, ParseError> {
+        let start = self.t.span();
+        if self.t.eat("-") {
+            let x = self.neg()?;
+            self.want_value(&x, "`-`")?;
+            let zero = self.mgr.value(V::from(0));
+            Ok((zero.sub(&x.0), join(&start, &x.1)))
+        } else {
+            self.atom()
+        }
+    }
+
+    fn atom(&mut self) -> Result<Spanned<V>, ParseError> {
+        let start = self.t.span();
+        match self.t.peek().cloned() {
+            Some(Token::Punct("(")) => {
+                self.t.bump();
+                let (x, _) = self.expr()?;
+                let end = self.t.expect(")")?;
+                Ok((x, join(&start, &end)))
+            }
+            Some(Token::Number(_)) => {
+                let (v, span) = self.literal()?;
+                Ok((self.mgr.value(V::from(v)), span))
+            }
+            Some(Token::Ident(w)) if w == "true" || w == "false" => {
+                self.t.bump();
+                Ok((self.mgr.boolean(w == "true"), start))
+            }
+            Some(Token::Ident(w)) if w == "min" || w == "max" => {
+                self.t.bump();
+                self.t.expect("(")?;
+                let mut args = vec![self.expr()?];
+                while self.t.eat(",") {
+                    args.push(self.expr()?);
+                }
+                let end = self.t.expect(")")?;
+                for a in args.iter() {
+                    self.want_value(a, &format!("`{}`", w))?;
+                }
+                let nodes: Vec<MddNode<V>> = args.into_iter().map(|(x, _)| x).collect();
+                let x = if w == "min" {
+                    self.mgr.min(&nodes)
+                } else {
+                    self.mgr.max(&nodes)
+                };
+                Ok((x, join(&start, &end)))
+            }
+            Some(Token::Ident(w)) if w == "case" => {
+                self.t.bump();
+                self.case(start)
+            }
+            Some(Token::Ident(w)) if matches!(self.t.peek_at(1), Some(Token::Punct("("))) => {
+                Err(ParseError::new(format!("unknown function `{}`", w), start))
+            }
+            Some(Token::Ident(_)) | Some(Token::Quoted(_)) => {
+                let (name, span) = self.t.name(&KEYWORDS)?;
+                if let Some(x) = self.bindings.get(&name) {
+                    return Ok((x.clone(), span));
+                }
+                match self.vars.get(&name) {
+                    Some(&range) => Ok((self.mgr.defvar(&name, range), span)),
+                    None => Err(ParseError::new(
+                        format!("unknown variable {}; declare it with `var {}: 0..N;`", name, name),
+                        span,
+                    )),
+                }
+            }
+            _ => self.t.unexpected("an operand"),
+        }
+    }
+
+    fn case(&mut self, start: Span) -> Result<Spanned<V>, ParseError> {
+        let subject_name = match self.t.peek() {
+            Some(Token::Ident(w)) | Some(Token::Quoted(w)) if !self.bindings.contains_key(w) => {
+                self.vars.get(w).map(|&r| (w.clone(), r))
+            }
+            _ => None,
+        };
+        let name_span = self.t.span();
+        let subject = self.sum()?;
+        self.want_value(&subject, "`case`")?;
+        // Only a bare variable has a known set of states.
+        let subject_name = subject_name.filter(|_| subject.1 == name_span);
+        self.t.expect("{")?;
+        // (states, result) in order, then the default.
+        let mut arms: Vec<Arm<V>> = Vec::new();
+        let mut default: Option<Spanned<V>> = None;
+        loop {
+            let arm_start = self.t.span();
+            let mut states = Vec::new();
+            let is_default = self.t.eat_ident("_");
+            if !is_default {
+                states.push(self.literal()?);
+                while self.t.eat("|") {
+                    states.push(self.literal()?);
+                }
+            }
+            self.t.expect("=>")?;
+            let result = self.expr()?;
+            if let Some(first) = arms.first().map(|a| &a.1).or(default.as_ref()) {
+                self.same_kind(first, &result, "`case`")?;
+            }
+            if is_default {
+                if default.is_some() {
+                    return Err(ParseError::new("second `_` arm", arm_start));
+                }
+                default = Some(result);
+            } else {
+                for (s, span) in states.iter() {
+                    if arms.iter().any(|(ss, _)| ss.iter().any(|(x, _)| x == s)) {
+                        return Err(ParseError::new(format!("state {} appears twice", s), span.clone()));
+                    }
+                }
+                arms.push((states, result));
+            }
+            if !self.t.eat(",") || self.t.is_punct("}") {
+                break;
+            }
+        }
+        let end = self.t.expect("}")?;
+        let span = join(&start, &end);
+        let mut acc = match default {
+            Some((x, _)) => x,
+            None => {
+                let (name, range) = match subject_name {
+                    Some(x) => x,
+                    None => {
+                        return Err(ParseError::new(
+                            "a `case` on an expression needs a `_` arm",
+                            span,
+                        ))
+                    }
+                };
+                let missing: Vec<String> = (0..range as i32)
+                    .filter(|s| !arms.iter().any(|(ss, _)| ss.iter().any(|(x, _)| x == s)))
+                    .map(|s| s.to_string())
+                    .collect();
+                if !missing.is_empty() {
+                    return Err(ParseError::new(
+                        format!("`case` does not cover state(s) {} of {}", missing.join(", "), name),
+                        span,
+                    ));
+                }
+                // Every state is covered: the last arm is the fallback.
+                let (_, (x, _)) = arms.pop().unwrap();
+                x
+            }
+        };
+        for (states, (x, _)) in arms.into_iter().rev() {
+            let cond = states
+                .iter()
+                .map(|(s, _)| subject.0.eq(&self.mgr.value(V::from(*s))))
+                .reduce(|a, b| a.or(&b))
+                .unwrap();
+            acc = cond.ite(&x, &acc);
+        }
+        Ok((acc, span))
+    }
+}
+
+/// Parses an infix expression (see the [module docs](self)) into an MDD of `mgr`.
+pub fn parse<V>(mgr: &mut MddMgr<V>, src: &str) -> Result<MddNode<V>, ParseError>
+where
+    V: MddValue,
+{
+    let t = TokenStream::new(src, &PUNCTS)?;
+    let vars = mgr.get_varorder().into_iter().collect();
+    let mut p = Parser {
+        mgr,
+        t,
+        vars,
+        bindings: HashMap::new(),
+    };
+    p.program()
+}
@@ -15,6 +15,8 @@ pub struct MddPath<V> {
     labels: Vec<String>,
     edges: Vec<usize>,
     ss: HashSet<V>,
+    /// A variable the diagram does not depend on, left out of the paths.
+    skip: Option<String>,
 }
 
 impl<V> MddPath<V>
@@ -56,9 +58,22 @@ where
             labels,
             edges,
             ss: ss.clone(),
+            skip: None,
         }
     }
 
+    /// Leaves `var`, on which the diagram must not depend, out of the paths instead of
+    /// listing each path once per state of `var`.
+    pub(crate) fn skipping(mut self, var: &str) -> Self {
+        let top = self.node.get_level();
+        if let Some(i) = self.labels.iter().position(|x| x == var) {
+            if Some(i) < top {
+                self.skip = Some(var.to_string());
+            }
+        }
+        self
+    }
+
     pub fn len(&self) -> u64 {
         self.node.mdd_count(&self.ss)
     }
@@ -97,6 +112,10 @@ where
                             let label = &self.labels[level.unwrap()];
                             let nedge = self.edges[level.unwrap()];
                             let level = level.and_then(|x| x.checked_sub(1));
+                            if self.skip.as_ref() == Some(label) {
+                                self.next_stack.push(MddStackValue::Node(level, nodeid));
+                                continue;
+                            }
                             for i in (0..nedge).rev() {
                                 self.next_stack.push(MddStackValue::Pop(label.to_string()));
                                 self.next_stack.push(MddStackValue::Node(level, nodeid));
@@ -145,6 +164,10 @@ where
                             let label = &self.labels[level.unwrap()];
                             let nedge = self.edges[level.unwrap()];
                             let level = level.and_then(|x| x.checked_sub(1));
+                            if self.skip.as_ref() == Some(label) {
+                                self.next_stack.push(MddStackValue::Node(level, nodeid));
+                                continue;
+                            }
                             for i in (0..nedge).rev() {
                                 self.next_stack.push(MddStackValue::Pop(label.to_string()));
                                 self.next_stack.push(MddStackValue::Node(level, nodeid));
@@ -1,5 +1,5 @@
-use std::collections::{HashMap, HashSet};
-use std::ops::{Add, Mul, Sub};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::ops::{Add, Div, Mul, Sub};
 
 use mddcore::prelude::*;
 
@@ -113,6 +113,270 @@ where
     result
 }
 
+/// [`prob`] under evidence on variable states: `evidence[x]` lists the states `x` is known
+/// to be in (e.g. `[2, 3]` for "state >= 2" of a 4-state component). Returns
+/// `(joint, conditional)` = `(P(f ∈ ss, evidence), P(f ∈ ss | evidence))`.
+///
+/// Each observed variable's state probabilities are reweighted to its conditional
+/// distribution `p_j·[j ∈ evidence] / P(x ∈ evidence)` before the traversal, so a node of an
+/// observed variable only follows the allowed edges; the joint is the conditional times
+/// `P(evidence) = Π P(x ∈ evidence[x])`. The conditional is undefined (`NaN` for `f64`)
+/// when the evidence has probability 0. A state listed twice counts once.
+///
+/// # Errors
+///
+/// Returns [`RelibError::MissingProbability`] if an observed variable has no entry in `pv`,
+/// and [`RelibError::Invalid`] if a listed state is out of range.
+pub fn prob_given<V, T>(
+    mdd: &mut MtMdd2Manager<V>,
+    node: &Node,
+    pv: &HashMap<String, Vec<T>>,
+    ss: &HashSet<V>,
+    evidence: &HashMap<String, Vec<usize>>,
+) -> Result<(T, T), RelibError>
+where
+    T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Div<Output = T> + Clone + Copy + PartialEq + From<f64>,
+    V: MddValue,
+{
+    let mut observed = pv.clone();
+    let mut pe = T::from(1.0);
+    for (x, states) in evidence.iter() {
+        let fp = pv.get(x).ok_or_else(|| RelibError::MissingProbability(x.clone()))?;
+        let states: BTreeSet<usize> = states.iter().copied().collect();
+        if let Some(&j) = states.iter().find(|&&j| j >= fp.len()) {
+            return Err(RelibError::Invalid(format!(
+                "evidence state {} of {} is out of range 0..{}",
+                j,
+                x,
+                fp.len()
+            )));
+        }
+        let mut q = vec![T::from(0.0); fp.len()];
+        let mut px = T::from(0.0);
+        for j in states {
+            q[j] = fp[j];
+            px = px + fp[j];
+        }
+        pe = pe * px;
+        observed.insert(x.clone(), q.into_iter().map(|v| v / px).collect());
+    }
+    let conditional = prob(mdd, node, &observed, ss);
+    Ok((pe * conditional, conditional))
+}
+
+/// One instruction of an [`MddProbTape`]: a terminal of either sub-forest, or a node
+/// reading its children from earlier slots of the tape.
+#[derive(Debug, Clone)]
+enum ProbInstr<V> {
+    Value(V),
+    Zero,
+    One,
+    Undet,
+    Node { level: Level, edges: Vec<usize> },
+}
+
+/// An MTMDD2 node linearized into a topologically ordered instruction tape (children before
+/// parents, root last), for evaluating [`prob`] under many parameter sets.
+///
+/// The graph is walked once in [`MddProbTape::new`]; each evaluation is then one linear sweep
+/// over a flat buffer, with no memo table. Parameters are given **per level**
+/// (`p[level][state]`, the level being the `defvar` order); [`MddProbTape::levels`] maps a
+/// name-keyed parameter set onto that layout. As in [`prob`], `Undet` contributes `0`.
+#[derive(Debug, Clone)]
+pub struct MddProbTape<V> {
+    tape: Vec<ProbInstr<V>>,
+    /// Variable label per level, for the levels the diagram actually reads.
+    labels: Vec<Option<String>>,
+}
+
+impl<V> MddProbTape<V>
+where
+    V: MddValue,
+{
+    pub fn new(mdd: &MtMdd2Manager<V>, node: &Node) -> Self {
+        let mut tape = MddProbTape {
+            tape: Vec::new(),
+            labels: Vec::new(),
+        };
+        let mut slots = BddHashMap::default();
+        match node {
+            Node::Value(fnode) => tape.vbuild(mdd.mtmdd(), *fnode, &mut slots),
+            Node::Bool(fnode) => tape.bbuild(mdd.mdd(), *fnode, &mut slots),
+        };
+        tape
+    }
+
+    fn push(&mut self, node: NodeId, instr: ProbInstr<V>, slots: &mut BddHashMap<NodeId, usize>) -> usize {
+        self.tape.push(instr);
+        let i = self.tape.len() - 1;
+        slots.insert(node, i);
+        i
+    }
+
+    fn set_label(&mut self, level: Level, label: &str) {
+        if self.labels.len() <= level {
+            self.labels.resize(level + 1, None);
+        }
+        self.labels[level] = Some(label.to_string());
+    }
+
+    fn vbuild(
+        &mut self,
+        mdd: &mtmdd::MtMddManager<V>,
+        node: NodeId,
+        slots: &mut BddHashMap<NodeId, usize>,
+    ) -> usize {
+        if let Some(&i) = slots.get(&node) {
+            return i;
+        }
+        let instr = match mdd.get_node(&node).unwrap() {
+            mtmdd::Node::Terminal(fnode) => ProbInstr::Value(fnode.value()),
+            mtmdd::Node::Undet => ProbInstr::Undet,
+            mtmdd::Node::NonTerminal(fnode) => {
+                let edges: Vec<usize> = fnode.iter().map(|x| self.vbuild(mdd, x, slots)).collect();
+                let level = mdd.level(&node).unwrap();
+                self.set_label(level, mdd.label(&node).unwrap());
+                ProbInstr::Node { level, edges }
+            }
+        };
+        self.push(node, instr, slots)
+    }
+
+    fn bbuild(
+        &mut self,
+        mdd: &mdd::MddManager,
+        node: NodeId,
+        slots: &mut BddHashMap<NodeId, usize>,
+    ) -> usize {
+        if let Some(&i) = slots.get(&node) {
+            return i;
+        }
+        let instr = match mdd.get_node(&node).unwrap() {
+            mdd::Node::Zero => ProbInstr::Zero,
+            mdd::Node::One => ProbInstr::One,
+            mdd::Node::Undet => ProbInstr::Undet,
+            mdd::Node::NonTerminal(fnode) => {
+                let edges: Vec<usize> = fnode.iter().map(|x| self.bbuild(mdd, x, slots)).collect();
+                let level = mdd.level(&node).unwrap();
+                self.set_label(level, mdd.label(&node).unwrap());
+                ProbInstr::Node { level, edges }
+            }
+        };
+        self.push(node, instr, slots)
+    }
+
+    /// Number of instructions (distinct nodes, terminals included).
+    pub fn len(&self) -> usize {
+        self.tape.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tape.is_empty()
+    }
+
+    /// Lay out a name-keyed parameter set per level, as [`eval`](Self::eval) expects.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a variable the diagram reads has no entry in `pv` (as [`prob`] does).
+    pub fn levels<T>(&self, pv: &HashMap<String, Vec<T>>) -> Vec<Vec<T>>
+    where
+        T: Clone,
+    {
+        self.labels
+            .iter()
+            .map(|x| match x {
+                Some(label) => pv.get(label).unwrap().clone(),
+                None => Vec::new(),
+            })
+            .collect()
+    }
+
+    /// Probability that the structure function takes a value in `ss`, with `p[level][j]` the
+    /// probability that the variable at `level` is in state `j`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `p` is shorter than the highest level the diagram reads, or `p[level]` is
+    /// shorter than the state count of the variable at `level`.
+    pub fn eval<T>(&self, p: &[Vec<T>], ss: &HashSet<V>) -> T
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialEq + From<f64>,
+    {
+        let mut buf = Vec::with_capacity(self.tape.len());
+        self.eval_into(p, ss, &mut buf)
+    }
+
+    /// [`eval`](Self::eval) reusing `buf` as the slot buffer, so a batch allocates it once.
+    fn eval_into<T>(&self, p: &[Vec<T>], ss: &HashSet<V>, buf: &mut Vec<T>) -> T
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialEq + From<f64>,
+    {
+        let indicator = |v: V| if ss.contains(&v) { T::from(1.0) } else { T::from(0.0) };
+        buf.clear();
+        for instr in self.tape.iter() {
+            let v = match instr {
+                ProbInstr::Value(v) => indicator(*v),
+                ProbInstr::Zero => indicator(V::from(0)),
+                ProbInstr::One => indicator(V::from(1)),
+                ProbInstr::Undet => T::from(0.0),
+                ProbInstr::Node { level, edges } => {
+                    let fp = &p[*level];
+                    let mut result = T::from(0.0);
+                    for (i, &x) in edges.iter().enumerate() {
+                        result = result + fp[i] * buf[x];
+                    }
+                    result
+                }
+            };
+            buf.push(v);
+        }
+        *buf.last().unwrap()
+    }
+}
+
+/// [`prob`] for every parameter set in `pvs`, sharing one [`MddProbTape`].
+pub fn prob_batch<V, T>(
+    mdd: &MtMdd2Manager<V>,
+    node: &Node,
+    pvs: &[HashMap<String, Vec<T>>],
+    ss: &HashSet<V>,
+) -> Vec<T>
+where
+    T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialEq + From<f64>,
+    V: MddValue,
+{
+    let tape = MddProbTape::new(mdd, node);
+    let mut buf = Vec::with_capacity(tape.len());
+    pvs.iter()
+        .map(|pv| {
+            let p = tape.levels(pv);
+            tape.eval_into(&p, ss, &mut buf)
+        })
+        .collect()
+}
+
+/// Columnar [`prob_batch`]: `pvs[k][level][j]` is the probability that the variable at
+/// `level` is in state `j` in the `k`-th parameter set.
+///
+/// # Panics
+///
+/// As [`MddProbTape::eval`], for any row of `pvs`.
+pub fn prob_batch_levels<V, T>(
+    mdd: &MtMdd2Manager<V>,
+    node: &Node,
+    pvs: &[Vec<Vec<T>>],
+    ss: &HashSet<V>,
+) -> Vec<T>
+where
+    T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialEq + From<f64>,
+    V: MddValue,
+{
+    let tape = MddProbTape::new(mdd, node);
+    let mut buf = Vec::with_capacity(tape.len());
+    pvs.iter().map(|p| tape.eval_into(p, ss, &mut buf)).collect()
+}
+
 /// Multi-state Birnbaum importance of every variable, computed by **backward differentiation**
 /// (reverse-mode gradient), the multi-state generalization of `bss::bdd_prob::bmeas`.
 ///
@@ -274,3 +538,459 @@ where
     }
     gradevent
 }
+
+/// A node of either sub-forest, flattened for [`posterior`], [`mpe`] and the other
+/// single-pass evaluations: a terminal or a variable node with its children as indices. The
+/// terminal carries its value ([`flatten_values`]) or whether that value is in `ss`
+/// ([`flatten`]); `None` for `Undet`, which has no value and is in neither `ss` nor its
+/// complement.
+pub(crate) enum FlatNode<L = Option<bool>> {
+    Terminal(L),
+    Node { level: Level, label: String, edges: Vec<usize> },
+}
+
+/// `Err(MissingProbability(x))` if a variable `x` of the diagram has no entry in `pv` or
+/// fewer probabilities than states: the check the fallible `try_*` evaluations run before
+/// the passes of this module, which panic there.
+pub fn check_probabilities<V, T>(mdd: &MtMdd2Manager<V>, node: &Node, pv: &HashMap<String, Vec<T>>) -> Result<(), RelibError>
+where
+    V: MddValue,
+{
+    for x in flatten(mdd, node, &HashSet::new()) {
+        if let FlatNode::Node { label, edges, .. } = x {
+            if !pv.get(&label).is_some_and(|p| p.len() >= edges.len()) {
+                return Err(RelibError::MissingProbability(label));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// [`check_probabilities`] for the columnar parameters of [`prob_batch_levels`]: `Err` if
+/// the row of a level the diagram reads is missing or shorter than the variable's state count.
+pub fn check_levels<V, T>(mdd: &MtMdd2Manager<V>, node: &Node, p: &[Vec<T>]) -> Result<(), RelibError>
+where
+    V: MddValue,
+{
+    for x in flatten(mdd, node, &HashSet::new()) {
+        if let FlatNode::Node { level, label, edges } = x {
+            if !p.get(level).is_some_and(|row| row.len() >= edges.len()) {
+                return Err(RelibError::MissingProbability(label));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The diagram in topological order, root first (index 0), with the value of each terminal
+/// (`0`/`1` in the boolean forest).
+pub(crate) fn flatten_values<V>(mdd: &MtMdd2Manager<V>, node: &Node) -> Vec<FlatNode<Option<V>>>
+where
+    V: MddValue,
+{
+    fn build<F, V>(f: NodeId, children: &F) -> Vec<FlatNode<Option<V>>>
+    where
+        F: Fn(NodeId) -> Result<(Level, String, Vec<NodeId>), Option<V>>,
+    {
+        let mut order = Vec::new();
+        let mut visited = BddHashSet::default();
+        let ids = |f: NodeId| children(f).map(|(_, _, e)| e).unwrap_or_default();
+        topo_postorder(f, &mut visited, &mut order, &ids);
+        order.reverse();
+        let index: BddHashMap<NodeId, usize> = order.iter().enumerate().map(|(i, &f)| (f, i)).collect();
+        order
+            .into_iter()
+            .map(|f| match children(f) {
+                Ok((level, label, edges)) => FlatNode::Node {
+                    level,
+                    label,
+                    edges: edges.iter().map(|e| index[e]).collect(),
+                },
+                Err(value) => FlatNode::Terminal(value),
+            })
+            .collect()
+    }
+    match node {
+        Node::Value(fnode) => {
+            let dd = mdd.mtmdd();
+            build(*fnode, &|f| match dd.get_node(&f).unwrap() {
+                mtmdd::Node::NonTerminal(x) => {
+                    Ok((dd.level(&f).unwrap(), dd.label(&f).unwrap().to_string(), x.iter().collect()))
+                }
+                mtmdd::Node::Terminal(x) => Err(Some(x.value())),
+                mtmdd::Node::Undet => Err(None),
+            })
+        }
+        Node::Bool(fnode) => {
+            let dd = mdd.mdd();
+            build(*fnode, &|f| match dd.get_node(&f).unwrap() {
+                mdd::Node::NonTerminal(x) => {
+                    Ok((dd.level(&f).unwrap(), dd.label(&f).unwrap().to_string(), x.iter().collect()))
+                }
+                mdd::Node::Zero => Err(Some(V::from(0))),
+                mdd::Node::One => Err(Some(V::from(1))),
+                mdd::Node::Undet => Err(None),
+            })
+        }
+    }
+}
+
+/// [`flatten_values`] with each terminal reduced to whether its value is in `ss`.
+fn flatten<V>(mdd: &MtMdd2Manager<V>, node: &Node, ss: &HashSet<V>) -> Vec<FlatNode>
+where
+    V: MddValue,
+{
+    flatten_values(mdd, node)
+        .into_iter()
+        .map(|f| match f {
+            FlatNode::Terminal(value) => FlatNode::Terminal(value.map(|v| ss.contains(&v))),
+            FlatNode::Node { level, label, edges } => FlatNode::Node { level, label, edges },
+        })
+        .collect()
+}
+
+/// Posterior state distributions `P(x = j | f ∈ ss)` of every variable of the diagram.
+///
+/// The multi-state form of `relib-bss`'s `bdd_prob::posterior`, with the forward weights of
+/// [`bmeas`]: for a variable `x`,
+///
+/// ```text
+/// P(x = j, f ∈ ss) = Σ_u w(u)·p_j·prob(u_j) + p_j·(P(f ∈ ss) − Σ_u w(u)·prob(u))
+/// ```
+///
+/// over the nodes `u` labelled `x`, where `w(u)` is the probability of reaching `u`; the
+/// second term is the mass of the paths that skip `x`. As for [`prob`], `Undet` is never in
+/// `ss`. The result is undefined (`NaN` for `f64`) when `f ∈ ss` is impossible.
+pub fn posterior<V, T>(
+    mdd: &MtMdd2Manager<V>,
+    node: &Node,
+    pv: &HashMap<String, Vec<T>>,
+    ss: &HashSet<V>,
+) -> HashMap<String, Vec<T>>
+where
+    T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Div<Output = T> + Clone + Copy + PartialEq + From<f64>,
+    V: MddValue,
+{
+    let flat = flatten(mdd, node, ss);
+    // prob of each node, children first
+    let mut probs = vec![T::from(0.0); flat.len()];
+    for (i, f) in flat.iter().enumerate().rev() {
+        probs[i] = match f {
+            FlatNode::Terminal(accept) => T::from(if *accept == Some(true) { 1.0 } else { 0.0 }),
+            FlatNode::Node { label, edges, .. } => {
+                let fp = pv.get(label).unwrap();
+                edges.iter().enumerate().fold(T::from(0.0), |acc, (j, &e)| acc + fp[j] * probs[e])
+            }
+        };
+    }
+    let total = probs[0];
+    let mut weight = vec![T::from(0.0); flat.len()];
+    weight[0] = T::from(1.0);
+    // label -> (mass through the variable, mass through each of its edges)
+    let mut mass: HashMap<String, (T, Vec<T>)> = HashMap::new();
+    for (i, f) in flat.iter().enumerate() {
+        if let FlatNode::Node { label, edges, .. } = f {
+            let w = weight[i];
+            let fp = pv.get(label).unwrap();
+            let (through, states) = mass
+                .entry(label.clone())
+                .or_insert_with(|| (T::from(0.0), vec![T::from(0.0); edges.len()]));
+            *through = *through + w * probs[i];
+            for (j, &e) in edges.iter().enumerate() {
+                weight[e] = weight[e] + w * fp[j];
+                states[j] = states[j] + w * fp[j] * probs[e];
+            }
+        }
+    }
+    mass.into_iter()
+        .map(|(x, (through, states))| {
+            let fp = pv.get(&x).unwrap();
+            let post = states
+                .iter()
+                .zip(fp)
+                .map(|(&s, &p)| (s + p * (total - through)) / total)
+                .collect();
+            (x, post)
+        })
+        .collect()
+}
+
+/// Most probable explanation: the assignment of states to the diagram's variables with
+/// `f ∈ ss` that has the highest probability, with that probability; `None` if `f` never
+/// takes a value in `ss`.
+///
+/// [`prob`] with max-product in place of sum-product. A variable skipped on an edge takes
+/// its most probable state; the products over skipped variables are accumulated per edge.
+/// Ties go to the lowest state.
+pub fn mpe<V, T>(
+    mdd: &MtMdd2Manager<V>,
+    node: &Node,
+    pv: &HashMap<String, Vec<T>>,
+    ss: &HashSet<V>,
+) -> Option<(T, HashMap<String, usize>)>
+where
+    T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialOrd + From<f64>,
+    V: MddValue,
+{
+    let flat = flatten(mdd, node, ss);
+    // Variables of the diagram from the lowest level up, with their most probable state;
+    // position 0 is the terminals and position k + 1 the k-th variable.
+    let mut vars: Vec<(Level, &str)> = Vec::new();
+    for f in flat.iter() {
+        if let FlatNode::Node { level, label, .. } = f {
+            vars.push((*level, label));
+        }
+    }
+    vars.sort();
+    vars.dedup();
+    let modes: Vec<(usize, T)> = vars
+        .iter()
+        .map(|(_, x)| {
+            let fp = pv.get(*x).unwrap();
+            (1..fp.len()).fold((0, fp[0]), |(j, m), k| if fp[k] > m { (k, fp[k]) } else { (j, m) })
+        })
+        .collect();
+    let pos: HashMap<Level, usize> = vars.iter().enumerate().map(|(k, (l, _))| (*l, k + 1)).collect();
+    let position = |f: &FlatNode| match f {
+        FlatNode::Node { level, .. } => pos[level],
+        FlatNode::Terminal(_) => 0,
+    };
+    let skipped = |a: usize, b: usize| modes[b..a - 1].iter().fold(T::from(1.0), |acc, &(_, m)| acc * m);
+
+    let mut best: Vec<(Option<T>, usize)> = vec![(None, 0); flat.len()];
+    for (i, f) in flat.iter().enumerate().rev() {
+        best[i] = match f {
+            FlatNode::Terminal(accept) => ((*accept == Some(true)).then(|| T::from(1.0)), 0),
+            FlatNode::Node { label, edges, .. } => {
+                let a = position(f);
+                let fp = pv.get(label).unwrap();
+                let mut result = (None, 0);
+                for (j, &e) in edges.iter().enumerate() {
+                    if let Some(v) = best[e].0 {
+                        let v = fp[j] * v * skipped(a, position(&flat[e]));
+                        if result.0.map_or(true, |r| v > r) {
+                            result = (Some(v), j);
+                        }
+                    }
+                }
+                result
+            }
+        };
+    }
+
+    let value = best[0].0?;
+    let mut assignment = HashMap::new();
+    let mut i = 0;
+    let mut above = vars.len() + 1;
+    loop {
+        let here = position(&flat[i]);
+        for k in here + 1..above {
+            assignment.insert(vars[k - 1].1.to_string(), modes[k - 1].0);
+        }
+        match &flat[i] {
+            FlatNode::Node { label, edges, .. } => {
+                let j = best[i].1;
+                assignment.insert(label.clone(), j);
+                i = edges[j];
+                above = here;
+            }
+            FlatNode::Terminal(_) => break,
+        }
+    }
+    Some((value, assignment))
+}
+
+/// `(P(φ ∈ ss), P(φ ∉ ss))`, each accumulated from its own terminals in one pass, so both
+/// are accurate when one of them is close to 1 (see `relib-bss`'s `prob_with_complement`).
+/// `Undet` counts towards neither.
+pub fn prob_with_complement<V, T>(
+    mdd: &MtMdd2Manager<V>,
+    node: &Node,
+    pv: &HashMap<String, Vec<T>>,
+    ss: &HashSet<V>,
+) -> (T, T)
+where
+    T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Clone + Copy + PartialEq + From<f64>,
+    V: MddValue,
+{
+    let flat = flatten(mdd, node, ss);
+    let mut probs = vec![(T::from(0.0), T::from(0.0)); flat.len()];
+    for (i, f) in flat.iter().enumerate().rev() {
+        probs[i] = match f {
+            FlatNode::Terminal(Some(true)) => (T::from(1.0), T::from(0.0)),
+            FlatNode::Terminal(Some(false)) => (T::from(0.0), T::from(1.0)),
+            FlatNode::Terminal(None) => (T::from(0.0), T::from(0.0)),
+            FlatNode::Node { label, edges, .. } => {
+                let fp = pv.get(label).unwrap();
+                edges.iter().enumerate().fold((T::from(0.0), T::from(0.0)), |(a, b), (j, &e)| {
+                    (a + fp[j] * probs[e].0, b + fp[j] * probs[e].1)
+                })
+            }
+        };
+    }
+    probs[0]
+}
+
+/// `Err(Invalid)` unless every entry of `pv` contains a distribution, i.e. its per-state
+/// intervals satisfy `Σ lo <= 1 <= Σ hi` (up to rounding); otherwise the box is empty and
+/// [`extreme_distribution`] would return weights that do not sum to 1.
+pub fn check_interval_distributions(pv: &HashMap<String, Vec<Interval>>) -> Result<(), RelibError> {
+    const EPS: f64 = 1e-12;
+    for (x, p) in pv.iter() {
+        let lo: f64 = p.iter().map(|q| q.lo).sum();
+        let hi: f64 = p.iter().map(|q| q.hi).sum();
+        if lo > 1.0 + EPS || hi < 1.0 - EPS {
+            return Err(RelibError::Invalid(format!(
+                "the state intervals of {} hold no distribution: Σ lo = {}, Σ hi = {}",
+                x, lo, hi
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// The distribution within the box `p` (per-state intervals, `Σ lo <= 1 <= Σ hi`) that puts
+/// as much mass as possible on the states in the order given: every state gets its lower
+/// bound and the remaining mass fills the states in `order` up to their upper bounds.
+pub(crate) fn extreme_distribution(p: &[Interval], order: impl Iterator<Item = usize>) -> Vec<f64> {
+    let mut q: Vec<f64> = p.iter().map(|x| x.lo).collect();
+    let mut rest = 1.0 - q.iter().sum::<f64>();
+    for j in order {
+        let add = rest.min(p[j].hi - p[j].lo).max(0.0);
+        q[j] += add;
+        rest -= add;
+    }
+    q
+}
+
+/// Bounds of [`prob`] when each state probability of a variable is only known to lie in an
+/// [`Interval`] (with `Σ lo <= 1 <= Σ hi` per variable).
+///
+/// A node's probability `Σ_j p_j·prob(u_j)` is linear in the state distribution, so its lower
+/// (upper) bound over the distributions within the intervals is a small linear program,
+/// solved greedily: starting from the lower bounds, the remaining mass goes to the children
+/// with the smallest (largest) bounds first. As in `relib-bss`'s `prob_interval`, solving it
+/// node by node decouples the occurrences of a variable, so the bounds are sound and may be
+/// loose; they are exact when every node of a variable makes the same choice.
+///
+/// # Errors
+///
+/// Returns [`RelibError::MissingProbability`] if a variable of the diagram has no (or too
+/// few) intervals, and [`RelibError::Invalid`] if the intervals of an entry of `pv` hold no
+/// distribution (see [`check_interval_distributions`]).
+pub fn prob_interval<V>(
+    mdd: &MtMdd2Manager<V>,
+    node: &Node,
+    pv: &HashMap<String, Vec<Interval>>,
+    ss: &HashSet<V>,
+) -> Result<Interval, RelibError>
+where
+    V: MddValue,
+{
+    check_interval_distributions(pv)?;
+    check_probabilities(mdd, node, pv)?;
+    let flat = flatten(mdd, node, ss);
+    let mut bounds = vec![Interval::from(0.0); flat.len()];
+    for (i, f) in flat.iter().enumerate().rev() {
+        bounds[i] = match f {
+            FlatNode::Terminal(accept) => Interval::from(if *accept == Some(true) { 1.0 } else { 0.0 }),
+            FlatNode::Node { label, edges, .. } => {
+                let fp = pv.get(label).unwrap();
+                let bound = |child: fn(&Interval) -> f64, upper: bool| {
+                    let v: Vec<f64> = edges.iter().map(|&e| child(&bounds[e])).collect();
+                    let mut order: Vec<usize> = (0..v.len()).collect();
+                    order.sort_by(|&a, &b| v[a].total_cmp(&v[b]));
+                    if upper {
+                        order.reverse();
+                    }
+                    let q = extreme_distribution(fp, order.into_iter());
+                    q.iter().zip(&v).map(|(q, v)| q * v).sum::<f64>()
+                };
+                Interval::new(bound(|x| x.lo, false), bound(|x| x.hi, true))
+            }
+        };
+    }
+    Ok(bounds[0])
+}
+
+/// The distribution `P(φ = v)` of the terminal value of `node` over every value `v` of its
+/// reachable terminals (`0`/`1` for a boolean node), in one bottom-up pass: each node
+/// carries the vector of masses it sends to each value. `Undet` terminals carry no mass, so
+/// the masses sum to less than 1 if one is reachable.
+///
+/// # Panics
+///
+/// Panics if a variable of the diagram has no entry in `pv` or fewer probabilities than
+/// states.
+pub fn distribution<V, T>(mdd: &MtMdd2Manager<V>, node: &Node, pv: &HashMap<String, Vec<T>>) -> BTreeMap<V, T>
+where
+    T: Add<Output = T> + Mul<Output = T> + Copy + From<f64>,
+    V: MddValue,
+{
+    let flat = flatten_values(mdd, node);
+    let values: Vec<V> = flat
+        .iter()
+        .filter_map(|f| match f {
+            FlatNode::Terminal(value) => *value,
+            FlatNode::Node { .. } => None,
+        })
+        .collect::<BTreeSet<V>>()
+        .into_iter()
+        .collect();
+    let mut mass: Vec<Vec<T>> = vec![Vec::new(); flat.len()];
+    for (i, f) in flat.iter().enumerate().rev() {
+        let mut m = vec![T::from(0.0); values.len()];
+        match f {
+            FlatNode::Terminal(Some(v)) => m[values.binary_search(v).unwrap()] = T::from(1.0),
+            FlatNode::Terminal(None) => (),
+            FlatNode::Node { label, edges, .. } => {
+                let fp = pv.get(label).unwrap_or_else(|| panic!("no probabilities for {}", label));
+                for (j, &e) in edges.iter().enumerate() {
+                    for (a, &b) in m.iter_mut().zip(mass[e].iter()) {
+                        *a = *a + fp[j] * b;
+                    }
+                }
+            }
+        }
+        mass[i] = m;
+    }
+    values.into_iter().zip(mass.swap_remove(0)).collect()
+}
+
+/// `E[φ] = Σ v·P(φ = v)` of a [`distribution`].
+pub fn expectation<V, T>(dist: &BTreeMap<V, T>) -> T
+where
+    T: Add<Output = T> + Mul<Output = T> + Copy + From<f64>,
+    V: MddValue,
+{
+    dist.iter().fold(T::from(0.0), |acc, (v, &p)| acc + T::from(v.to_f64()) * p)
+}
+
+/// `Var[φ] = Σ (v − E[φ])²·P(φ = v)` of a [`distribution`].
+pub fn variance<V, T>(dist: &BTreeMap<V, T>) -> T
+where
+    T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Copy + From<f64>,
+    V: MddValue,
+{
+    let mean = expectation(dist);
+    dist.iter().fold(T::from(0.0), |acc, (v, &p)| {
+        let d = T::from(v.to_f64()) - mean;
+        acc + d * d * p
+    })
+}
+
+/// The exceedance curve `P(φ >= v)` at every value `v` of a [`distribution`], summed from the
+/// top value down.
+pub fn exceedance<V, T>(dist: &BTreeMap<V, T>) -> BTreeMap<V, T>
+where
+    T: Add<Output = T> + Copy + From<f64>,
+    V: MddValue,
+{
+    let mut tail = T::from(0.0);
+    let mut result = BTreeMap::new();
+    for (&v, &p) in dist.iter().rev() {
+        tail = tail + p;
+        result.insert(v, tail);
+    }
+    result
+}
@@ -9,8 +9,12 @@
 use mddcore::prelude::*;
 use std::collections::HashMap;
 
+use crate::ctmc::Ctmc;
 use crate::mdd::{MddMgr, MddNode};
 use crate::mdd_minsol;
+use crate::mdd_prob;
+use crate::network::FlowNetwork;
+use crate::rbd::{Block, Rbd};
 use crate::zmdd::{ZmddMgr, ZmddNode};
 
 /// `φ` evaluated at the extreme state vector: every component at state 0 (`top = false`) or at
@@ -86,10 +90,38 @@ where
         self.mdd.defvar(label, range)
     }
 
+    /// Defines a variable bound to a CTMC; see [`MddMgr::defvar_ctmc`].
+    pub fn defvar_ctmc(&mut self, label: &str, ctmc: Ctmc) -> Result<MddNode<V>, RelibError> {
+        self.mdd.defvar_ctmc(label, ctmc)
+    }
+
     pub fn rpn(&mut self, rpn: &str, vars: &HashMap<String, usize>) -> Result<MddNode<V>, String> {
         self.mdd.rpn(rpn, vars)
     }
 
+    pub fn try_rpn(&mut self, rpn: &str, vars: &HashMap<String, usize>) -> Result<MddNode<V>, RelibError> {
+        self.mdd.try_rpn(rpn, vars)
+    }
+
+    pub fn parse(&mut self, src: &str) -> Result<MddNode<V>, RelibError> {
+        self.mdd.parse(src)
+    }
+
+    /// Compiles a multi-state reliability block diagram; see [`Rbd::compile`].
+    pub fn compile_rbd(&mut self, rbd: &Rbd, block: &Block) -> Result<MddNode<V>, RelibError> {
+        rbd.compile(&mut self.mdd, block)
+    }
+
+    /// The s-t maximum flow of `net`; see [`FlowNetwork::max_flow`].
+    pub fn max_flow(&mut self, net: &FlowNetwork<V>, s: usize, t: usize) -> Result<MddNode<V>, RelibError> {
+        net.max_flow(&mut self.mdd, s, t)
+    }
+
+    /// Whether the s-t maximum flow of `net` reaches `d`; see [`FlowNetwork::demand`].
+    pub fn flow_demand(&mut self, net: &FlowNetwork<V>, s: usize, t: usize, d: V) -> Result<MddNode<V>, RelibError> {
+        net.demand(&mut self.mdd, s, t, d)
+    }
+
     pub fn value(&self, value: V) -> MddNode<V> {
         self.mdd.value(value)
     }
@@ -126,6 +158,22 @@ where
         self.mdd.max(nodes)
     }
 
+    pub fn weighted_threshold(&self, weights: &[V], threshold: V, nodes: &[MddNode<V>]) -> MddNode<V> {
+        self.mdd.weighted_threshold(weights, threshold, nodes)
+    }
+
+    pub fn kofn(&self, k: usize, nodes: &[MddNode<V>], level: V) -> MddNode<V> {
+        self.mdd.kofn(k, nodes, level)
+    }
+
+    pub fn generalized_kofn(&self, ks: &[usize], nodes: &[MddNode<V>]) -> MddNode<V> {
+        self.mdd.generalized_kofn(ks, nodes)
+    }
+
+    pub fn sum(&self, nodes: &[MddNode<V>], cap: Option<V>) -> MddNode<V> {
+        self.mdd.sum(nodes, cap)
+    }
+
     pub fn get_varorder(&self) -> Vec<(String, usize)> {
         self.mdd.get_varorder()
     }
@@ -154,7 +202,8 @@ where
     // --- minpath: minsol (MTMDD2) -> genuine ZMDD family --------------------
 
     /// Minimal path vectors of the structure function `node` as a genuine ZMDD family
-    /// ([`ZmddNode`]), or `None` if the function is not coherent (monotone).
+    /// ([`ZmddNode`]), or `None` if the function is not coherent (monotone);
+    /// [`MddNode::check_coherence`] gives a counterexample.
     ///
     /// The minsol runs on the MTMDD2 forest; the fake-ZMDD result is converted (internally,
     /// once) into this manager's [`ZmddMgr`], so the returned [`ZmddNode`] supports the
@@ -208,4 +257,88 @@ where
         let baseline = eval_extreme(&src_rc, &tag, true);
         fake.map(|f| self.zmdd.convert_rev(&src_rc, &f, vars.clone(), baseline))
     }
+
+    /// Fallible [`minpath`](Self::minpath): `Err` if the manager of `node` has been dropped.
+    pub fn try_minpath(&self, node: &MddNode<V>) -> Result<Option<ZmddNode<V>>, RelibError> {
+        node.try_get_mgr()?;
+        Ok(self.minpath(node))
+    }
+
+    /// Fallible [`mincut`](Self::mincut); see [`try_minpath`](Self::try_minpath).
+    pub fn try_mincut(&self, node: &MddNode<V>) -> Result<Option<ZmddNode<V>>, RelibError> {
+        node.try_get_mgr()?;
+        Ok(self.mincut(node))
+    }
+
+    // --- bounds from interval-valued probabilities ---------------------------
+
+    /// Lower and upper bounds of `P(φ ∈ ss)` when each state probability is only known to
+    /// lie in an [`Interval`] (with `Σ lo <= 1 <= Σ hi` per variable).
+    ///
+    /// If [`minpath`](Self::minpath) reports `node` coherent and `ss` is an upper (or lower)
+    /// set of the values `φ` takes, `P(φ ∈ ss)` is monotone under stochastic dominance of the
+    /// component distributions. The bounds are then the probabilities at the two extreme
+    /// distributions within the intervals — the mass pushed to the lowest and to the highest
+    /// states — and they are exact. Otherwise this falls back to
+    /// [`MddNode::prob_interval`], whose bounds are sound but may be loose.
+    ///
+    /// # Errors
+    ///
+    /// As [`MddNode::prob_interval`]: `Err` if a variable of the diagram has no intervals or
+    /// the intervals of a variable hold no distribution.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mss::prelude::*;
+    /// use std::collections::HashMap;
+    ///
+    /// let mut mss: MssMgr<i32> = MssMgr::new();
+    /// let phi = mss.parse("var x: 0..2; var y: 0..2; min(x, y)").unwrap();
+    /// let p = |lo: f64, hi: f64| Interval::new(lo, hi);
+    /// let pv = HashMap::from([
+    ///     ("x".to_string(), vec![p(0.1, 0.2), p(0.3, 0.4), p(0.5, 0.6)]),
+    ///     ("y".to_string(), vec![p(0.0, 0.1), p(0.2, 0.2), p(0.7, 0.8)]),
+    /// ]);
+    ///
+    /// // P(φ = 2) = P(x = 2)·P(y = 2) lies between 0.5·0.7 and 0.6·0.8.
+    /// let b = mss.prob_interval(&phi, &pv, &[2]).unwrap();
+    /// assert!((b.lo - 0.35).abs() < 1e-12 && (b.hi - 0.48).abs() < 1e-12);
+    /// ```
+    pub fn prob_interval(
+        &self,
+        node: &MddNode<V>,
+        pv: &HashMap<String, Vec<Interval>>,
+        ss: &[V],
+    ) -> Result<Interval, RelibError> {
+        mdd_prob::check_interval_distributions(pv)?;
+        if let Some(paths) = self.minpath(node) {
+            let (inside, outside): (Vec<V>, Vec<V>) = paths.labels().into_iter().partition(|v| ss.contains(v));
+            let upper = match (inside.iter().min(), outside.iter().max()) {
+                (Some(a), Some(b)) => a > b,
+                _ => true,
+            };
+            let lower = match (inside.iter().max(), outside.iter().min()) {
+                (Some(a), Some(b)) => a < b,
+                _ => true,
+            };
+            if upper || lower {
+                let extreme = |high: bool| -> HashMap<String, Vec<f64>> {
+                    pv.iter()
+                        .map(|(x, p)| {
+                            let q = if high {
+                                mdd_prob::extreme_distribution(p, (0..p.len()).rev())
+                            } else {
+                                mdd_prob::extreme_distribution(p, 0..p.len())
+                            };
+                            (x.clone(), q)
+                        })
+                        .collect()
+                };
+                let (a, b) = (node.try_prob(&extreme(false), ss)?, node.try_prob(&extreme(true), ss)?);
+                return Ok(Interval::new(a.min(b), a.max(b)));
+            }
+        }
+        node.prob_interval(pv, ss)
+    }
 }
@@ -0,0 +1,283 @@
+//! Multi-state flow networks: s-t max-flow capacity and demand satisfaction.
+//!
+//! A [`FlowNetwork`] is a graph whose edges carry multi-state variables with a capacity for
+//! each state (a pipe segment, a transmission line). Arcs are directed; edges carry flow
+//! either way. [`FlowNetwork::max_flow`] builds the maximum `s`-`t` flow as a value
+//! [`MddNode`], and [`FlowNetwork::demand`] the boolean "the flow reaches `d`". Both are
+//! ordinary diagrams, so `prob`, `distribution` and
+//! [`MssMgr::minpath`](crate::mss::MssMgr::minpath) apply directly.
+//!
+//! By the max-flow min-cut theorem the flow is the smallest total capacity of an `s`-`t` cut:
+//!
+//! ```text
+//! maxflow(x) = min_C Σ_{e ∈ C} cap_e(x_e)
+//! ```
+//!
+//! The minimum only needs the **minimal** cuts. Among the vertices on some `s`-`t` path, these
+//! are the cuts of a source side `S` whose vertices are all reachable from `s` inside `S` and
+//! whose other vertices all reach `t` outside it. They are enumerated by growing `S` from `s`
+//! one adjacent vertex at a time (take it or exclude it for good), and each becomes a sum of
+//! capacity nodes under one `min`. The work depends on the number of minimal cuts, exponential
+//! in the vertices at worst, but never on the number of state vectors.
+
+use std::collections::HashMap;
+
+use mddcore::prelude::*;
+
+use crate::mdd::{MddMgr, MddNode};
+
+/// An edge of a [`FlowNetwork`]: `capacity[j]` is its capacity with `var` in state `j`, or
+/// `capacity[0]` throughout if it has no variable.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlowEdge<V> {
+    pub from: usize,
+    pub to: usize,
+    pub directed: bool,
+    pub var: Option<String>,
+    pub capacity: Vec<V>,
+}
+
+/// A flow network with multi-state edge capacities; see the [module docs](self).
+#[derive(Debug, Clone)]
+pub struct FlowNetwork<V> {
+    n: usize,
+    edges: Vec<FlowEdge<V>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Source,
+    Sink,
+    Free,
+}
+
+impl<V> FlowNetwork<V>
+where
+    V: MddValue,
+{
+    /// A network with `n` vertices `0..n` and no edges.
+    pub fn new(n: usize) -> Self {
+        FlowNetwork { n, edges: Vec::new() }
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.n
+    }
+
+    pub fn edges(&self) -> &[FlowEdge<V>] {
+        &self.edges
+    }
+
+    /// Adds an arc from `u` to `v`; see [`add_edge`](Self::add_edge) for `var` and `capacity`.
+    pub fn add_arc(&mut self, u: usize, v: usize, var: Option<&str>, capacity: &[V]) -> Result<(), RelibError> {
+        self.push(u, v, true, var, capacity)
+    }
+
+    /// Adds an undirected edge between `u` and `v` whose capacity is `capacity[j]` when the
+    /// variable `var` (with `capacity.len()` states) is in state `j`, or the single
+    /// `capacity[0]` when `var` is `None`. Edges are processed in insertion order.
+    pub fn add_edge(&mut self, u: usize, v: usize, var: Option<&str>, capacity: &[V]) -> Result<(), RelibError> {
+        self.push(u, v, false, var, capacity)
+    }
+
+    fn push(&mut self, u: usize, v: usize, directed: bool, var: Option<&str>, capacity: &[V]) -> Result<(), RelibError> {
+        if u >= self.n || v >= self.n {
+            return Err(RelibError::Invalid(format!("edge ({}, {}) refers to a vertex outside 0..{}", u, v, self.n)));
+        }
+        if u == v {
+            return Err(RelibError::Invalid(format!("edge ({}, {}) is a self-loop", u, v)));
+        }
+        match (var, capacity.len()) {
+            (_, 0) => return Err(RelibError::Invalid(format!("edge ({}, {}) has no capacities", u, v))),
+            (None, k) if k > 1 => {
+                return Err(RelibError::Invalid(format!("edge ({}, {}) has {} capacities but no variable", u, v, k)))
+            }
+            _ => (),
+        }
+        self.edges.push(FlowEdge {
+            from: u,
+            to: v,
+            directed,
+            var: var.map(|x| x.to_string()),
+            capacity: capacity.to_vec(),
+        });
+        Ok(())
+    }
+
+    /// The maximum flow from `s` to `t` as a value diagram.
+    ///
+    /// Variables not yet known to `mgr` are declared with `capacity.len()` states, in reverse
+    /// edge order so the first edge ends up at the top of the diagram. A variable may label
+    /// several edges (a shared component); each edge keeps its own capacities.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if `s` or `t` is outside the network, `s == t`, or an edge variable is
+    /// already defined (or used on another edge) with a different number of states.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mss::prelude::*;
+    /// use std::collections::HashMap;
+    ///
+    /// // Two parallel pipes (capacity 0, 1 or 2) into a pump (0 or 3).
+    /// let mut net = FlowNetwork::new(3);
+    /// net.add_edge(0, 1, Some("P1"), &[0, 1, 2]).unwrap();
+    /// net.add_edge(0, 1, Some("P2"), &[0, 1, 2]).unwrap();
+    /// net.add_arc(1, 2, Some("M"), &[0, 3]).unwrap();
+    ///
+    /// let mut mss: MssMgr<i32> = MssMgr::new();
+    /// let f = mss.max_flow(&net, 0, 2).unwrap();
+    /// let g = mss.parse("min(P1 + P2, case M { 0 => 0, 1 => 3 })").unwrap();
+    /// assert_eq!(f.get_node(), g.get_node());
+    /// ```
+    pub fn max_flow(&self, mgr: &mut MddMgr<V>, s: usize, t: usize) -> Result<MddNode<V>, RelibError> {
+        if s >= self.n || t >= self.n {
+            return Err(RelibError::Invalid(format!("terminal {} is outside 0..{}", if s >= self.n { s } else { t }, self.n)));
+        }
+        if s == t {
+            return Err(RelibError::Invalid(format!("source and sink are both {}", s)));
+        }
+        let caps = self.capacities(mgr)?;
+        // Only the vertices on some s-t path matter; the others stay out of both sides.
+        let (from_s, to_t) = (self.reachable(s, true), self.reachable(t, false));
+        if !from_s[t] {
+            return Ok(mgr.value(V::from(0)));
+        }
+        let mut side: Vec<Option<Side>> = (0..self.n).map(|v| (from_s[v] && to_t[v]).then_some(Side::Free)).collect();
+        side[s] = Some(Side::Source);
+        side[t] = Some(Side::Sink);
+        let mut cuts = Vec::new();
+        self.grow(&mut side, t, &mut cuts);
+        let sums: Vec<MddNode<V>> = cuts
+            .into_iter()
+            .map(|cut| {
+                let first = caps[cut[0]].clone();
+                cut[1..].iter().fold(first, |acc, &e| acc.add(&caps[e]))
+            })
+            .collect();
+        Ok(mgr.min(&sums))
+    }
+
+    /// The boolean diagram "the maximum flow from `s` to `t` is at least `d`".
+    ///
+    /// # Errors
+    ///
+    /// As [`max_flow`](Self::max_flow).
+    pub fn demand(&self, mgr: &mut MddMgr<V>, s: usize, t: usize, d: V) -> Result<MddNode<V>, RelibError> {
+        let flow = self.max_flow(mgr, s, t)?;
+        Ok(flow.ge(&mgr.value(d)))
+    }
+
+    /// The capacity node of every edge, declaring the unknown variables.
+    fn capacities(&self, mgr: &mut MddMgr<V>) -> Result<Vec<MddNode<V>>, RelibError> {
+        let mut states: HashMap<String, usize> = mgr.get_varorder().into_iter().collect();
+        for e in self.edges.iter() {
+            if let Some(x) = &e.var {
+                let k = *states.entry(x.clone()).or_insert(e.capacity.len());
+                if k != e.capacity.len() {
+                    return Err(RelibError::Invalid(format!(
+                        "{} has {} states, but edge ({}, {}) gives {} capacities",
+                        x,
+                        k,
+                        e.from,
+                        e.to,
+                        e.capacity.len()
+                    )));
+                }
+            }
+        }
+        for e in self.edges.iter().rev() {
+            if let Some(x) = &e.var {
+                mgr.defvar(x, e.capacity.len());
+            }
+        }
+        Ok(self
+            .edges
+            .iter()
+            .map(|e| match &e.var {
+                Some(x) => {
+                    let h = mgr.defvar(x, e.capacity.len()).get_header().unwrap();
+                    let nodes: Vec<_> = e.capacity.iter().map(|&c| mgr.value(c)).collect();
+                    mgr.create_node(h, &nodes)
+                }
+                None => mgr.value(e.capacity[0]),
+            })
+            .collect())
+    }
+
+    /// The `(tail, head)` pairs along which flow can pass over edge `e`.
+    fn ways(e: &FlowEdge<V>) -> impl Iterator<Item = (usize, usize)> {
+        let back = if e.directed { None } else { Some((e.to, e.from)) };
+        std::iter::once((e.from, e.to)).chain(back)
+    }
+
+    /// The vertices reachable from `v` (`forward`), or that reach `v`.
+    fn reachable(&self, v: usize, forward: bool) -> Vec<bool> {
+        let mut seen = vec![false; self.n];
+        seen[v] = true;
+        let mut stack = vec![v];
+        while let Some(u) = stack.pop() {
+            for (a, b) in self.edges.iter().flat_map(Self::ways) {
+                let (a, b) = if forward { (a, b) } else { (b, a) };
+                if a == u && !seen[b] {
+                    seen[b] = true;
+                    stack.push(b);
+                }
+            }
+        }
+        seen
+    }
+
+    /// Enumerates the source sides reachable from `s` inside themselves: the first free
+    /// vertex adjacent to the source side is either taken or excluded for good. At a leaf,
+    /// the cut is kept if every vertex outside the source side reaches `t` outside it.
+    /// Vertices without a side (`None`) are off every s-t path and ignored.
+    fn grow(&self, side: &mut Vec<Option<Side>>, t: usize, cuts: &mut Vec<Vec<usize>>) {
+        let next = self
+            .edges
+            .iter()
+            .flat_map(Self::ways)
+            .find(|&(a, b)| side[a] == Some(Side::Source) && side[b] == Some(Side::Free))
+            .map(|(_, b)| b);
+        match next {
+            Some(v) => {
+                side[v] = Some(Side::Source);
+                self.grow(side, t, cuts);
+                side[v] = Some(Side::Sink);
+                self.grow(side, t, cuts);
+                side[v] = Some(Side::Free);
+            }
+            None => {
+                if self.sink_side_reaches(side, t) {
+                    let inside = |v: usize| side[v] == Some(Side::Source);
+                    let outside = |v: usize| side[v].is_some() && !inside(v);
+                    let cut = (0..self.edges.len())
+                        .filter(|&i| {
+                            let e = &self.edges[i];
+                            (inside(e.from) && outside(e.to)) || (!e.directed && inside(e.to) && outside(e.from))
+                        })
+                        .collect();
+                    cuts.push(cut);
+                }
+            }
+        }
+    }
+
+    fn sink_side_reaches(&self, side: &[Option<Side>], t: usize) -> bool {
+        let outside = |v: usize| matches!(side[v], Some(Side::Sink | Side::Free));
+        let mut seen = vec![false; self.n];
+        seen[t] = true;
+        let mut stack = vec![t];
+        while let Some(v) = stack.pop() {
+            for (a, b) in self.edges.iter().flat_map(Self::ways) {
+                if b == v && outside(a) && !seen[a] {
+                    seen[a] = true;
+                    stack.push(a);
+                }
+            }
+        }
+        (0..self.n).all(|v| seen[v] || !outside(v))
+    }
+}
@@ -0,0 +1,232 @@
+//! Multi-state reliability block diagrams.
+//!
+//! The multi-state counterpart of `relib-bss`'s `rbd` module. A component is a variable
+//! whose state is its performance level (`0` = failed, `states - 1` = nominal), and a
+//! composite block evaluates to the performance level of the composition, so the compiled
+//! [`MddNode`] is a value-valued structure function through an [`MddMgr`]:
+//!
+//! | block | performance |
+//! |---|---|
+//! | `component(x, m)` | the `m`-state variable `x` |
+//! | `series([b…])` | `min b` — the bottleneck (flow through a chain) |
+//! | `parallel([b…])` | `max b` — the best of redundant branches |
+//! | `sum([b…])` | `Σ b` — capacities of branches that share the load |
+//! | `kofn(k, [b…])` | the `k`-th largest `b`: the level at least `k` branches reach |
+//! | `bridge(a, b, c, d, e)` | `max` over the paths `ad`, `be`, `ace`, `bcd` of their `min` |
+//! | `standby(active, [spare…])` | placeholder, compiled as `parallel` |
+//! | `sub(name)` | the sub-diagram defined as `name`, compiled once per call |
+//!
+//! With two-state components, `series`, `parallel`, `kofn` and `bridge` give the same 0/1
+//! structure function as the boolean builder.
+
+use std::collections::{HashMap, HashSet};
+
+use mddcore::prelude::*;
+
+use crate::mdd::{MddMgr, MddNode};
+
+/// A block of a multi-state reliability block diagram; see the [module docs](self).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    Component(String, usize),
+    Series(Vec<Block>),
+    Parallel(Vec<Block>),
+    Sum(Vec<Block>),
+    KofN(usize, Vec<Block>),
+    Bridge(Box<[Block; 5]>),
+    Standby(Box<Block>, Vec<Block>),
+    Sub(String),
+}
+
+impl Block {
+    /// A component with `states` performance levels `0..states`.
+    pub fn component(name: &str, states: usize) -> Block {
+        Block::Component(name.to_string(), states)
+    }
+
+    pub fn series(blocks: Vec<Block>) -> Block {
+        Block::Series(blocks)
+    }
+
+    pub fn parallel(blocks: Vec<Block>) -> Block {
+        Block::Parallel(blocks)
+    }
+
+    pub fn sum(blocks: Vec<Block>) -> Block {
+        Block::Sum(blocks)
+    }
+
+    pub fn kofn(k: usize, blocks: Vec<Block>) -> Block {
+        Block::KofN(k, blocks)
+    }
+
+    /// Bridge with `a`, `b` on the input side, `d`, `e` on the output side (`a`–`d` and
+    /// `b`–`e` are the two straight paths) and `c` across the middle.
+    pub fn bridge(a: Block, b: Block, c: Block, d: Block, e: Block) -> Block {
+        Block::Bridge(Box::new([a, b, c, d, e]))
+    }
+
+    pub fn standby(active: Block, spares: Vec<Block>) -> Block {
+        Block::Standby(Box::new(active), spares)
+    }
+
+    pub fn sub(name: &str) -> Block {
+        Block::Sub(name.to_string())
+    }
+}
+
+/// Named sub-diagrams and the compiler for multi-state [`Block`]s.
+#[derive(Debug, Clone, Default)]
+pub struct Rbd {
+    subs: HashMap<String, Block>,
+}
+
+struct Compiler<'a, V> {
+    rbd: &'a Rbd,
+    mgr: &'a mut MddMgr<V>,
+    done: HashMap<&'a str, MddNode<V>>,
+    visiting: HashSet<&'a str>,
+}
+
+impl<'a, V> Compiler<'a, V>
+where
+    V: MddValue,
+{
+    fn all(&mut self, what: &str, blocks: &'a [Block]) -> Result<Vec<MddNode<V>>, RelibError> {
+        if blocks.is_empty() {
+            return Err(RelibError::Invalid(format!("{} block has no children", what)));
+        }
+        blocks.iter().map(|b| self.compile(b)).collect()
+    }
+
+    /// `k`-th largest of `xs` as `L(k, i) = max(min(x_i, L(k-1, i+1)), L(k, i+1))`, one
+    /// row of the table per element (`None` where fewer than `k` elements remain).
+    fn kth_largest(&self, k: usize, xs: &[MddNode<V>]) -> MddNode<V> {
+        let mut row: Vec<Option<MddNode<V>>> = vec![None; k + 1];
+        for x in xs.iter().rev() {
+            for j in (1..=k).rev() {
+                let take = if j == 1 {
+                    Some(x.clone())
+                } else {
+                    row[j - 1].as_ref().map(|rest| x.min(rest))
+                };
+                row[j] = match (take, &row[j]) {
+                    (Some(a), Some(b)) => Some(a.max(b)),
+                    (a, b) => a.or_else(|| b.clone()),
+                };
+            }
+        }
+        row[k].take().unwrap()
+    }
+
+    fn compile(&mut self, block: &'a Block) -> Result<MddNode<V>, RelibError> {
+        match block {
+            Block::Component(x, states) => Ok(self.mgr.defvar(x, *states)),
+            Block::Series(bs) => {
+                let v = self.all("series", bs)?;
+                Ok(self.mgr.min(&v))
+            }
+            Block::Parallel(bs) => {
+                let v = self.all("parallel", bs)?;
+                Ok(self.mgr.max(&v))
+            }
+            Block::Sum(bs) => {
+                let v = self.all("sum", bs)?;
+                Ok(v[1..].iter().fold(v[0].clone(), |acc, x| acc.add(x)))
+            }
+            Block::KofN(k, bs) => {
+                let v = self.all("k-out-of-n", bs)?;
+                if *k == 0 || *k > v.len() {
+                    return Err(RelibError::Invalid(format!("k-out-of-n block needs 1 <= k <= {}, got {}", v.len(), k)));
+                }
+                Ok(self.kth_largest(*k, &v))
+            }
+            Block::Bridge(bs) => {
+                let v = self.all("bridge", &bs[..])?;
+                let (a, b, c, d, e) = (&v[0], &v[1], &v[2], &v[3], &v[4]);
+                let paths = [a.min(d), b.min(e), a.min(c).min(e), b.min(c).min(d)];
+                Ok(self.mgr.max(&paths))
+            }
+            Block::Standby(active, spares) => {
+                let mut v = vec![self.compile(active)?];
+                for s in spares {
+                    v.push(self.compile(s)?);
+                }
+                Ok(self.mgr.max(&v))
+            }
+            Block::Sub(name) => {
+                if let Some(x) = self.done.get(name.as_str()) {
+                    return Ok(x.clone());
+                }
+                let (key, body) = self
+                    .rbd
+                    .subs
+                    .get_key_value(name)
+                    .ok_or_else(|| RelibError::Invalid(format!("unknown sub-diagram {}", name)))?;
+                if !self.visiting.insert(key) {
+                    return Err(RelibError::Invalid(format!("sub-diagram {} contains itself", name)));
+                }
+                let x = self.compile(body)?;
+                self.visiting.remove(key.as_str());
+                self.done.insert(key, x.clone());
+                Ok(x)
+            }
+        }
+    }
+}
+
+impl Rbd {
+    pub fn new() -> Self {
+        Rbd::default()
+    }
+
+    /// Defines the sub-diagram `name`, usable as [`Block::sub`]`(name)` in other blocks.
+    pub fn define(&mut self, name: &str, block: Block) -> Result<(), RelibError> {
+        if self.subs.contains_key(name) {
+            return Err(RelibError::Invalid(format!("sub-diagram {} is already defined", name)));
+        }
+        self.subs.insert(name.to_string(), block);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Block> {
+        self.subs.get(name)
+    }
+
+    /// Compiles `block` into its performance function.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if a [`Block::sub`] names an undefined or self-containing sub-diagram,
+    /// a composite block has no children, or a k-out-of-n block has `k` outside `1..=n`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mss::prelude::*;
+    ///
+    /// // Two pipes of capacity 0..=2 feeding a pump of capacity 0..=3.
+    /// let mut rbd = Rbd::new();
+    /// rbd.define("pipes", Block::sum(vec![
+    ///     Block::component("P1", 3), Block::component("P2", 3),
+    /// ])).unwrap();
+    /// let system = Block::series(vec![Block::sub("pipes"), Block::component("M", 4)]);
+    ///
+    /// let mut mss: MssMgr<i32> = MssMgr::new();
+    /// let f = mss.compile_rbd(&rbd, &system).unwrap();
+    /// let g = mss.parse("min(P1 + P2, M)").unwrap();
+    /// assert_eq!(f.get_node(), g.get_node());
+    /// ```
+    pub fn compile<V>(&self, mgr: &mut MddMgr<V>, block: &Block) -> Result<MddNode<V>, RelibError>
+    where
+        V: MddValue,
+    {
+        let mut c = Compiler {
+            rbd: self,
+            mgr,
+            done: HashMap::new(),
+            visiting: HashSet::new(),
+        };
+        c.compile(block)
+    }
+}
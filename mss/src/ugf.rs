@@ -0,0 +1,342 @@
+//! Universal generating functions (UGF) for series-parallel multi-state systems.
+//!
+//! Levitin's UGF of a performance `G` is the polynomial `u(z) = Σ_k p_k·z^{g_k}`: the
+//! distribution `P(G = g_k) = p_k` as a map from performance to mass ([`Ugf`]). Independent
+//! blocks combine with a composition operator `Ω_ω`,
+//!
+//! ```text
+//! Ω_ω(Σ_i p_i z^{a_i}, Σ_j q_j z^{b_j}) = Σ_{i,j} p_i·q_j · z^{ω(a_i, b_j)}
+//! ```
+//!
+//! with `ω = min` for series, `max` for parallel and `+` for load sharing
+//! ([`Ugf::compose`] takes any `ω`). Like terms are collected as they appear, so no variable
+//! order is involved. The technique needs the blocks to be independent: a component that
+//! appears twice (or a bridge) is out of its reach, while the MDD engine handles both.
+//!
+//! [`from_block`] evaluates an [`Rbd`] model with the same block semantics as
+//! [`Rbd::compile`], and [`cross_check`] compares the result with the
+//! [`distribution`](crate::mdd::MddNode::distribution) of the compiled MDD: two independent
+//! engines on one model.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::ops::{Add, Mul, Sub};
+
+use mddcore::prelude::*;
+
+use crate::mdd::MddMgr;
+use crate::mdd_prob;
+use crate::rbd::{Block, Rbd};
+
+/// The UGF of a performance: its distribution `performance -> probability`; see the
+/// [module docs](self).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ugf<V, T> {
+    terms: BTreeMap<V, T>,
+}
+
+impl<V, T> Ugf<V, T>
+where
+    V: MddValue,
+    T: Add<Output = T> + Mul<Output = T> + Copy + From<f64>,
+{
+    /// The UGF with the given terms.
+    pub fn new(terms: BTreeMap<V, T>) -> Self {
+        Ugf { terms }
+    }
+
+    /// The UGF of a component with performance `levels[j]` in state `j`, which has
+    /// probability `probs[j]`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if `levels` is empty or `probs` has fewer entries than `levels`.
+    pub fn component(levels: &[V], probs: &[T]) -> Result<Self, RelibError> {
+        if levels.is_empty() {
+            return Err(RelibError::Invalid("a component needs at least one state".to_string()));
+        }
+        if probs.len() < levels.len() {
+            return Err(RelibError::Invalid(format!("{} states but {} probabilities", levels.len(), probs.len())));
+        }
+        let mut terms = BTreeMap::new();
+        for (&g, &p) in levels.iter().zip(probs) {
+            collect(&mut terms, g, p);
+        }
+        Ok(Ugf { terms })
+    }
+
+    /// The UGF of the fixed performance `v`.
+    pub fn constant(v: V) -> Self {
+        Ugf {
+            terms: BTreeMap::from([(v, T::from(1.0))]),
+        }
+    }
+
+    /// The terms, i.e. the distribution `P(G = g)`.
+    pub fn terms(&self) -> &BTreeMap<V, T> {
+        &self.terms
+    }
+
+    /// `Ω_ω(self, other)`: the UGF of `ω(G1, G2)` for independent `G1`, `G2`.
+    pub fn compose<F>(&self, other: &Ugf<V, T>, op: F) -> Ugf<V, T>
+    where
+        F: Fn(V, V) -> V,
+    {
+        let mut terms = BTreeMap::new();
+        for (&a, &p) in self.terms.iter() {
+            for (&b, &q) in other.terms.iter() {
+                collect(&mut terms, op(a, b), p * q);
+            }
+        }
+        Ugf { terms }
+    }
+
+    /// The UGF of `ω(G)`.
+    pub fn map<F>(&self, op: F) -> Ugf<V, T>
+    where
+        F: Fn(V) -> V,
+    {
+        let mut terms = BTreeMap::new();
+        for (&a, &p) in self.terms.iter() {
+            collect(&mut terms, op(a), p);
+        }
+        Ugf { terms }
+    }
+
+    /// Series composition: `min`.
+    pub fn series(&self, other: &Ugf<V, T>) -> Ugf<V, T> {
+        self.compose(other, |a, b| a.min(b))
+    }
+
+    /// Parallel composition: `max`.
+    pub fn parallel(&self, other: &Ugf<V, T>) -> Ugf<V, T> {
+        self.compose(other, |a, b| a.max(b))
+    }
+
+    /// Load-sharing composition: `+`.
+    pub fn sum(&self, other: &Ugf<V, T>) -> Ugf<V, T> {
+        self.compose(other, |a, b| a + b)
+    }
+
+    /// `P(G ∈ ss)`.
+    pub fn prob(&self, ss: &[V]) -> T {
+        ss.iter()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .filter_map(|v| self.terms.get(v))
+            .fold(T::from(0.0), |acc, &p| acc + p)
+    }
+
+    /// `E[G]`.
+    pub fn expectation(&self) -> T {
+        mdd_prob::expectation(&self.terms)
+    }
+
+    /// `P(G >= v)` at every performance `v`.
+    pub fn exceedance(&self) -> BTreeMap<V, T> {
+        mdd_prob::exceedance(&self.terms)
+    }
+}
+
+fn collect<V, T>(terms: &mut BTreeMap<V, T>, v: V, p: T)
+where
+    V: MddValue,
+    T: Add<Output = T> + Copy,
+{
+    terms.entry(v).and_modify(|q| *q = *q + p).or_insert(p);
+}
+
+/// The `k`-th largest of independent performances: for each threshold `v`, the probability
+/// that at least `k` of them reach `v` (a count distribution built one block at a time),
+/// then the differences of these tails.
+fn kth_largest<V, T>(k: usize, us: &[Ugf<V, T>]) -> Ugf<V, T>
+where
+    V: MddValue,
+    T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Copy + From<f64>,
+{
+    let values: Vec<V> = us
+        .iter()
+        .flat_map(|u| u.terms.keys().copied())
+        .collect::<BTreeSet<V>>()
+        .into_iter()
+        .collect();
+    let tail = |v: V| {
+        let mut count = vec![T::from(0.0); us.len() + 1];
+        count[0] = T::from(1.0);
+        for (i, u) in us.iter().enumerate() {
+            let q = u.terms.range(v..).fold(T::from(0.0), |acc, (_, &p)| acc + p);
+            let r = T::from(1.0) - q;
+            for c in (0..=i + 1).rev() {
+                let up = if c > 0 { count[c - 1] * q } else { T::from(0.0) };
+                count[c] = count[c] * r + up;
+            }
+        }
+        count[k..].iter().fold(T::from(0.0), |acc, &p| acc + p)
+    };
+    let tails: Vec<T> = values.iter().map(|&v| tail(v)).collect();
+    let mut terms = BTreeMap::new();
+    for (i, &v) in values.iter().enumerate() {
+        let above = tails.get(i + 1).copied().unwrap_or(T::from(0.0));
+        terms.insert(v, tails[i] - above);
+    }
+    Ugf { terms }
+}
+
+struct Evaluator<'a, T> {
+    rbd: &'a Rbd,
+    pv: &'a HashMap<String, Vec<T>>,
+    seen: HashSet<&'a str>,
+    visiting: HashSet<&'a str>,
+}
+
+impl<'a, T> Evaluator<'a, T>
+where
+    T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Copy + From<f64>,
+{
+    fn all<V>(&mut self, what: &str, blocks: &'a [Block]) -> Result<Vec<Ugf<V, T>>, RelibError>
+    where
+        V: MddValue,
+    {
+        if blocks.is_empty() {
+            return Err(RelibError::Invalid(format!("{} block has no children", what)));
+        }
+        blocks.iter().map(|b| self.eval(b)).collect()
+    }
+
+    fn fold<V, F>(us: Vec<Ugf<V, T>>, op: F) -> Ugf<V, T>
+    where
+        V: MddValue,
+        F: Fn(&Ugf<V, T>, &Ugf<V, T>) -> Ugf<V, T>,
+    {
+        let mut it = us.into_iter();
+        let first = it.next().unwrap();
+        it.fold(first, |acc, u| op(&acc, &u))
+    }
+
+    fn eval<V>(&mut self, block: &'a Block) -> Result<Ugf<V, T>, RelibError>
+    where
+        V: MddValue,
+    {
+        match block {
+            Block::Component(x, states) => {
+                if !self.seen.insert(x) {
+                    return Err(RelibError::Invalid(format!(
+                        "component {} appears more than once; UGF needs independent blocks",
+                        x
+                    )));
+                }
+                let p = self.pv.get(x).ok_or_else(|| RelibError::MissingProbability(x.clone()))?;
+                let levels: Vec<V> = (0..*states).map(|j| V::from(j as i32)).collect();
+                Ugf::component(&levels, p).map_err(|e| RelibError::Invalid(format!("{}: {}", x, e)))
+            }
+            Block::Series(bs) => Ok(Self::fold(self.all("series", bs)?, Ugf::series)),
+            Block::Parallel(bs) => Ok(Self::fold(self.all("parallel", bs)?, Ugf::parallel)),
+            Block::Sum(bs) => Ok(Self::fold(self.all("sum", bs)?, Ugf::sum)),
+            Block::KofN(k, bs) => {
+                let us = self.all("k-out-of-n", bs)?;
+                if *k == 0 || *k > us.len() {
+                    return Err(RelibError::Invalid(format!("k-out-of-n block needs 1 <= k <= {}, got {}", us.len(), k)));
+                }
+                Ok(kth_largest(*k, &us))
+            }
+            Block::Bridge(_) => {
+                Err(RelibError::Invalid("a bridge repeats its components across paths; UGF needs a series-parallel structure".to_string()))
+            }
+            Block::Standby(active, spares) => {
+                let mut us = vec![self.eval(active)?];
+                for s in spares {
+                    us.push(self.eval(s)?);
+                }
+                Ok(Self::fold(us, Ugf::parallel))
+            }
+            Block::Sub(name) => {
+                let body = self.rbd.get(name).ok_or_else(|| RelibError::Invalid(format!("unknown sub-diagram {}", name)))?;
+                if !self.visiting.insert(name) {
+                    return Err(RelibError::Invalid(format!("sub-diagram {} contains itself", name)));
+                }
+                let u = self.eval(body)?;
+                self.visiting.remove(name.as_str());
+                Ok(u)
+            }
+        }
+    }
+}
+
+/// The UGF of the performance of `block`, with the block semantics of [`Rbd::compile`]: a
+/// component's performance is its state, and `pv[x][j]` is the probability of state `j`.
+///
+/// # Errors
+///
+/// Returns `Err` where [`Rbd::compile`] would, and also if a component appears more than once
+/// (including through a sub-diagram used twice), for a bridge, or if a component has no
+/// (or too few) probabilities in `pv`.
+///
+/// # Example
+///
+/// ```
+/// use mss::prelude::*;
+/// use mss::ugf;
+/// use std::collections::HashMap;
+///
+/// // Two pipes sharing the load, in series with a pump.
+/// let rbd = Rbd::new();
+/// let system = Block::series(vec![
+///     Block::sum(vec![Block::component("P1", 3), Block::component("P2", 3)]),
+///     Block::component("M", 4),
+/// ]);
+/// let pv: HashMap<String, Vec<f64>> = HashMap::from([
+///     ("P1".to_string(), vec![0.1, 0.2, 0.7]),
+///     ("P2".to_string(), vec![0.2, 0.2, 0.6]),
+///     ("M".to_string(), vec![0.05, 0.05, 0.1, 0.8]),
+/// ]);
+/// let u: Ugf<i32, f64> = ugf::from_block(&rbd, &system, &pv).unwrap();
+/// assert!((u.terms().values().sum::<f64>() - 1.0).abs() < 1e-12);
+///
+/// let mut mgr: MddMgr<i32> = MddMgr::new();
+/// assert!(ugf::cross_check(&mut mgr, &rbd, &system, &pv, 1e-12).unwrap() < 1e-12);
+/// ```
+pub fn from_block<V, T>(rbd: &Rbd, block: &Block, pv: &HashMap<String, Vec<T>>) -> Result<Ugf<V, T>, RelibError>
+where
+    V: MddValue,
+    T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Copy + From<f64>,
+{
+    let mut e = Evaluator {
+        rbd,
+        pv,
+        seen: HashSet::new(),
+        visiting: HashSet::new(),
+    };
+    e.eval(block)
+}
+
+/// Evaluates `block` both ways, by [`from_block`] and by the
+/// [`distribution`](crate::mdd::MddNode::distribution) of [`Rbd::compile`] in `mgr`, and
+/// returns the largest difference of `P(G = v)` over the performances `v`.
+///
+/// # Errors
+///
+/// Returns `Err` if either engine rejects the model, or if the two distributions differ by
+/// more than `tol` at some performance (naming the first one).
+pub fn cross_check<V>(
+    mgr: &mut MddMgr<V>,
+    rbd: &Rbd,
+    block: &Block,
+    pv: &HashMap<String, Vec<f64>>,
+    tol: f64,
+) -> Result<f64, RelibError>
+where
+    V: MddValue,
+{
+    let u: Ugf<V, f64> = from_block(rbd, block, pv)?;
+    let dist = rbd.compile(mgr, block)?.try_distribution(pv)?;
+    let mut worst = 0.0_f64;
+    for &v in u.terms.keys().chain(dist.keys()) {
+        let a = u.terms.get(&v).copied().unwrap_or(0.0);
+        let b = dist.get(&v).copied().unwrap_or(0.0);
+        let d = (a - b).abs();
+        if d > tol {
+            return Err(RelibError::Invalid(format!("P(G = {}) is {} by UGF but {} by MDD", v, a, b)));
+        }
+        worst = worst.max(d);
+    }
+    Ok(worst)
+}
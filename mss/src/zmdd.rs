@@ -1,13 +1,14 @@
 //! Ergonomic wrapper over [`ZmddManager`]: `ZmddMgr` / `ZmddNode` for the set families of
 //! minimal path vectors. Families are produced by [`MssMgr::minpath`](crate::mss::MssMgr::minpath)
-//! and support the label-wise set operations `intersect` / `setdiff` (see `mddcore::zmdd_ops`),
-//! plus `count` / `extract`.
+//! and support the set operations of `mddcore::zmdd_ops` (`intersect`, `setdiff`, `union`,
+//! `product`, `minimal` / `maximal`, `relabel`), plus `count` / `extract`.
 
 use mddcore::prelude::*;
 use mddcore::mtmdd::Node as ZNode;
 
 use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
+use std::ops::{Add, Mul, Sub};
 use std::rc::{Rc, Weak};
 
 use crate::zmdd_convert;
@@ -177,7 +178,53 @@ where
     }
 
     pub fn get_mgr(&self) -> Rc<RefCell<ZmddManager<V>>> {
-        self.parent.upgrade().unwrap()
+        self.try_get_mgr().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`get_mgr`](Self::get_mgr): `Err` if the manager has been dropped.
+    pub fn try_get_mgr(&self) -> Result<Rc<RefCell<ZmddManager<V>>>, RelibError> {
+        self.mgr_of(&[])
+    }
+
+    /// The manager of `self` and `others`.
+    fn mgr_of(&self, others: &[&ZmddNode<V>]) -> Result<Rc<RefCell<ZmddManager<V>>>, RelibError> {
+        let zmdd = self.parent.upgrade().ok_or(RelibError::ManagerDropped)?;
+        for x in others {
+            if !x.parent.ptr_eq(&self.parent) {
+                return Err(if x.parent.strong_count() == 0 {
+                    RelibError::ManagerDropped
+                } else {
+                    RelibError::ManagerMismatch
+                });
+            }
+        }
+        Ok(zmdd)
+    }
+
+    /// `Err(MissingProbability(x))` if a variable `x` the diagram reads has no entry in `pv`,
+    /// or a recorded variable has fewer probabilities than states: where
+    /// [`prob_bounds`](Self::prob_bounds) and [`prob_sdp`](Self::prob_sdp) panic.
+    fn check_probabilities<T>(&self, dd: &ZmddManager<V>, pv: &HashMap<String, Vec<T>>) -> Result<(), RelibError> {
+        for (x, states) in self.vars.iter() {
+            if pv.get(x).is_some_and(|p| p.len() < *states) {
+                return Err(RelibError::MissingProbability(x.clone()));
+            }
+        }
+        let mut seen = BddHashSet::default();
+        let mut stack = vec![self.node];
+        while let Some(id) = stack.pop() {
+            if !seen.insert(id) {
+                continue;
+            }
+            if let ZNode::NonTerminal(f) = dd.get_node(&id).unwrap() {
+                let label = dd.label(&id).unwrap();
+                if !pv.contains_key(label) {
+                    return Err(RelibError::MissingProbability(label.to_string()));
+                }
+                stack.extend(f.iter());
+            }
+        }
+        Ok(())
     }
 
     pub fn get_id(&self) -> NodeId {
@@ -202,7 +249,12 @@ where
     /// A vector is filed under the label **equal to its own `φ(x)`**, so this is the set of
     /// levels for which the family holds anything.
     pub fn labels(&self) -> Vec<V> {
-        let mgr = self.parent.upgrade().unwrap();
+        self.try_labels().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`labels`](Self::labels): `Err` if the manager has been dropped.
+    pub fn try_labels(&self) -> Result<Vec<V>, RelibError> {
+        let mgr = self.mgr_of(&[])?;
         let dd = mgr.borrow();
         let mut seen = BddHashSet::default();
         let mut out = Vec::new();
@@ -219,7 +271,7 @@ where
         }
         out.sort();
         out.dedup();
-        out
+        Ok(out)
     }
 
     /// The **classical** minimal path / cut vectors *at level* `v`:
@@ -227,63 +279,308 @@ where
     ///
     /// [`extract`](Self::extract) alone answers a different question — it returns the stratum
     /// whose `φ(x)` is **exactly** `v`. The classical set is the union of the strata on the
-    /// relevant side of `v` with the dominated vectors removed, which is what this does.
+    /// relevant side of `v` with the dominated vectors removed: this relabels those strata to
+    /// `v` and filters them on the diagram with [`minimal`](Self::minimal) (paths) /
+    /// [`maximal`](Self::maximal) (cuts).
     /// The two agree at the extreme labels but differ in between: for a cut family a vector
     /// with `φ(x) < v` can still be maximal within `{x : φ(x) ≤ v}`, and it lives in a lower
     /// stratum.
     pub fn extract_level(&self, v: V) -> Vec<HashMap<String, usize>> {
-        let ss: HashSet<V> = self
-            .labels()
-            .into_iter()
-            .filter(|w| if self.reverse { *w <= v } else { *w >= v })
+        let reverse = self.reverse;
+        self.merged(v, |w| if reverse { w <= v } else { w >= v })
+            .extract(&HashSet::from([v]))
+            .collect()
+    }
+
+    /// The strata selected by `keep` under the single label `v`, with the dominated vectors
+    /// removed: minimal for paths, maximal (true states) for cuts.
+    fn merged<F>(&self, v: V, keep: F) -> ZmddNode<V>
+    where
+        F: Fn(V) -> bool,
+    {
+        self.relabel(|w| if keep(w) { Some(v) } else { None }).minimal_raw()
+    }
+
+    /// Esary–Proschan bounds on `P(φ >= level)` from this family alone, for independent
+    /// components with state probabilities `pv`.
+    ///
+    /// For a path family with the minimal path vectors `P_k` of `level` (see
+    /// [`extract_level`](Self::extract_level)), `φ >= level` iff `x >= P_k` for some `k`:
+    ///
+    /// ```text
+    /// max_k P(x >= P_k)  <=  P(φ >= level)  <=  B
+    /// ```
+    ///
+    /// For a cut family with the maximal cut vectors `C_k` of the level below, `φ >= level`
+    /// iff `x <= C_k` for no `k`:
+    ///
+    /// ```text
+    /// 1 − B  <=  P(φ >= level)  <=  1 − max_k P(x <= C_k)
+    /// ```
+    ///
+    /// `B` is the Esary–Proschan product taken at every node of the diagram (in stored
+    /// coordinates): a node on `X` with children `c_0 .. c_{M-1}` gives
+    ///
+    /// ```text
+    /// B = 1 − Π_d (1 − P(X >= d) · B(c_d))
+    /// ```
+    ///
+    /// with `1` at a terminal and `0` for the empty family. The events under a node are
+    /// increasing in independent components, hence associated, so every step keeps an upper
+    /// bound, and `B` is never looser than `1 − Π_k (1 − P(x >= P_k))` over the vectors
+    /// themselves. The bounds need φ coherent, as for any family from `minpath` / `mincut`.
+    /// Both terms are recursions over the diagram memoized by node, so they are linear in its
+    /// size; the interval is clamped so that rounding cannot put `B` on the wrong side of the
+    /// `max` term. [`prob_sdp`](Self::prob_sdp) gives the exact value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a recorded variable has no entry in `pv` or fewer probabilities than states.
+    pub fn prob_bounds(&self, level: V, pv: &HashMap<String, Vec<f64>>) -> Interval {
+        self.try_prob_bounds(level, pv).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`prob_bounds`](Self::prob_bounds): `Err` if the manager has been dropped or
+    /// `pv` lacks probabilities, where `prob_bounds` panics.
+    pub fn try_prob_bounds(&self, level: V, pv: &HashMap<String, Vec<f64>>) -> Result<Interval, RelibError> {
+        let mgr = self.mgr_of(&[])?;
+        self.check_probabilities(&mgr.borrow(), pv)?;
+        let family = self.level_family(level);
+        let tails = self.tails(pv);
+        let dd = mgr.borrow();
+        let max = max_member_prob(&dd, family.node, &tails, &mut BddHashMap::default());
+        let union = union_bound(&dd, family.node, &tails, &mut BddHashMap::default());
+        Ok(if self.reverse {
+            Interval::new((1.0 - union).min(1.0 - max), 1.0 - max)
+        } else {
+            Interval::new(max, union.max(max))
+        })
+    }
+
+    /// The exact `P(φ >= level)` for independent components with state probabilities `pv`, by
+    /// a sum of disjoint products over the diagram of the family (see
+    /// [`prob_bounds`](Self::prob_bounds) for the vectors used).
+    ///
+    /// With `U(F)` the probability that `x` lies above some vector of a family `F` (in the
+    /// family's own coordinates, levels below max for a cut family), a node on `X` with
+    /// children `c_0 .. c_{M-1}` gives
+    ///
+    /// ```text
+    /// U(F) = Σ_s P(X = s) · U(c_0 ∪ .. ∪ c_s)
+    /// ```
+    ///
+    /// since the vectors that `x` can lie above are those with `X <= s`; the unions are
+    /// [`union`](Self::union)s on the diagram, memoized by node. A terminal is `1`, the empty
+    /// family `0`. For a path family `P(φ >= level) = U`, for a cut family `1 − U`.
+    ///
+    /// # Panics
+    ///
+    /// As [`prob_bounds`](Self::prob_bounds).
+    pub fn prob_sdp<T>(&self, level: V, pv: &HashMap<String, Vec<T>>) -> T
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Copy + From<f64>,
+    {
+        self.try_prob_sdp(level, pv).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`prob_sdp`](Self::prob_sdp); see [`try_prob_bounds`](Self::try_prob_bounds).
+    pub fn try_prob_sdp<T>(&self, level: V, pv: &HashMap<String, Vec<T>>) -> Result<T, RelibError>
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Copy + From<f64>,
+    {
+        let mgr = self.mgr_of(&[])?;
+        self.check_probabilities(&mgr.borrow(), pv)?;
+        let family = self.level_family(level);
+        let probs: HashMap<&str, Vec<T>> = self
+            .vars
+            .iter()
+            .filter_map(|(x, states)| {
+                let p = pv.get(x)?;
+                assert!(p.len() >= *states, "not enough probabilities for {}", x);
+                let p = &p[..*states];
+                Some((x.as_str(), if self.reverse { p.iter().rev().copied().collect() } else { p.to_vec() }))
+            })
             .collect();
-        let vectors: Vec<HashMap<String, usize>> = self.extract(&ss).collect();
-        // Keep the extreme elements: maximal for cuts, minimal for paths.
-        vectors
+        let mut dd = mgr.borrow_mut();
+        let mut memo = BddHashMap::default();
+        let u = upset_prob(&mut dd, family.node, &probs, &mut memo);
+        Ok(if self.reverse {
+            T::from(1.0) - u
+        } else {
+            u
+        })
+    }
+
+    /// The family whose upsets (in stored coordinates) make up `φ >= level` (paths) or
+    /// `φ < level` (cuts), under the single label `level`.
+    fn level_family(&self, level: V) -> ZmddNode<V> {
+        let reverse = self.reverse;
+        self.merged(level, |w| if reverse { w < level } else { w >= level })
+    }
+
+    /// `tails[x][d]`: the probability that `x` is at least `d` in stored coordinates.
+    fn tails(&self, pv: &HashMap<String, Vec<f64>>) -> HashMap<&str, Vec<f64>> {
+        self.vars
             .iter()
-            .filter(|x| {
-                !vectors.iter().any(|y| {
-                    y != *x
-                        && self.vars.iter().all(|(n, _)| {
-                            let (a, b) = (y[n], x[n]);
-                            if self.reverse { a >= b } else { a <= b }
-                        })
-                })
+            .filter_map(|(x, states)| {
+                let p = pv.get(x)?;
+                assert!(p.len() >= *states, "not enough probabilities for {}", x);
+                let mut p: Vec<f64> = p[..*states].to_vec();
+                if self.reverse {
+                    p.reverse();
+                }
+                let mut tail = vec![0.0; *states];
+                let mut acc = 0.0;
+                for d in (0..*states).rev() {
+                    acc += p[d];
+                    tail[d] = acc;
+                }
+                Some((x.as_str(), tail))
             })
-            .cloned()
             .collect()
     }
 
     /// Label-wise intersection with another family from the same manager.
     pub fn intersect(&self, other: &ZmddNode<V>) -> ZmddNode<V> {
-        let zmdd = self.parent.upgrade().unwrap();
+        self.try_intersect(other).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`intersect`](Self::intersect): `Err` if the manager has been dropped or `other` belongs to another manager.
+    pub fn try_intersect(&self, other: &ZmddNode<V>) -> Result<ZmddNode<V>, RelibError> {
+        let zmdd = self.mgr_of(&[other])?;
         let result = zmdd.borrow_mut().intersect(self.node, other.node);
-        self.rewrap(&zmdd, result)
+        Ok(self.rewrap(&zmdd, result))
     }
 
     /// Label-wise difference (`self − other`).
     pub fn setdiff(&self, other: &ZmddNode<V>) -> ZmddNode<V> {
-        let zmdd = self.parent.upgrade().unwrap();
+        self.try_setdiff(other).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`setdiff`](Self::setdiff): see [`try_intersect`](Self::try_intersect).
+    pub fn try_setdiff(&self, other: &ZmddNode<V>) -> Result<ZmddNode<V>, RelibError> {
+        let zmdd = self.mgr_of(&[other])?;
         let result = zmdd.borrow_mut().setdiff(self.node, other.node);
+        Ok(self.rewrap(&zmdd, result))
+    }
+
+    /// Label-wise union with another family from the same manager; a vector of both under
+    /// different labels keeps the greater label.
+    pub fn union(&self, other: &ZmddNode<V>) -> ZmddNode<V> {
+        self.try_union(other).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`union`](Self::union): see [`try_intersect`](Self::try_intersect).
+    pub fn try_union(&self, other: &ZmddNode<V>) -> Result<ZmddNode<V>, RelibError> {
+        let zmdd = self.mgr_of(&[other])?;
+        let result = zmdd.borrow_mut().union(self.node, other.node);
+        Ok(self.rewrap(&zmdd, result))
+    }
+
+    /// Label-wise product: the joins of a vector of `self` and one of `other` with the same
+    /// label, each component at the farther of its two states from the baseline (the
+    /// greater state for path families, the lower for cut families).
+    pub fn product(&self, other: &ZmddNode<V>) -> ZmddNode<V> {
+        self.try_product(other).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`product`](Self::product): see [`try_intersect`](Self::try_intersect).
+    pub fn try_product(&self, other: &ZmddNode<V>) -> Result<ZmddNode<V>, RelibError> {
+        let zmdd = self.mgr_of(&[other])?;
+        let result = zmdd.borrow_mut().product(self.node, other.node);
+        Ok(self.rewrap(&zmdd, result))
+    }
+
+    /// The vectors of each label with no other vector of that label below them, in true
+    /// component states.
+    pub fn minimal(&self) -> ZmddNode<V> {
+        if self.reverse {
+            self.maximal_raw()
+        } else {
+            self.minimal_raw()
+        }
+    }
+
+    /// The vectors of each label with no other vector of that label above them, in true
+    /// component states.
+    pub fn maximal(&self) -> ZmddNode<V> {
+        if self.reverse {
+            self.minimal_raw()
+        } else {
+            self.maximal_raw()
+        }
+    }
+
+    /// Fallible [`minimal`](Self::minimal): `Err` if the manager has been dropped.
+    pub fn try_minimal(&self) -> Result<ZmddNode<V>, RelibError> {
+        self.mgr_of(&[])?;
+        Ok(self.minimal())
+    }
+
+    /// Fallible [`maximal`](Self::maximal): `Err` if the manager has been dropped.
+    pub fn try_maximal(&self) -> Result<ZmddNode<V>, RelibError> {
+        self.mgr_of(&[])?;
+        Ok(self.maximal())
+    }
+
+    /// `minimal` in the stored coordinates (levels below max for a cut family).
+    fn minimal_raw(&self) -> ZmddNode<V> {
+        let zmdd = self.get_mgr();
+        let result = zmdd.borrow_mut().minimal(self.node);
         self.rewrap(&zmdd, result)
     }
 
+    fn maximal_raw(&self) -> ZmddNode<V> {
+        let zmdd = self.get_mgr();
+        let result = zmdd.borrow_mut().maximal(self.node);
+        self.rewrap(&zmdd, result)
+    }
+
+    /// The family with every label `v` replaced by `map(v)`; the vectors of a label mapped to
+    /// `None` are dropped.
+    pub fn relabel<F>(&self, map: F) -> ZmddNode<V>
+    where
+        F: Fn(V) -> Option<V>,
+    {
+        self.try_relabel(map).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`relabel`](Self::relabel): `Err` if the manager has been dropped.
+    pub fn try_relabel<F>(&self, map: F) -> Result<ZmddNode<V>, RelibError>
+    where
+        F: Fn(V) -> Option<V>,
+    {
+        let zmdd = self.mgr_of(&[])?;
+        let result = zmdd.borrow_mut().relabel(self.node, &map);
+        Ok(self.rewrap(&zmdd, result))
+    }
+
     /// Number of vectors whose terminal label is in `ss` (the strata selected by `ss`, see
     /// [`extract`](Self::extract) — this counts the baseline member too).
     pub fn count(&self, ss: &HashSet<V>) -> u64 {
-        let mgr = self.parent.upgrade().unwrap();
+        self.try_count(ss).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`count`](Self::count): `Err` if the manager has been dropped.
+    pub fn try_count(&self, ss: &HashSet<V>) -> Result<u64, RelibError> {
+        let mgr = self.mgr_of(&[])?;
         let dd = mgr.borrow();
         let mut cache = BddHashMap::default();
-        zmdd_count(&dd, self.node, ss, &mut cache)
+        Ok(zmdd_count(&dd, self.node, ss, &mut cache))
     }
 
     /// Graphviz source for this family's diagram. Edge labels are the raw edge indices; for
     /// a `reverse` (cut) family `extract` reports `edge_num-1 - d`, but the graph is raw.
     /// The `Undet` terminal (the empty family) and the edges into it are omitted.
     pub fn dot(&self) -> String {
-        let mgr = self.parent.upgrade().unwrap();
-        let dd = mgr.borrow();
-        dd.dot_string(&self.node)
+        self.try_dot().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible [`dot`](Self::dot): `Err` if the manager has been dropped.
+    pub fn try_dot(&self) -> Result<String, RelibError> {
+        let mgr = self.mgr_of(&[])?;
+        let result = mgr.borrow().dot_string(&self.node);
+        Ok(result)
     }
 
     /// Enumerate the vectors whose terminal label is in `ss`.
@@ -331,6 +628,105 @@ impl<V> Drop for ZmddNode<V> {
     }
 }
 
+/// `max_s P(x >= s)` (stored coordinates) over the vectors `s` of the family below `node`,
+/// or `0` for the empty family.
+fn max_member_prob<V>(
+    dd: &ZmddManager<V>,
+    node: NodeId,
+    tails: &HashMap<&str, Vec<f64>>,
+    memo: &mut BddHashMap<NodeId, f64>,
+) -> f64
+where
+    V: MddValue,
+{
+    if let Some(&x) = memo.get(&node) {
+        return x;
+    }
+    let result = match dd.get_node(&node).unwrap() {
+        ZNode::Undet => 0.0,
+        ZNode::Terminal(_) => 1.0,
+        ZNode::NonTerminal(f) => {
+            let label = dd.label(&node).unwrap();
+            let tail = tails.get(label).unwrap_or_else(|| panic!("no probabilities for {}", label));
+            let edges: Vec<NodeId> = f.iter().collect();
+            edges
+                .into_iter()
+                .enumerate()
+                .map(|(d, e)| tail[d] * max_member_prob(dd, e, tails, memo))
+                .fold(0.0, f64::max)
+        }
+    };
+    memo.insert(node, result);
+    result
+}
+
+/// The upper bound `B` of [`ZmddNode::prob_bounds`] on `P(x >= s)` for some vector `s` of
+/// the family below `node` (stored coordinates).
+fn union_bound<V>(
+    dd: &ZmddManager<V>,
+    node: NodeId,
+    tails: &HashMap<&str, Vec<f64>>,
+    memo: &mut BddHashMap<NodeId, f64>,
+) -> f64
+where
+    V: MddValue,
+{
+    if let Some(&x) = memo.get(&node) {
+        return x;
+    }
+    let result = match dd.get_node(&node).unwrap() {
+        ZNode::Undet => 0.0,
+        ZNode::Terminal(_) => 1.0,
+        ZNode::NonTerminal(f) => {
+            let label = dd.label(&node).unwrap();
+            let tail = tails.get(label).unwrap_or_else(|| panic!("no probabilities for {}", label));
+            let edges: Vec<NodeId> = f.iter().collect();
+            let none: f64 = edges
+                .into_iter()
+                .enumerate()
+                .map(|(d, e)| 1.0 - tail[d] * union_bound(dd, e, tails, memo))
+                .product();
+            1.0 - none
+        }
+    };
+    memo.insert(node, result);
+    result
+}
+
+/// `U(node)` of [`ZmddNode::prob_sdp`].
+fn upset_prob<V, T>(
+    dd: &mut ZmddManager<V>,
+    node: NodeId,
+    probs: &HashMap<&str, Vec<T>>,
+    memo: &mut BddHashMap<NodeId, T>,
+) -> T
+where
+    V: MddValue,
+    T: Add<Output = T> + Mul<Output = T> + Copy + From<f64>,
+{
+    if let Some(&x) = memo.get(&node) {
+        return x;
+    }
+    let result = match dd.get_node(&node).unwrap() {
+        ZNode::Undet => T::from(0.0),
+        ZNode::Terminal(_) => T::from(1.0),
+        ZNode::NonTerminal(f) => {
+            let edges: Vec<NodeId> = f.iter().collect();
+            let label = dd.label(&node).unwrap();
+            let p = probs.get(label).unwrap_or_else(|| panic!("no probabilities for {}", label)).clone();
+            let mut below = dd.undet();
+            let mut sum = T::from(0.0);
+            for (s, e) in edges.into_iter().enumerate() {
+                below = dd.union(below, e);
+                sum = sum + p[s] * upset_prob(dd, below, probs, memo);
+            }
+            sum
+        }
+    };
+    memo.insert(node, result);
+    result
+}
+
 fn zmdd_count<V>(
     dd: &ZmddManager<V>,
     node: NodeId,
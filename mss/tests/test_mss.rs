@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use mss::prelude::*;
 
@@ -438,3 +438,1176 @@ fn test_zmdd_dot() {
         assert!(dot.contains(&format!("label=\"{}\"", label)), "missing {}", label);
     }
 }
+
+#[test]
+fn test_prob_batch_matches_prob() {
+    let mut mgr: MddMgr<i32> = MddMgr::new();
+    let x = mgr.defvar("x", 3);
+    let y = mgr.defvar("y", 3);
+    let z = mgr.defvar("z", 2);
+    let one = mgr.value(1);
+    let mut value = x.min(&y).add(&z);
+    let mut boolean = value.ge(&one);
+
+    let params: Vec<HashMap<String, Vec<f64>>> = (0..4)
+        .map(|k| {
+            let a = 0.1 * k as f64;
+            let mut pv = HashMap::new();
+            pv.insert("x".to_string(), vec![0.1 + a, 0.3, 0.6 - a]);
+            pv.insert("y".to_string(), vec![0.2, 0.3 + a, 0.5 - a]);
+            pv.insert("z".to_string(), vec![0.7 - a, 0.3 + a]);
+            pv
+        })
+        .collect();
+
+    for ss in [vec![0], vec![1, 2], vec![3]] {
+        let batch = value.prob_batch(&params, &ss);
+        for (pv, got) in params.iter().zip(batch.iter()) {
+            assert!((value.prob(pv, &ss) - got).abs() < 1e-12);
+        }
+    }
+    let batch = boolean.prob_batch(&params, &[1]);
+    for (pv, got) in params.iter().zip(batch.iter()) {
+        assert!((boolean.prob(pv, &[1]) - got).abs() < 1e-12);
+    }
+
+    // Columnar variant: parameters indexed by level (the defvar order).
+    let cols: Vec<Vec<Vec<f64>>> = params
+        .iter()
+        .map(|pv| vec![pv["x"].clone(), pv["y"].clone(), pv["z"].clone()])
+        .collect();
+    assert_eq!(value.prob_batch_levels(&cols, &[2]), value.prob_batch(&params, &[2]));
+}
+
+#[test]
+fn test_parse_matches_rpn() {
+    let mut vars = HashMap::new();
+    vars.insert("x".to_string(), 3usize);
+    vars.insert("y".to_string(), 4usize);
+    vars.insert("z".to_string(), 2usize);
+    let cases = [
+        ("x + y * 2 - z", "x y 2 * + z -"),
+        ("-x + 3", "0 x - 3 +"),
+        ("min(x, y, z + 1) >= 1 && !(y == 3) || z != 0", "x y min z 1 + min 1 >= y 3 == ! && z 0 != ||"),
+        ("if x < y then x else y / 2", "x y < x y 2 / ?"),
+        ("max(x, y) <= 2", "x y max 2 <="),
+        (
+            "case x { 0 => 0, 1 | 2 => y }",
+            "x 0 == 0 x 1 == x 2 == || y y ? ?",
+        ),
+        ("case x + y { 3 => true, _ => false }", "x y + 3 == True False ?"),
+        ("let m = min(x, y); m * m > z", "x y min save(m) load(m) * z >"),
+    ];
+    for (infix, rpn) in cases {
+        let mut mgr: MddMgr<i64> = MddMgr::new();
+        mgr.defvar("x", 3);
+        mgr.defvar("y", 4);
+        mgr.defvar("z", 2);
+        let f = mgr.parse(infix).unwrap();
+        let g = mgr.rpn(rpn, &vars).unwrap();
+        assert_eq!(f.get_node(), g.get_node(), "{infix}");
+    }
+
+    // Inline declarations: same diagram as with defvar up front.
+    let mut mgr: MddMgr<i32> = MddMgr::new();
+    let f = mgr
+        .parse("var x: 0..2; var \"pump B\": 0..3; x + \"pump B\" // sum of states")
+        .unwrap();
+    assert_eq!(
+        mgr.get_varorder(),
+        vec![("x".to_string(), 3), ("pump B".to_string(), 4)]
+    );
+    let mut pv = HashMap::new();
+    pv.insert("x".to_string(), vec![0.2_f64, 0.3, 0.5]);
+    pv.insert("pump B".to_string(), vec![0.1, 0.2, 0.3, 0.4]);
+    let mut f5 = f.eq(&mgr.value(5));
+    assert!((f5.prob(&pv, &[1]) - 0.5 * 0.4).abs() < 1e-12);
+}
+
+#[test]
+fn test_parse_errors() {
+    let check = |src: &str, span: std::ops::Range<usize>, msg: &str| {
+        let mut mgr: MddMgr<i32> = MddMgr::new();
+        mgr.defvar("x", 3);
+        let Err(RelibError::Parse(err)) = mgr.parse(src) else {
+            panic!("{src}: expected a parse error");
+        };
+        assert_eq!(err.span, span, "{src}: {err}");
+        assert!(err.message.contains(msg), "{src}: {err}");
+    };
+    check("x + w", 4..5, "unknown variable w");
+    check("x + (x > 1)", 4..11, "`+` needs a value, found a boolean");
+    check("x && true", 0..1, "`&&` needs a boolean");
+    check("if x then 1 else 2", 3..4, "condition of `if` needs a boolean");
+    check("if x > 0 then 1 else false", 14..26, "`if` mixes booleans and values");
+    check("avg(x, 1)", 0..3, "unknown function `avg`");
+    check("case x { 0 => 1, 2 => 3 }", 0..25, "does not cover state(s) 1 of x");
+    check("case x + 1 { 0 => 1 }", 0..21, "needs a `_` arm");
+    check("case x { 0 => 1, 0 => 2, _ => 3 }", 17..18, "state 0 appears twice");
+    check("var x: 0..4; x", 4..11, "already declared with states 0..2");
+    check("var y: 1..4; y", 7..8, "states start at 0");
+    check("x + 3000000000", 4..14, "within i32");
+    check("x y", 2..3, "expected an operator or end of input");
+    check("min(x, 1", 8..8, "expected `)`, found end of input");
+}
+
+#[test]
+fn test_rbd_compile() {
+    let mut rbd = Rbd::new();
+    let comps = ["a", "b", "c", "d", "e"].map(|x| Block::component(x, 3));
+    rbd.define("core", Block::bridge(
+        comps[0].clone(), comps[1].clone(), comps[2].clone(), comps[3].clone(), comps[4].clone(),
+    ))
+    .unwrap();
+    rbd.define("vote", Block::kofn(2, comps[..4].to_vec())).unwrap();
+    assert!(rbd.define("vote", Block::component("x", 2)).is_err());
+
+    let mut mss: MssMgr<i32> = MssMgr::new();
+    let check = |mss: &mut MssMgr<i32>, block: &Block, src: &str| {
+        let f = mss.compile_rbd(&rbd, block).unwrap();
+        let g = mss.parse(src).unwrap();
+        assert_eq!(f.get_node(), g.get_node(), "{src}");
+    };
+    check(&mut mss, &Block::sub("core"), "max(min(a, d), min(b, e), min(a, c, e), min(b, c, d))");
+    check(
+        &mut mss,
+        &Block::sub("vote"),
+        "max(min(a, b), min(a, c), min(a, d), min(b, c), min(b, d), min(c, d))",
+    );
+    check(&mut mss, &Block::kofn(1, comps[..3].to_vec()), "max(a, b, c)");
+    check(&mut mss, &Block::kofn(3, comps[..3].to_vec()), "min(a, b, c)");
+    check(
+        &mut mss,
+        &Block::series(vec![
+            Block::sum(vec![Block::sub("vote"), comps[4].clone()]),
+            Block::standby(Block::component("f", 5), vec![Block::component("g", 2)]),
+        ]),
+        "min(max(min(a, b), min(a, c), min(a, d), min(b, c), min(b, d), min(c, d)) + e, max(f, g))",
+    );
+
+    let mut err = |block: Block| mss.compile_rbd(&rbd, &block).unwrap_err().to_string();
+    assert_eq!(err(Block::sub("pump")), "unknown sub-diagram pump");
+    assert!(err(Block::parallel(vec![])).contains("no children"));
+    assert!(err(Block::kofn(4, comps[..3].to_vec())).contains("1 <= k <= 3"));
+    assert!(err(Block::kofn(0, comps[..3].to_vec())).contains("1 <= k <= 3"));
+    rbd.define("loop", Block::series(vec![Block::sub("loop")])).unwrap();
+    assert!(mss.compile_rbd(&rbd, &Block::sub("loop")).unwrap_err().to_string().contains("contains itself"));
+}
+
+#[test]
+fn test_weighted_threshold() {
+    let mut mss: MssMgr<i32> = MssMgr::new();
+    let xs = [mss.defvar("a", 3), mss.defvar("b", 4), mss.defvar("c", 2)];
+    let f = mss.weighted_threshold(&[2, 1, 3], 5, &xs);
+    assert!(f.is_boolean());
+    let g = mss.parse("2 * a + b + 3 * c >= 5").unwrap();
+    assert_eq!(f.get_node(), g.get_node());
+
+    // Two-state nodes with unit weights: at least 2 of 3.
+    let ys = [mss.defvar("y1", 2), mss.defvar("y2", 2), mss.defvar("y3", 2)];
+    let f = mss.weighted_threshold(&[1, 1, 1], 2, &ys);
+    let g = mss.parse("y1 + y2 + y3 >= 2").unwrap();
+    assert_eq!(f.get_node(), g.get_node());
+
+    // Negative weights, and a per-state weight given as a value MDD.
+    let w = mss.parse("case b { 0 => 0, 1 => 5, 2 => 1, 3 => 2 }").unwrap();
+    let f = mss.weighted_threshold(&[-1, 1, 2], 2, &[xs[0].clone(), w, xs[2].clone()]);
+    let g = mss
+        .parse("2 * c - a + case b { 0 => 0, 1 => 5, 2 => 1, 3 => 2 } >= 2")
+        .unwrap();
+    assert_eq!(f.get_node(), g.get_node());
+}
+
+#[test]
+fn test_posterior_and_mpe_bruteforce() {
+    let mut mgr: MddMgr<i32> = MddMgr::new();
+    let f = mgr
+        .parse("var x: 0..2; var y: 0..1; var z: 0..2; case x { 0 => 0, 1 => min(y, z), 2 => z }")
+        .unwrap();
+    let names = ["x", "y", "z"];
+    let ranges = [3, 2, 3];
+    let pv: HashMap<String, Vec<f64>> = HashMap::from([
+        ("x".to_string(), vec![0.2, 0.5, 0.3]),
+        ("y".to_string(), vec![0.4, 0.6]),
+        ("z".to_string(), vec![0.1, 0.3, 0.6]),
+    ]);
+
+    for ss in [vec![0], vec![1, 2]] {
+        let mut total = 0.0;
+        let mut joint: Vec<Vec<f64>> = ranges.iter().map(|&m| vec![0.0; m]).collect();
+        let mut best = (0.0, vec![]);
+        for x in 0..3 {
+            for y in 0..2 {
+                for z in 0..3 {
+                    let s = [x, y, z];
+                    let point: HashMap<String, Vec<f64>> = (0..3)
+                        .map(|i| {
+                            let mut v = vec![0.0; ranges[i]];
+                            v[s[i]] = 1.0;
+                            (names[i].to_string(), v)
+                        })
+                        .collect();
+                    if f.clone().prob(&point, &ss) != 1.0 {
+                        continue;
+                    }
+                    let w: f64 = (0..3).map(|i| pv[names[i]][s[i]]).product();
+                    total += w;
+                    for i in 0..3 {
+                        joint[i][s[i]] += w;
+                    }
+                    if w > best.0 {
+                        best = (w, s.to_vec());
+                    }
+                }
+            }
+        }
+        let post = f.posterior(&pv, &ss);
+        for (i, x) in names.iter().enumerate() {
+            for j in 0..ranges[i] {
+                assert!((post[*x][j] - joint[i][j] / total).abs() < 1e-12, "{x}={j} given {ss:?}");
+            }
+        }
+        let (p, states) = f.mpe(&pv, &ss).unwrap();
+        assert!((p - best.0).abs() < 1e-12);
+        assert_eq!(names.map(|x| states[x]).to_vec(), best.1, "given {ss:?}");
+    }
+    assert!(f.mpe(&pv, &[3]).is_none());
+}
+
+#[test]
+fn test_prob_given_bruteforce() {
+    let mut mgr: MddMgr<i32> = MddMgr::new();
+    let mut f = mgr.parse("var x: 0..2; var y: 0..2; var z: 0..1; if z == 1 then max(x, y) else min(x, y)").unwrap();
+    let pv = HashMap::from([
+        ("x".to_string(), vec![0.2, 0.5, 0.3]),
+        ("y".to_string(), vec![0.1, 0.3, 0.6]),
+        ("z".to_string(), vec![0.4, 0.6_f64]),
+    ]);
+    let value = |x: usize, y: usize, z: usize| if z == 1 { x.max(y) } else { x.min(y) };
+    let evidence = HashMap::from([("x".to_string(), vec![1, 2]), ("z".to_string(), vec![0])]);
+    for ss in [vec![0], vec![1], vec![2], vec![1, 2]] {
+        let (mut joint, mut pe) = (0.0, 0.0);
+        for x in 1..3 {
+            for y in 0..3 {
+                let w = pv["x"][x] * pv["y"][y] * pv["z"][0];
+                pe += w;
+                if ss.contains(&(value(x, y, 0) as i32)) {
+                    joint += w;
+                }
+            }
+        }
+        let (j, c) = f.prob_given(&pv, &ss, &evidence).unwrap();
+        assert!((j - joint).abs() < 1e-12, "{ss:?}");
+        assert!((c - joint / pe).abs() < 1e-12, "{ss:?}");
+        // Repeated states count once.
+        let repeated = HashMap::from([("x".to_string(), vec![2, 1, 2]), ("z".to_string(), vec![0, 0])]);
+        assert_eq!(f.prob_given(&pv, &ss, &repeated).unwrap(), (j, c));
+    }
+    let out_of_range = HashMap::from([("x".to_string(), vec![3])]);
+    assert_eq!(
+        f.prob_given(&pv, &[0], &out_of_range).unwrap_err(),
+        RelibError::Invalid("evidence state 3 of x is out of range 0..3".to_string())
+    );
+    let unknown = HashMap::from([("w".to_string(), vec![0])]);
+    assert_eq!(
+        f.prob_given(&pv, &[0], &unknown).unwrap_err(),
+        RelibError::MissingProbability("w".to_string())
+    );
+}
+
+#[test]
+fn test_prob_interval() {
+    let mut mss: MssMgr<i32> = MssMgr::new();
+    let p = |lo: f64, hi: f64| Interval::new(lo, hi);
+    let pv = HashMap::from([
+        ("x".to_string(), vec![p(0.1, 0.3), p(0.2, 0.5), p(0.3, 0.6)]),
+        ("y".to_string(), vec![p(0.2, 0.4), p(0.6, 0.8)]),
+    ]);
+    // Point distributions within the intervals: the box corners projected onto the simplex.
+    let mut points: Vec<HashMap<String, Vec<f64>>> = Vec::new();
+    for x in [[0.1, 0.3, 0.6], [0.3, 0.2, 0.5], [0.2, 0.5, 0.3], [0.1, 0.5, 0.4], [0.3, 0.4, 0.3]] {
+        for y in [[0.2, 0.8], [0.4, 0.6], [0.3, 0.7]] {
+            points.push(HashMap::from([("x".to_string(), x.to_vec()), ("y".to_string(), y.to_vec())]));
+        }
+    }
+
+    // Coherent, `ss` an upper set: exact bounds attained at the extreme distributions.
+    let phi = mss.parse("var x: 0..2; var y: 0..1; x + y").unwrap();
+    let b = mss.prob_interval(&phi, &pv, &[2, 3]).unwrap();
+    // x = (0.3, 0.4, 0.3), y = (0.4, 0.6) and x = (0.1, 0.3, 0.6), y = (0.2, 0.8).
+    assert!((b.lo - (0.3 + 0.4 * 0.6)).abs() < 1e-12, "{b}");
+    assert!((b.hi - (0.6 + 0.3 * 0.8)).abs() < 1e-12, "{b}");
+    // `ss` not an upper or lower set, and a non-coherent function: sound fallback.
+    let psi = mss.parse("case x { 1 => y, _ => 1 - y }").unwrap();
+    for (f, ss) in [(&phi, &[2][..]), (&psi, &[1])] {
+        let b = mss.prob_interval(f, &pv, ss).unwrap();
+        for pt in points.iter() {
+            let v = f.clone().prob(pt, ss);
+            assert!(b.lo <= v + 1e-12 && v <= b.hi + 1e-12, "{v} outside {b}");
+        }
+    }
+
+    // Intervals that hold no distribution, and a variable without intervals.
+    for (y, sums) in [
+        (vec![p(0.5, 0.6), p(0.6, 0.8)], "Σ lo = 1.1, Σ hi = 1.4"),
+        (vec![p(0.2, 0.3), p(0.5, 0.6)], "Σ lo = 0.7, Σ hi = 0.8999999999999999"),
+    ] {
+        let bad = HashMap::from([("x".to_string(), pv["x"].clone()), ("y".to_string(), y)]);
+        let msg = format!("the state intervals of y hold no distribution: {sums}");
+        assert_eq!(mss.prob_interval(&phi, &bad, &[2]).unwrap_err(), RelibError::Invalid(msg.clone()));
+        assert_eq!(psi.prob_interval(&bad, &[1]).unwrap_err(), RelibError::Invalid(msg));
+    }
+    let partial = HashMap::from([("x".to_string(), pv["x"].clone())]);
+    assert_eq!(
+        psi.prob_interval(&partial, &[1]).unwrap_err(),
+        RelibError::MissingProbability("y".to_string())
+    );
+}
+
+#[test]
+fn test_prob_with_complement() {
+    let mut mgr: MddMgr<i32> = MddMgr::new();
+    let f = mgr.parse("var x: 0..2; var y: 0..2; min(x, y)").unwrap();
+    let pv = HashMap::from([
+        ("x".to_string(), vec![1e-9_f64, 1e-7, 1.0 - 1e-7 - 1e-9]),
+        ("y".to_string(), vec![1e-8, 1e-6, 1.0 - 1e-6 - 1e-8]),
+    ]);
+    let (p, q) = f.prob_with_complement(&pv, &[2]);
+    let direct = f.clone().prob(&pv, &[0, 1]);
+    assert_eq!(q, direct);
+    assert!((p + q - 1.0).abs() < 1e-15);
+
+    // Exact rationals through the multi-state passes.
+    let r = |n: i128, d: i128| Rational::new(n, d);
+    let pv = HashMap::from([
+        ("x".to_string(), vec![r(1, 10), r(2, 10), r(7, 10)]),
+        ("y".to_string(), vec![r(1, 4), r(1, 4), r(1, 2)]),
+    ]);
+    let (p, q) = f.prob_with_complement(&pv, &[2]);
+    assert_eq!(p, r(7, 20));
+    assert_eq!(q, r(13, 20));
+    assert_eq!(f.clone().prob(&pv, &[1, 2]), r(9, 10) * r(3, 4));
+}
+
+#[test]
+fn test_try_variants() {
+    let mut mss: MssMgr<i32> = MssMgr::new();
+    let vars = HashMap::from([("x".to_string(), 3usize), ("y".to_string(), 2usize)]);
+    let f = mss.rpn("x y +", &vars).unwrap();
+    let g = mss.rpn("x 1 >=", &vars).unwrap();
+    assert_eq!(
+        mss.try_rpn("x z +", &vars).unwrap_err(),
+        RelibError::UnknownVariable("z".to_string())
+    );
+    assert_eq!(mss.rpn("x +", &vars).unwrap_err(), "missing operand for +");
+
+    // Operand kinds.
+    assert_eq!(f.try_max(&f).unwrap().get_node(), f.max(&f).get_node());
+    assert!(matches!(f.try_add(&g), Err(RelibError::KindMismatch(_))));
+    assert!(matches!(g.try_and(&f), Err(RelibError::KindMismatch(_))));
+    assert!(matches!(f.try_not(), Err(RelibError::KindMismatch(_))));
+    assert!(matches!(f.try_ite(&f, &f), Err(RelibError::KindMismatch(_))));
+    assert!(matches!(g.try_ite(&f, &g), Err(RelibError::KindMismatch(_))));
+    assert_eq!(g.try_ite(&f, &f).unwrap().get_node(), g.ite(&f, &f).get_node());
+
+    // Probabilities.
+    let mut pv: HashMap<String, Vec<f64>> = HashMap::new();
+    pv.insert("x".to_string(), vec![0.2, 0.3, 0.5]);
+    assert_eq!(
+        f.try_prob(&pv, &[2]).unwrap_err(),
+        RelibError::MissingProbability("y".to_string())
+    );
+    pv.insert("y".to_string(), vec![0.4]);
+    assert!(f.try_bmeas(&pv, &[2]).is_err());
+    pv.insert("y".to_string(), vec![0.4, 0.6]);
+    let mut h = f.clone();
+    assert_eq!(f.try_prob(&pv, &[2]).unwrap(), h.prob(&pv, &[2]));
+
+    // Nodes of another manager, and a node that outlives its manager.
+    let mut other: MddMgr<i32> = MddMgr::new();
+    let z = other.defvar("z", 2);
+    assert_eq!(f.try_add(&z).unwrap_err(), RelibError::ManagerMismatch);
+    drop(other);
+    assert_eq!(z.try_sub(&z).unwrap_err(), RelibError::ManagerDropped);
+    assert_eq!(z.try_prob(&pv, &[0]).unwrap_err(), RelibError::ManagerDropped);
+    assert_eq!(mss.try_minpath(&z).unwrap_err(), RelibError::ManagerDropped);
+    assert!(mss.try_minpath(&g).unwrap().is_some());
+
+    // Evaluations.
+    let short = HashMap::from([("x".to_string(), vec![0.2, 0.3, 0.5])]);
+    let missing = || RelibError::MissingProbability("y".to_string());
+    assert_eq!(f.try_distribution(&pv).unwrap(), f.distribution(&pv));
+    assert_eq!(f.try_distribution(&short).unwrap_err(), missing());
+    assert_eq!(f.try_prob_with_complement(&short, &[2]).unwrap_err(), missing());
+    assert_eq!(f.try_prob_batch(&[pv.clone(), short.clone()], &[2]).unwrap_err(), missing());
+    assert_eq!(f.try_prob_batch(std::slice::from_ref(&pv), &[2]).unwrap(), vec![h.prob(&pv, &[2])]);
+    assert!(matches!(
+        f.try_prob_batch_levels(&[vec![vec![0.2, 0.3, 0.5]]], &[2]),
+        Err(RelibError::MissingProbability(_))
+    ));
+    assert_eq!(f.try_posterior(&short, &[2]).unwrap_err(), missing());
+    assert_eq!(f.try_mpe(&short, &[2]).unwrap_err(), missing());
+    assert_eq!(f.try_importance(&short, &[2]).unwrap_err(), missing());
+    assert_eq!(f.try_size().unwrap(), f.size());
+    assert_eq!(z.try_dot().unwrap_err(), RelibError::ManagerDropped);
+    assert_eq!(z.try_get_children().unwrap_err(), RelibError::ManagerDropped);
+    assert_eq!(z.try_size().unwrap_err(), RelibError::ManagerDropped);
+    assert_eq!(z.try_mdd_count(&HashSet::from([1])).unwrap_err(), RelibError::ManagerDropped);
+    assert_eq!(z.try_check_coherence().unwrap_err(), RelibError::ManagerDropped);
+    assert_eq!(z.try_irrelevant().unwrap_err(), RelibError::ManagerDropped);
+    assert_eq!(z.try_structural_importance().unwrap_err(), RelibError::ManagerDropped);
+
+    // Families of minimal vectors.
+    let paths = mss.minpath(&f.ge(&mss.value(2))).unwrap();
+    assert_eq!(paths.try_labels().unwrap(), paths.labels());
+    assert_eq!(paths.try_prob_sdp(1, &short).unwrap_err(), missing());
+    assert_eq!(paths.try_prob_bounds(1, &short).unwrap_err(), missing());
+    assert_eq!(paths.try_prob_sdp(1, &pv).unwrap(), paths.prob_sdp(1, &pv));
+    let mut other = MssMgr::<i32>::new();
+    let w = other.defvar("w", 2);
+    let foreign = other.minpath(&w.ge(&other.value(1))).unwrap();
+    assert_eq!(paths.try_union(&foreign).unwrap_err(), RelibError::ManagerMismatch);
+    drop(other);
+    assert_eq!(paths.try_intersect(&foreign).unwrap_err(), RelibError::ManagerDropped);
+    assert_eq!(foreign.try_count(&HashSet::from([1])).unwrap_err(), RelibError::ManagerDropped);
+    assert_eq!(foreign.try_minimal().unwrap_err(), RelibError::ManagerDropped);
+    assert_eq!(foreign.try_dot().unwrap_err(), RelibError::ManagerDropped);
+}
+
+/// The model shared by the distribution, importance, DPLD and coherence tests: the
+/// capacities `a` and `b` add up (capped at 3) while `c` is up, and only `a` counts, capped
+/// at 1, while `c` is down.
+const MODEL: &str = "var a: 0..2; var b: 0..3; var c: 0..1; if c == 1 then min(a + b, 3) else min(a, 1)";
+const MODEL_NAMES: [&str; 3] = ["a", "b", "c"];
+const MODEL_SIZES: [usize; 3] = [3, 4, 2];
+
+/// `φ` of [`MODEL`] at the state vector `x`, in [`MODEL_NAMES`] order.
+fn model_phi(x: &[usize]) -> i32 {
+    let (a, b) = (x[0] as i32, x[1] as i32);
+    if x[2] == 1 {
+        (a + b).min(3)
+    } else {
+        a.min(1)
+    }
+}
+
+fn model_pv() -> HashMap<String, Vec<f64>> {
+    HashMap::from([
+        ("a".to_string(), vec![0.2, 0.3, 0.5]),
+        ("b".to_string(), vec![0.1, 0.2, 0.3, 0.4]),
+        ("c".to_string(), vec![0.35, 0.65]),
+    ])
+}
+
+/// Every state vector of components with `sizes` states, the first component varying fastest.
+fn state_vectors(sizes: &[usize]) -> Vec<Vec<usize>> {
+    (0..sizes.iter().product::<usize>())
+        .map(|mut n| {
+            sizes
+                .iter()
+                .map(|&m| {
+                    let s = n % m;
+                    n /= m;
+                    s
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// The probability of the state vector `x` of the components `names`.
+fn vector_prob(pv: &HashMap<String, Vec<f64>>, names: &[&str], x: &[usize]) -> f64 {
+    names.iter().zip(x).map(|(n, &s)| pv[*n][s]).product()
+}
+
+#[test]
+fn test_distribution_matches_prob() {
+    let mut mgr: MddMgr<i32> = MddMgr::new();
+    let mut f = mgr.parse(MODEL).unwrap();
+    let pv = model_pv();
+    let mut expected: BTreeMap<i32, f64> = BTreeMap::new();
+    for x in state_vectors(&MODEL_SIZES) {
+        *expected.entry(model_phi(&x)).or_insert(0.0) += vector_prob(&pv, &MODEL_NAMES, &x);
+    }
+    let dist = f.distribution(&pv);
+    assert_eq!(dist.keys().collect::<Vec<_>>(), expected.keys().collect::<Vec<_>>());
+    let mut mean = 0.0;
+    let mut second = 0.0;
+    for (&v, &p) in expected.iter() {
+        assert!((dist[&v] - p).abs() < 1e-12, "P(f = {})", v);
+        assert!((p - f.prob(&pv, &[v])).abs() < 1e-12, "P(f = {})", v);
+        mean += v as f64 * p;
+        second += (v * v) as f64 * p;
+    }
+    assert!((f.expectation(&pv) - mean).abs() < 1e-12);
+    assert!((f.variance(&pv) - (second - mean * mean)).abs() < 1e-12);
+    for (&v, &p) in f.exceedance(&pv).iter() {
+        let above: f64 = expected.range(v..).map(|(_, &q)| q).sum();
+        assert!((p - above).abs() < 1e-12, "P(f >= {})", v);
+    }
+
+    // A boolean node has the masses of 0 and 1.
+    let mut g = mgr.parse("a + b >= 3").unwrap();
+    let bdist = g.distribution(&pv);
+    assert_eq!(bdist.keys().copied().collect::<Vec<_>>(), vec![0, 1]);
+    assert!((bdist[&1] - g.prob(&pv, &[1])).abs() < 1e-12);
+    assert!((g.expectation(&pv) - bdist[&1]).abs() < 1e-12);
+}
+
+#[test]
+fn test_importance_bruteforce() {
+    let mut mgr: MddMgr<i32> = MddMgr::new();
+    let mut f = mgr.parse(MODEL).unwrap();
+    let ss = [2, 3];
+    let pv = model_pv();
+    let imp = f.importance(&pv, &ss);
+
+    // joint[x][j] = (P(x = j), P(x = j, φ ∈ ss), E[φ; x = j]).
+    let mut joint: Vec<Vec<(f64, f64, f64)>> = MODEL_SIZES.iter().map(|&m| vec![(0.0, 0.0, 0.0); m]).collect();
+    for x in state_vectors(&MODEL_SIZES) {
+        let p = vector_prob(&pv, &MODEL_NAMES, &x);
+        let value = model_phi(&x);
+        let ok = if ss.contains(&value) { 1.0 } else { 0.0 };
+        for (i, &j) in x.iter().enumerate() {
+            let e = &mut joint[i][j];
+            *e = (e.0 + p, e.1 + p * ok, e.2 + p * value as f64);
+        }
+    }
+    let r: f64 = f.prob(&pv, &ss);
+    let u = 1.0 - r;
+    for (i, x) in MODEL_NAMES.iter().enumerate() {
+        let m = &imp[*x];
+        for (j, &(p, pr, pe)) in joint[i].iter().enumerate() {
+            assert!((m.conditional[j] - pr / p).abs() < 1e-12, "{} {}", x, j);
+            assert!((m.performance[j] - pe / p).abs() < 1e-12, "{} {}", x, j);
+        }
+        for k in 1..MODEL_SIZES[i] {
+            let sum = |js: std::ops::Range<usize>| {
+                js.fold((0.0, 0.0), |(p, q), j| (p + joint[i][j].0, q + joint[i][j].0 - joint[i][j].1))
+            };
+            let (p_lo, f_lo) = sum(0..k);
+            let (p_hi, f_hi) = sum(k..MODEL_SIZES[i]);
+            let (u_lo, u_hi) = (f_lo / p_lo, f_hi / p_hi);
+            assert!((m.birnbaum[k - 1] - (u_lo - u_hi)).abs() < 1e-12);
+            assert!((m.criticality[k - 1] - p_lo * (u_lo - u_hi) / u).abs() < 1e-12);
+            assert!((m.diagnostic[k - 1] - f_lo / u).abs() < 1e-12);
+            assert!((m.raw[k - 1] - u_lo / u).abs() < 1e-12);
+            assert!((m.rrw[k - 1] - u / u_hi).abs() < 1e-12);
+        }
+    }
+
+    // For a binary component the threshold Birnbaum is the adjacent-state bmeas.
+    let bm = f.bmeas(&pv, &ss);
+    assert!((imp["c"].birnbaum[0] - bm["c"][0]).abs() < 1e-12);
+
+    // Fussell–Vesely from the minimal cut vectors, the maximal failed vectors.
+    let vectors = state_vectors(&MODEL_SIZES);
+    let failed: Vec<&Vec<usize>> = vectors.iter().filter(|x| !ss.contains(&model_phi(x))).collect();
+    let below = |x: &[usize], y: &[usize]| x.iter().zip(y).all(|(a, b)| a <= b);
+    let cuts: Vec<&Vec<usize>> =
+        failed.iter().copied().filter(|&y| !failed.iter().any(|&z| z != y && below(y, z))).collect();
+    let mut strictly_below_diagnostic = false;
+    for (i, x) in MODEL_NAMES.iter().enumerate() {
+        let m = &imp[*x];
+        assert_eq!(m.fussell_vesely.len(), MODEL_SIZES[i] - 1);
+        for k in 1..MODEL_SIZES[i] {
+            let occurred: f64 = vectors
+                .iter()
+                .filter(|v| cuts.iter().any(|y| y[i] < k && below(v, y)))
+                .map(|v| vector_prob(&pv, &MODEL_NAMES, v))
+                .sum();
+            assert!((m.fussell_vesely[k - 1] - occurred / u).abs() < 1e-12, "{} {}", x, k);
+            assert!(m.fussell_vesely[k - 1] <= m.diagnostic[k - 1] + 1e-12);
+            strictly_below_diagnostic |= m.fussell_vesely[k - 1] < m.diagnostic[k - 1] - 1e-9;
+        }
+    }
+    assert!(strictly_below_diagnostic);
+
+    // A binary fault tree: the top event fails with the cut {a, b} or {c}; c is not in {a, b}.
+    let mut ft: MddMgr<i32> = MddMgr::new();
+    let mut g = ft.parse("var a: 0..1; var b: 0..1; var c: 0..1; (a == 1 || b == 1) && c == 1").unwrap();
+    let q: HashMap<String, Vec<f64>> = HashMap::from([
+        ("a".to_string(), vec![0.1, 0.9]),
+        ("b".to_string(), vec![0.2, 0.8]),
+        ("c".to_string(), vec![0.3, 0.7]),
+    ]);
+    let gi = g.importance(&q, &[1]);
+    let top = 1.0 - g.prob(&q, &[1]);
+    assert!((gi["a"].fussell_vesely[0] - 0.1 * 0.2 / top).abs() < 1e-12);
+    assert!((gi["c"].fussell_vesely[0] - 0.3 / top).abs() < 1e-12);
+}
+
+#[test]
+fn test_dpld_bruteforce() {
+    let mut mgr: MddMgr<i32> = MddMgr::new();
+    let f = mgr.parse(MODEL).unwrap();
+    let pv = model_pv();
+    let vectors = state_vectors(&MODEL_SIZES);
+
+    for (i, x) in MODEL_NAMES.iter().enumerate() {
+        let others: usize = (0..3).filter(|&k| k != i).map(|k| MODEL_SIZES[k]).product();
+        for s in 0..MODEL_SIZES[i] {
+            for r in 0..MODEL_SIZES[i] {
+                for j in 0..4 {
+                    for h in 0..4 {
+                        let mut d = f.dpld(x, s, r, j, h).unwrap();
+                        let (mut count, mut p) = (0, 0.0);
+                        // The vectors of the other components, with x_i = 0 standing for "any".
+                        for v in vectors.iter().filter(|v| v[i] == 0) {
+                            let mut state = v.clone();
+                            state[i] = s;
+                            let before = model_phi(&state);
+                            state[i] = r;
+                            if before == j && model_phi(&state) == h {
+                                count += 1;
+                                p += (0..3).filter(|&k| k != i).map(|k| pv[MODEL_NAMES[k]][state[k]]).product::<f64>();
+                            }
+                        }
+                        assert!((d.structural_importance() - count as f64 / others as f64).abs() < 1e-12);
+                        assert!((d.prob(&pv, &[1]) - p).abs() < 1e-12);
+                        let critical: Vec<_> = d.critical_vectors(x).collect();
+                        assert!(critical.iter().all(|v| !v.contains_key(*x)));
+                        let expanded: usize = critical
+                            .iter()
+                            .map(|v| {
+                                (0..3)
+                                    .filter(|&k| k != i && !v.contains_key(MODEL_NAMES[k]))
+                                    .map(|k| MODEL_SIZES[k])
+                                    .product::<usize>()
+                            })
+                            .sum();
+                        assert_eq!(expanded, count);
+                    }
+                }
+            }
+        }
+    }
+
+    // Integrated: any degradation of the system from state 2 when a goes from 2 to 1.
+    let down = f.dpld_integrated("a", 2, 1, &[2], &[0, 1]).unwrap();
+    let single = f.dpld("a", 2, 1, 2, 1).unwrap().or(&f.dpld("a", 2, 1, 2, 0).unwrap());
+    assert_eq!(down.get_id2(), single.get_id2());
+
+    assert_eq!(f.dpld("d", 0, 1, 0, 1).unwrap_err(), RelibError::UnknownVariable("d".to_string()));
+    assert!(matches!(f.dpld("c", 0, 2, 0, 1), Err(RelibError::Invalid(_))));
+}
+
+#[test]
+fn test_check_coherence_bruteforce() {
+    // The model's components plus an unused `d`.
+    let names = ["a", "b", "c", "d"];
+    let sizes = [3usize, 4, 2, 2];
+    type Phi = fn(&[usize]) -> i32;
+    let exprs: [(&str, Phi); 7] = [
+        ("min(a + b, 3)", |x| (x[0] + x[1]).min(3) as i32),
+        ("if c == 1 then min(a + b, 3) else min(a, 1)", model_phi),
+        ("if c == 1 then b else 3 - b", |x| if x[2] == 1 { x[1] as i32 } else { 3 - x[1] as i32 }),
+        ("if a == 1 then b else 0", |x| if x[0] == 1 { x[1] as i32 } else { 0 }),
+        ("a + b >= 3 && c == 1", |x| (x[0] + x[1] >= 3 && x[2] == 1) as i32),
+        ("a != 1 || b == 0", |x| (x[0] != 1 || x[1] == 0) as i32),
+        ("max(a, c)", |x| x[0].max(x[2]) as i32),
+    ];
+    let vectors = state_vectors(&sizes);
+    for (expr, value) in exprs {
+        let mut mgr: MddMgr<i32> = MddMgr::new();
+        let f = mgr.parse(&format!("var a: 0..2; var b: 0..3; var c: 0..1; var d: 0..1; {}", expr)).unwrap();
+        let eval = |x: &HashMap<String, usize>| value(&names.map(|n| x.get(n).copied().unwrap_or(0)));
+        let step = |v: &[usize], i: usize| -> Option<Vec<usize>> {
+            let mut w = v.to_vec();
+            w[i] += 1;
+            (w[i] < sizes[i]).then_some(w)
+        };
+        let monotone = vectors
+            .iter()
+            .all(|v| (0..4).all(|i| step(v, i).map_or(true, |w| value(v) <= value(&w))));
+        let irrelevant: Vec<String> = (0..4)
+            .filter(|&i| vectors.iter().all(|v| step(v, i).map_or(true, |w| value(v) == value(&w))))
+            .map(|i| names[i].to_string())
+            .collect();
+
+        match f.check_coherence() {
+            Coherence::Coherent => assert!(monotone, "{}", expr),
+            Coherence::Decreasing { var, low, high } => {
+                assert!(!monotone, "{}", expr);
+                assert_eq!(low[&var] + 1, high[&var], "{}", expr);
+                assert!(low.keys().all(|x| *x == var || low[x] == high[x]));
+                assert!(eval(&low) > eval(&high), "{}", expr);
+            }
+        }
+        assert_eq!(f.irrelevant(), irrelevant, "{}", expr);
+    }
+}
+
+#[test]
+fn test_zmdd_union_product_dominance_bruteforce() {
+    use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+    type Family = BTreeMap<Vec<usize>, i32>;
+    let names = ["x", "y", "z"];
+    // Every (dense vector, label) pair of a family, vectors in `names` order.
+    fn members(z: &ZmddNode<i32>, names: &[&str]) -> Family {
+        let mut out = BTreeMap::new();
+        for l in z.labels() {
+            for v in z.extract(&HashSet::from([l])) {
+                let key: Vec<usize> = names.iter().map(|n| v[*n]).collect();
+                assert!(out.insert(key, l).is_none(), "vector under two labels");
+            }
+        }
+        out
+    }
+    let le = |a: &[usize], b: &[usize]| a.iter().zip(b).all(|(x, y)| x <= y);
+    let extreme = |f: &Family, below: bool| -> Family {
+        f.iter()
+            .filter(|(s, r)| {
+                !f.iter().any(|(t, q)| q == *r && t != *s && if below { le(t, s) } else { le(s, t) })
+            })
+            .map(|(s, r)| (s.clone(), *r))
+            .collect()
+    };
+
+    let mut mgr: MssMgr<i32> = MssMgr::new();
+    let x = mgr.defvar("x", 3);
+    let y = mgr.defvar("y", 3);
+    let z = mgr.defvar("z", 3);
+    let f = mgr.minpath(&x.min(&y).max(&z)).unwrap();
+    let g = mgr.minpath(&x.add(&z).min(&mgr.value(2))).unwrap();
+    let h = mgr.minpath(&y.max(&z)).unwrap();
+    let (mf, mg, mh) = (members(&f, &names), members(&g, &names), members(&h, &names));
+
+    for (a, ma, b, mb) in [(&f, &mf, &g, &mg), (&g, &mg, &h, &mh), (&f, &mf, &h, &mh)] {
+        let mut union = ma.clone();
+        for (s, &r) in mb.iter() {
+            let e = union.entry(s.clone()).or_insert(r);
+            *e = (*e).max(r);
+        }
+        assert_eq!(members(&a.union(b), &names), union);
+
+        let mut product: Family = BTreeMap::new();
+        for (s, &r) in ma.iter() {
+            for (t, &q) in mb.iter().filter(|(_, &q)| q == r) {
+                let join: Vec<usize> = s.iter().zip(t).map(|(i, j)| *i.max(j)).collect();
+                let e = product.entry(join).or_insert(q);
+                *e = (*e).max(q);
+            }
+        }
+        let prod = a.product(b);
+        assert_eq!(members(&prod, &names), product);
+        assert_eq!(members(&prod.minimal(), &names), extreme(&product, true));
+        assert_eq!(members(&prod.maximal(), &names), extreme(&product, false));
+    }
+
+    // Collapse all positive strata into one label, then keep the minimal vectors.
+    let merged = f.union(&g).union(&h).relabel(|v| if v >= 1 { Some(1) } else { None });
+    let labels: BTreeSet<i32> = members(&merged, &names).values().copied().collect();
+    assert_eq!(labels, BTreeSet::from([1]));
+    let mut all: Family = BTreeMap::new();
+    for m in [&mf, &mg, &mh] {
+        for (s, &r) in m.iter().filter(|(_, &r)| r >= 1) {
+            all.insert(s.clone(), r);
+        }
+    }
+    let all: Family = all.into_keys().map(|s| (s, 1)).collect();
+    assert_eq!(members(&merged, &names), all);
+    assert_eq!(members(&merged.minimal(), &names), extreme(&all, true));
+}
+
+#[test]
+fn test_zmdd_prob_bounds_sdp() {
+    let mut mgr: MssMgr<i32> = MssMgr::new();
+    let x = mgr.defvar("x", 3);
+    let y = mgr.defvar("y", 3);
+    let z = mgr.defvar("z", 3);
+    let w = mgr.defvar("w", 2);
+    let pv = HashMap::from([
+        ("x".to_string(), vec![0.2, 0.3, 0.5]),
+        ("y".to_string(), vec![0.1, 0.6, 0.3]),
+        ("z".to_string(), vec![0.4, 0.4, 0.2]),
+        ("w".to_string(), vec![0.25, 0.75]),
+    ]);
+    let systems = [
+        x.min(&y).max(&z),
+        x.add(&z).min(&mgr.value(2)),
+        x.min(&y).max(&z.min(&w.mul(&mgr.value(2)))),
+    ];
+    for mut f in systems {
+        let paths = mgr.minpath(&f).unwrap();
+        let cuts = mgr.mincut(&f).unwrap();
+        for v in 1..=2 {
+            let ss: Vec<i32> = (v..=4).collect();
+            let exact: f64 = f.prob(&pv, &ss);
+            for family in [&paths, &cuts] {
+                assert!((family.prob_sdp(v, &pv) - exact).abs() < 1e-12);
+                let b = family.prob_bounds(v, &pv);
+                assert!(b.lo <= exact + 1e-12 && exact <= b.hi + 1e-12, "{:?} {}", b, exact);
+            }
+            // The max terms, from the vectors themselves.
+            let member = |vector: &HashMap<String, usize>, above: bool| -> f64 {
+                pv.iter()
+                    .map(|(x, p)| {
+                        let s = vector[x];
+                        let range = if above { s..p.len() } else { 0..s + 1 };
+                        p[range].iter().sum::<f64>()
+                    })
+                    .product()
+            };
+            let max_path = paths.extract_level(v).iter().map(|x| member(x, true)).fold(0.0, f64::max);
+            assert!((paths.prob_bounds(v, &pv).lo - max_path).abs() < 1e-12);
+            let max_cut = cuts.extract_level(v - 1).iter().map(|x| member(x, false)).fold(0.0, f64::max);
+            assert!((cuts.prob_bounds(v, &pv).hi - (1.0 - max_cut)).abs() < 1e-12);
+            // The product terms are no looser than the products over the vectors.
+            let none_path: f64 = paths.extract_level(v).iter().map(|x| 1.0 - member(x, true)).product();
+            assert!(paths.prob_bounds(v, &pv).hi <= 1.0 - none_path + 1e-12);
+            let none_cut: f64 = cuts.extract_level(v - 1).iter().map(|x| 1.0 - member(x, false)).product();
+            assert!(cuts.prob_bounds(v, &pv).lo >= none_cut - 1e-12);
+        }
+    }
+
+    // With y certain to be at 2, P(min(x, y) >= 2) = 0.1 is both bounds, and 1 − 0.9 rounds
+    // below 0.1.
+    let mut mgr: MssMgr<i32> = MssMgr::new();
+    let x = mgr.defvar("x", 3);
+    let y = mgr.defvar("y", 3);
+    let f = x.min(&y);
+    let pv = HashMap::from([("x".to_string(), vec![0.5, 0.4, 0.1]), ("y".to_string(), vec![0.0, 0.0, 1.0])]);
+    for family in [mgr.minpath(&f).unwrap(), mgr.mincut(&f).unwrap()] {
+        let b = family.prob_bounds(2, &pv);
+        assert!(b.lo <= b.hi && (b.lo - 0.1).abs() < 1e-12 && (b.hi - 0.1).abs() < 1e-12, "{:?}", b);
+    }
+}
+
+/// Brute-force check of `FlowNetwork::max_flow` / `demand` on every state vector, against
+/// augmenting paths on the capacities of that vector.
+fn check_flow(net: &FlowNetwork<i32>, s: usize, t: usize) {
+    fn max_flow(n: usize, arcs: &[(usize, usize, i32)], s: usize, t: usize) -> i32 {
+        let mut res = vec![vec![0; n]; n];
+        for &(u, v, c) in arcs {
+            res[u][v] += c;
+        }
+        let mut flow = 0;
+        loop {
+            let mut prev = vec![usize::MAX; n];
+            prev[s] = s;
+            let mut queue = std::collections::VecDeque::from([s]);
+            while let Some(u) = queue.pop_front() {
+                for v in 0..n {
+                    if prev[v] == usize::MAX && res[u][v] > 0 {
+                        prev[v] = u;
+                        queue.push_back(v);
+                    }
+                }
+            }
+            if prev[t] == usize::MAX {
+                return flow;
+            }
+            let mut push = i32::MAX;
+            let mut v = t;
+            while v != s {
+                push = push.min(res[prev[v]][v]);
+                v = prev[v];
+            }
+            let mut v = t;
+            while v != s {
+                res[prev[v]][v] -= push;
+                res[v][prev[v]] += push;
+                v = prev[v];
+            }
+            flow += push;
+        }
+    }
+
+    let mut mgr: MddMgr<i32> = MddMgr::new();
+    let mut f = net.max_flow(&mut mgr, s, t).unwrap();
+    let mut ok = net.demand(&mut mgr, s, t, 2).unwrap();
+    let vars = mgr.get_varorder();
+    let mut state = vec![0usize; vars.len()];
+    loop {
+        let pv: HashMap<String, Vec<f64>> = vars
+            .iter()
+            .zip(state.iter())
+            .map(|((x, m), &j)| {
+                let mut p = vec![0.0; *m];
+                p[j] = 1.0;
+                (x.clone(), p)
+            })
+            .collect();
+        let mut arcs = Vec::new();
+        for e in net.edges() {
+            let c = match &e.var {
+                Some(x) => e.capacity[state[vars.iter().position(|(y, _)| y == x).unwrap()]],
+                None => e.capacity[0],
+            };
+            arcs.push((e.from, e.to, c));
+            if !e.directed {
+                arcs.push((e.to, e.from, c));
+            }
+        }
+        let expected = max_flow(net.vertex_count(), &arcs, s, t);
+        assert_eq!(f.prob(&pv, &[expected]), 1.0, "state {state:?}");
+        assert_eq!(ok.prob(&pv, &[i32::from(expected >= 2)]), 1.0, "state {state:?}");
+        let Some(i) = (0..state.len()).find(|&i| state[i] + 1 < vars[i].1) else {
+            break;
+        };
+        state[i] += 1;
+        state[..i].iter_mut().for_each(|j| *j = 0);
+    }
+}
+
+#[test]
+fn test_flow_network_bruteforce() {
+    // Bridge with a directed middle arc, a fixed-capacity edge and a variable shared by two
+    // edges.
+    let mut bridge = FlowNetwork::new(4);
+    bridge.add_edge(0, 1, Some("a"), &[0, 1, 3]).unwrap();
+    bridge.add_edge(0, 2, Some("b"), &[0, 2]).unwrap();
+    bridge.add_arc(1, 2, Some("c"), &[0, 1, 2]).unwrap();
+    bridge.add_edge(1, 3, None, &[1]).unwrap();
+    bridge.add_edge(2, 3, Some("b"), &[0, 3]).unwrap();
+    check_flow(&bridge, 0, 3);
+    check_flow(&bridge, 3, 0);
+    check_flow(&bridge, 1, 2);
+
+    // Directed 2x3 grid with an arc pointing back towards the source, and an unreachable
+    // vertex 6.
+    let mut grid = FlowNetwork::new(7);
+    for (k, (u, v)) in [(0, 1), (1, 2), (3, 4), (4, 5), (0, 3), (1, 4), (5, 2), (4, 1)].into_iter().enumerate() {
+        let capacity: &[i32] = if k % 2 == 0 { &[0, 1, 2] } else { &[0, 2] };
+        grid.add_arc(u, v, Some(&format!("g{k}")), capacity).unwrap();
+    }
+    grid.add_arc(6, 2, Some("h"), &[0, 5]).unwrap();
+    check_flow(&grid, 0, 2);
+    check_flow(&grid, 2, 0);
+    check_flow(&grid, 3, 2);
+
+    let mut mss: MssMgr<i32> = MssMgr::new();
+    let f = mss.max_flow(&grid, 0, 2).unwrap();
+    assert!(mss.minpath(&f).is_some());
+    assert_eq!(mss.max_flow(&grid, 2, 0).unwrap().value(), Some(0));
+    assert!(mss.max_flow(&grid, 0, 0).is_err());
+    assert!(mss.max_flow(&grid, 0, 7).is_err());
+    assert!(grid.add_edge(0, 7, None, &[1]).is_err());
+    assert!(grid.add_edge(0, 1, None, &[1, 2]).is_err());
+    let mut bad = FlowNetwork::new(2);
+    bad.add_edge(0, 1, Some("g0"), &[0, 1]).unwrap();
+    assert!(mss.max_flow(&bad, 0, 1).is_err());
+}
+
+#[test]
+fn test_kofn_sum_builders() {
+    let mut mgr: MddMgr<i32> = MddMgr::new();
+    let states = [3, 4, 3, 2];
+    let names = ["a", "b", "c", "d"];
+    let xs: Vec<MddNode<i32>> = names.iter().zip(states).map(|(x, m)| mgr.defvar(x, m)).collect();
+    // Performance levels through a case table: b delivers 0, 2, 3 or 5 units.
+    let perf = mgr.parse("case b { 0 => 0, 1 => 2, 2 => 3, 3 => 5 }").unwrap();
+    let loads = [xs[0].clone(), perf.clone(), xs[2].clone(), mgr.value(1).sub(&xs[3])];
+
+    // sum without a cap is the plain addition; with a cap the clamped one.
+    let plain = loads[1..].iter().fold(loads[0].clone(), |acc, x| acc.add(x));
+    assert_eq!(mgr.sum(&loads, None).get_node(), plain.get_node());
+    for cap in [0, 3, 6, 20] {
+        let capped = plain.min(&mgr.value(cap));
+        assert_eq!(mgr.sum(&loads, Some(cap)).get_node(), capped.get_node(), "cap {cap}");
+    }
+
+    let mut kofn: Vec<(usize, i32, MddNode<i32>)> = Vec::new();
+    for k in 0..=5 {
+        for level in 0..=3 {
+            kofn.push((k, level, mgr.kofn(k, &xs, level)));
+        }
+    }
+    let ks_list: [&[usize]; 5] = [&[2, 2, 2], &[1, 2, 4], &[3, 1], &[0, 5, 2], &[1, 0, 9]];
+    let mut gk: Vec<MddNode<i32>> = ks_list.iter().map(|ks| mgr.generalized_kofn(ks, &xs)).collect();
+    // k = 0 and k > n are decided before any condition is built.
+    let mut fresh: MddMgr<i32> = MddMgr::new();
+    let ys: Vec<MddNode<i32>> = ["p", "q"].iter().map(|x| fresh.defvar(x, 3)).collect();
+    let live = fresh.live_node_count();
+    assert_eq!(fresh.kofn(0, &ys, 1).get_node(), fresh.boolean(true).get_node());
+    assert_eq!(fresh.kofn(3, &ys, 1).get_node(), fresh.boolean(false).get_node());
+    assert_eq!(fresh.generalized_kofn(&[3, 3], &ys).get_node(), fresh.value(0).get_node());
+    assert_eq!(fresh.live_node_count(), live);
+
+    let mut state = vec![0usize; names.len()];
+    loop {
+        let pv: HashMap<String, Vec<f64>> = names
+            .iter()
+            .zip(states)
+            .zip(state.iter())
+            .map(|((x, m), &j)| {
+                let mut p = vec![0.0; m];
+                p[j] = 1.0;
+                (x.to_string(), p)
+            })
+            .collect();
+        let at_least = |l: usize| state.iter().filter(|&&s| s >= l).count();
+        for (k, level, f) in kofn.iter_mut() {
+            let expected = at_least(*level as usize) >= *k;
+            assert_eq!(f.prob(&pv, &[i32::from(expected)]), 1.0, "k {k} level {level} state {state:?}");
+        }
+        for (ks, g) in ks_list.iter().zip(gk.iter_mut()) {
+            let expected = (1..=ks.len()).filter(|&l| at_least(l) >= ks[l - 1]).max().unwrap_or(0);
+            assert_eq!(g.prob(&pv, &[expected as i32]), 1.0, "ks {ks:?} state {state:?}");
+        }
+        let Some(i) = (0..state.len()).find(|&i| state[i] + 1 < states[i]) else {
+            break;
+        };
+        state[i] += 1;
+        state[..i].iter_mut().for_each(|j| *j = 0);
+    }
+
+    // With one k for every level, the generalized system is the k-th largest state.
+    let rbd = Rbd::new();
+    let blocks: Vec<Block> = names.iter().zip(states).map(|(x, m)| Block::component(x, m)).collect();
+    let kth = rbd.compile(&mut mgr, &Block::kofn(2, blocks)).unwrap();
+    assert_eq!(gk[0].get_node(), kth.get_node());
+}
+
+#[test]
+fn test_ugf_cross_check() {
+    use mss::ugf;
+
+    let states = [("a", 3), ("b", 3), ("c", 2), ("d", 4), ("e", 3), ("f", 2), ("g", 3)];
+    let pv: HashMap<String, Vec<f64>> = states
+        .iter()
+        .enumerate()
+        .map(|(i, &(x, m))| {
+            let w: Vec<f64> = (0..m).map(|j| (1 + i + 2 * j) as f64).collect();
+            let total: f64 = w.iter().sum();
+            (x.to_string(), w.into_iter().map(|p| p / total).collect())
+        })
+        .collect();
+    let comp = |x: &str| Block::component(x, states.iter().find(|s| s.0 == x).unwrap().1);
+
+    let mut rbd = Rbd::new();
+    rbd.define("pipes", Block::sum(vec![comp("a"), comp("b"), comp("c")])).unwrap();
+    rbd.define("vote", Block::kofn(2, vec![comp("e"), comp("f"), comp("g")])).unwrap();
+    let models = [
+        Block::series(vec![Block::sub("pipes"), comp("d")]),
+        Block::parallel(vec![Block::sub("pipes"), Block::sub("vote")]),
+        Block::standby(Block::series(vec![comp("d"), Block::sub("vote")]), vec![Block::sub("pipes")]),
+        Block::kofn(1, vec![comp("a"), comp("b")]),
+        Block::kofn(3, vec![Block::sub("pipes"), comp("d"), comp("e"), comp("g")]),
+    ];
+    for block in models.iter() {
+        let mut mgr: MddMgr<i32> = MddMgr::new();
+        let worst = ugf::cross_check(&mut mgr, &rbd, block, &pv, 1e-12).unwrap();
+        assert!(worst < 1e-12);
+        let u: Ugf<i32, f64> = ugf::from_block(&rbd, block, &pv).unwrap();
+        let mut f = rbd.compile(&mut mgr, block).unwrap();
+        assert!((u.expectation() - f.expectation(&pv)).abs() < 1e-12);
+        assert!((u.prob(&[1, 2, 2]) - f.prob(&pv, &[1, 2])).abs() < 1e-12);
+    }
+
+    // A custom operator against the same function on the MDD.
+    let mut mgr: MddMgr<i32> = MddMgr::new();
+    let (a, d) = (mgr.defvar("a", 3), mgr.defvar("d", 4));
+    let ua = Ugf::component(&[0, 1, 2], &pv["a"]).unwrap();
+    let ud = Ugf::component(&[0, 1, 2, 3], &pv["d"]).unwrap();
+    let u = ua.compose(&ud, |x, y| x * y - y).map(|v| v.max(0));
+    let f = a.mul(&d).sub(&d).max(&mgr.value(0));
+    for (v, p) in f.distribution(&pv) {
+        assert!((u.terms()[&v] - p).abs() < 1e-12);
+    }
+
+    // Models outside the reach of UGF.
+    let err = |block: Block| ugf::from_block::<i32, f64>(&rbd, &block, &pv).unwrap_err().to_string();
+    assert!(err(Block::series(vec![comp("a"), comp("a")])).contains("more than once"));
+    assert!(err(Block::parallel(vec![Block::sub("pipes"), Block::sub("pipes")])).contains("more than once"));
+    assert!(err(Block::bridge(comp("a"), comp("b"), comp("c"), comp("d"), comp("e"))).contains("bridge"));
+    assert!(err(Block::component("z", 2)).contains("no probabilities for z"));
+    assert!(err(Block::sub("none")).contains("unknown"));
+}
+
+#[test]
+fn test_ctmc_uniformization() {
+    // Two states: fails at rate l, is repaired at rate m, starts working.
+    let (l, m) = (0.3, 1.7);
+    let two = Ctmc::new(vec![vec![-m, m], vec![l, -l]], vec![0.0, 1.0]).unwrap();
+    for t in [0.0, 0.01, 0.5, 3.0, 40.0, 5000.0] {
+        let up = m / (l + m) + l / (l + m) * (-(l + m) * t).exp();
+        let p = two.transient(t);
+        assert!((p[1] - up).abs() < 1e-12, "t {t}: {} vs {up}", p[1]);
+        assert!((p[0] + p[1] - 1.0).abs() < 1e-12);
+    }
+
+    // Pure degradation 2 -> 1 -> 0 at rates a and b.
+    let (a, b) = (0.4, 1.5);
+    let wear = Ctmc::new(
+        vec![vec![0.0, 0.0, 0.0], vec![b, -b, 0.0], vec![0.0, a, -a]],
+        vec![0.0, 0.0, 1.0],
+    )
+    .unwrap();
+    for t in [0.2, 1.0, 7.5] {
+        let p = wear.transient(t);
+        let p1 = a / (b - a) * ((-a * t).exp() - (-b * t).exp());
+        assert!((p[2] - (-a * t).exp()).abs() < 1e-12);
+        assert!((p[1] - p1).abs() < 1e-12);
+    }
+    // The absorbing state is the unique stationary distribution.
+    let pi = wear.steady_state().unwrap();
+    assert!((pi[0] - 1.0).abs() < 1e-12 && pi[1].abs() < 1e-12);
+
+    // Birth-death chain: π_i ∝ Π up/down rates.
+    let q = vec![vec![-2.0, 2.0, 0.0], vec![1.0, -4.0, 3.0], vec![0.0, 5.0, -5.0]];
+    let bd = Ctmc::new(q, vec![1.0, 0.0, 0.0]).unwrap();
+    let w = [1.0, 2.0, 2.0 * 3.0 / 5.0];
+    let total: f64 = w.iter().sum();
+    let pi = bd.steady_state().unwrap();
+    let late = bd.transient(200.0);
+    for i in 0..3 {
+        assert!((pi[i] - w[i] / total).abs() < 1e-12);
+        assert!((late[i] - pi[i]).abs() < 1e-10);
+    }
+
+    // Two closed classes: no unique stationary distribution.
+    let split = Ctmc::new(vec![vec![0.0, 0.0], vec![0.0, 0.0]], vec![0.5, 0.5]).unwrap();
+    assert!(split.steady_state().is_err());
+    assert_eq!(split.transient(3.0), vec![0.5, 0.5]);
+
+    assert!(Ctmc::new(vec![], vec![]).is_err());
+    assert!(Ctmc::new(vec![vec![-1.0, 1.0]], vec![1.0]).is_err());
+    assert!(Ctmc::new(vec![vec![-1.0, 1.0], vec![-1.0, 1.0]], vec![1.0, 0.0]).is_err());
+    assert!(Ctmc::new(vec![vec![-1.0, 1.0], vec![1.0, 0.0]], vec![1.0, 0.0]).is_err());
+    assert!(Ctmc::new(vec![vec![-1.0, 1.0], vec![1.0, -1.0]], vec![0.7, 0.7]).is_err());
+
+    // Availability of a series system of two bound components and a fixed one.
+    let mut mgr: MddMgr<i32> = MddMgr::new();
+    let x = mgr.defvar_ctmc("x", two.clone()).unwrap();
+    let y = mgr.defvar_ctmc("y", bd.clone()).unwrap();
+    let z = mgr.defvar("z", 2);
+    assert!(mgr.defvar_ctmc("z", wear.clone()).is_err());
+    assert_eq!(mgr.ctmc("x"), Some(&two));
+    let f = x.min(&y).min(&z.mul(&mgr.value(2)));
+    let pv = HashMap::from([("z".to_string(), vec![0.1, 0.9])]);
+    let times = [0.0, 0.3, 2.0, 300.0];
+    let curve = mgr.availability(&f, &[1, 2], &times, &pv).unwrap();
+    for (&t, &got) in times.iter().zip(curve.iter()) {
+        let (px, py) = (two.transient(t), bd.transient(t));
+        let expected = px[1] * (py[1] + py[2]) * 0.9;
+        assert!((got - expected).abs() < 1e-12, "t {t}");
+    }
+    let steady = mgr.steady_availability(&f, &[1, 2], &pv).unwrap();
+    let expected = m / (l + m) * (w[1] + w[2]) / total * 0.9;
+    assert!((steady - expected).abs() < 1e-12);
+    assert!((curve[3] - steady).abs() < 1e-10);
+
+    // Chains of other variables are not solved: this one has no unique steady state.
+    mgr.defvar_ctmc("idle", Ctmc::new(vec![vec![0.0, 0.0], vec![0.0, 0.0]], vec![1.0, 0.0]).unwrap())
+        .unwrap();
+    assert!(mgr.ctmc_steady_probabilities().is_err());
+    assert_eq!(mgr.steady_availability(&f, &[1, 2], &pv).unwrap(), steady);
+    assert_eq!(mgr.availability(&f, &[1, 2], &times, &pv).unwrap(), curve);
+
+    // A bound variable's chain is not silently overridden by pv.
+    let mut shadowed = pv.clone();
+    shadowed.insert("x".to_string(), vec![0.5, 0.5]);
+    assert_eq!(
+        mgr.availability(&f, &[1, 2], &times, &shadowed).unwrap_err().to_string(),
+        "x is bound to a CTMC and also has probabilities in pv"
+    );
+    assert!(mgr.steady_availability(&f, &[1, 2], &shadowed).is_err());
+    // A variable neither bound to a chain nor in pv.
+    let unbound: HashMap<String, Vec<f64>> = HashMap::new();
+    let missing = RelibError::MissingProbability("z".to_string());
+    assert_eq!(mgr.availability(&f, &[1, 2], &times, &unbound).unwrap_err(), missing);
+    assert_eq!(mgr.steady_availability(&f, &[1, 2], &unbound).unwrap_err(), missing);
+}